@@ -0,0 +1,109 @@
+//! Capture files with the raw telemetry packets of a session.
+//!
+//! A capture starts with [`MAGIC`] followed by one record per packet: the time since the start of
+//! the recording in microseconds as a little endian `u64`, the length of the packet as a little
+//! endian `u32` and the packet itself.
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Identifies a capture file and its format version.
+pub const MAGIC: &[u8; 8] = b"F1LSCAP1";
+
+/// Writes received packets to a capture file.
+#[derive(Debug)]
+pub struct CaptureWriter {
+    file: File,
+    started: Instant,
+}
+
+impl CaptureWriter {
+    /// Creates (or truncates) the file at `path` and starts the clock of the recording.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(MAGIC)?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// Appends a packet received now.
+    ///
+    /// Every record is written at once, so the capture stays readable when the program is killed.
+    pub fn write(&mut self, packet: &[u8]) -> io::Result<()> {
+        let timestamp = self.started.elapsed();
+        let mut record = Vec::with_capacity(12 + packet.len());
+        record.extend_from_slice(&(timestamp.as_micros() as u64).to_le_bytes());
+        record.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        record.extend_from_slice(packet);
+        self.file.write_all(&record)
+    }
+}
+
+/// A packet read back from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Time since the start of the recording
+    pub timestamp: Duration,
+    pub data: Vec<u8>,
+}
+
+/// Reads the packets of a capture file in the order they were received.
+#[derive(Debug)]
+pub struct CaptureReader {
+    reader: BufReader<File>,
+}
+
+impl CaptureReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an f1_light_sync capture",
+            ));
+        }
+        Ok(Self { reader })
+    }
+
+    /// The next packet, `None` at the end of the capture.
+    ///
+    /// A record cut off by killing the recording counts as the end.
+    pub fn read(&mut self) -> io::Result<Option<CapturedPacket>> {
+        let mut header = [0; 12];
+        match self.reader.read_exact(&mut header) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+
+        let (timestamp, length) = header.split_at(8);
+        let timestamp = u64::from_le_bytes(timestamp.try_into().unwrap());
+        let length = u32::from_le_bytes(length.try_into().unwrap());
+        let mut data = vec![0; length as usize];
+        match self.reader.read_exact(&mut data) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+
+        Ok(Some(CapturedPacket {
+            timestamp: Duration::from_micros(timestamp),
+            data,
+        }))
+    }
+}
+
+impl Iterator for CaptureReader {
+    type Item = io::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+/// A color written as `[r, g, b]` in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub struct Rgb(pub [u8; 3]);
+
+impl Rgb {
+    pub const OFF: Rgb = Rgb([0, 0, 0]);
+
+    /// Temperatures in °C at the ends of the [`temperature`](Self::temperature) colors.
+    pub const COLD: i8 = 15;
+    pub const HOT: i8 = 50;
+
+    /// The color used for a flag when the config does not specify one.
+    pub fn default_for(flag: Option<Flag>) -> Self {
+        Rgb(match flag {
+            None => return Self::OFF,
+            Some(Flag::Global(GlobalFlag::Vsc | GlobalFlag::VscEnding)) => [255, 100, 0],
+            Some(Flag::Global(GlobalFlag::Sc | GlobalFlag::ScEnding)) => [255, 180, 0],
+            Some(Flag::Global(GlobalFlag::Red))
+            | Some(Flag::StartLights(_))
+            | Some(Flag::PositionLost)
+            | Some(Flag::PitStop) => [255, 0, 0],
+            Some(Flag::Global(GlobalFlag::FormationLap)) | Some(Flag::PositionGained) => {
+                [0, 255, 0]
+            }
+            Some(Flag::Local(LocalFlag::Green)) => [0, 255, 0],
+            Some(Flag::Local(LocalFlag::Yellow)) | Some(Flag::Local(LocalFlag::DoubleYellow)) => {
+                [255, 220, 0]
+            }
+            Some(Flag::Local(LocalFlag::Blue)) => [0, 0, 255],
+            Some(Flag::Penalty { .. }) | Some(Flag::Finish) | Some(Flag::BlackAndWhite) => {
+                [255, 255, 255]
+            }
+            Some(Flag::PitLimiter) => [0, 200, 255],
+            Some(Flag::Countdown(_)) => [255, 120, 0],
+            Some(Flag::PitLane) => [0, 120, 255],
+            Some(Flag::PitThisLap) => [255, 0, 200],
+            Some(Flag::FastestLap) => [160, 0, 255],
+            Some(Flag::Damage) => [255, 60, 0],
+            Some(Flag::Collision { red: false }) => [255, 255, 255],
+            Some(Flag::Collision { red: true }) => [255, 0, 0],
+            Some(Flag::Ambient(temperature)) => return Self::temperature(temperature),
+            Some(Flag::Motion(percent)) => return Self::motion(percent),
+            // Lights cannot show black, a dim white stands out from the off state
+            Some(Flag::Black) => [60, 60, 60],
+        })
+    }
+
+    /// Blue at [`COLD`](Self::COLD) through green and yellow to red at [`HOT`](Self::HOT), dimmed
+    /// to stay in the background.
+    pub fn temperature(temperature: i8) -> Self {
+        let warmth = f32::from(temperature.clamp(Self::COLD, Self::HOT) - Self::COLD)
+            / f32::from(Self::HOT - Self::COLD);
+        // Around the color wheel from blue (240°) to red (0°), 60° at a time
+        let hue = (1.0 - warmth) * 4.0;
+        let rise = |x: f32| (x.clamp(0.0, 1.0) * 128.0) as u8;
+        Rgb([rise(2.0 - hue), rise(hue.min(4.0 - hue)), rise(hue - 2.0)])
+    }
+
+    /// Cyan as bright as `percent`, never quite off so the lights stay on when standing still.
+    pub fn motion(percent: u8) -> Self {
+        let brightness = 0.1 + 0.9 * f32::from(percent.min(100)) / 100.0;
+        Rgb([0, (120.0 * brightness) as u8, (255.0 * brightness) as u8])
+    }
+
+    pub fn r(self) -> u8 {
+        self.0[0]
+    }
+
+    pub fn g(self) -> u8 {
+        self.0[1]
+    }
+
+    pub fn b(self) -> u8 {
+        self.0[2]
+    }
+}
@@ -0,0 +1,1006 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::value::SeqAccessDeserializer;
+
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::flag::PenaltyKind;
+use crate::flag::SessionKind;
+use crate::game::AmbientTemperature;
+use crate::game::GapTarget;
+use crate::game::MotionSource;
+use crate::manager::BLACK_AND_WHITE_SHOW_TIME;
+use crate::manager::GREEN_FLASH_TIME;
+use crate::manager::Priority;
+use crate::output::blink::Pattern;
+use crate::output::chroma::ChromaConfig;
+use crate::output::mqtt::MqttConfig;
+use crate::output::openrgb::OpenRgbConfig;
+use crate::output::sacn::SacnConfig;
+use crate::output::serial::SerialConfig;
+use crate::output::simhub::Format;
+use crate::output::websocket::WebSocketConfig;
+use crate::output::wled::WledConfig;
+#[cfg(all(feature = "ws2812", target_os = "linux"))]
+use crate::output::ws2812::Ws2812Config;
+
+/// Contents of the TOML config file passed with `--config`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Where the flags are sent to, either a single `[output]` or a list of `[[output]]`
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+    /// Outputs that show only some of the flags, each zone on its own
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub zone: Vec<ZoneConfig>,
+    /// Outputs that show the player's DRS instead of the flags, like a second WLED segment
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub drs: Vec<OutputConfig>,
+    pub rev_lights: Option<RevLightsConfig>,
+    pub rpm: Option<RpmConfig>,
+    /// Outputs that show the ERS deploy mode and battery instead of the flags
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub ers: Vec<OutputConfig>,
+    /// Outputs that show the tyre compound of the player in its color
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub tyre: Vec<OutputConfig>,
+    /// Outputs that show the gear, as a number or a color for every gear
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub gear: Vec<OutputConfig>,
+    pub tyre_warning: Option<TyreWarningConfig>,
+    pub temperature_warning: Option<TemperatureWarningConfig>,
+    pub low_fuel: Option<LowFuelConfig>,
+    /// Strips with a light for every car in the color of its team, in race order
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub position_board: Vec<OutputConfig>,
+    pub timing_tower: Option<TimingTowerConfig>,
+    pub rain: Option<RainConfig>,
+    pub lap_delta: Option<LapDeltaConfig>,
+    pub gap: Option<GapConfig>,
+    /// Outputs that fill up as the race or session goes on
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub progress: Vec<OutputConfig>,
+    /// Lights around the track that show the yellow marshal zones they cover
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub marshal_post: Vec<MarshalPostConfig>,
+    pub ambient: Option<AmbientConfig>,
+    pub countdown: Option<CountdownConfig>,
+    pub fastest_lap: Option<FastestLapConfig>,
+    pub damage: Option<DamageConfig>,
+    pub collision: Option<CollisionConfig>,
+    pub position: Option<PositionConfig>,
+    pub pit: Option<PitConfig>,
+    pub finish_animation: Option<FinishAnimationConfig>,
+    /// Order in which the flags take precedence, the ones left out go after it in the default order
+    pub priority: Option<Vec<Priority>>,
+    pub penalty: Option<PenaltyConfig>,
+    pub black_and_white: Option<BlackAndWhiteConfig>,
+    pub green_flash: Option<GreenFlashConfig>,
+    pub keepalive: Option<KeepaliveConfig>,
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Codes of every UDP and serial flag output, the `codes` of an output go over these
+    #[serde(default)]
+    pub codes: FlagTable<String>,
+    pub second_player: Option<SecondPlayerConfig>,
+    pub http: Option<HttpConfig>,
+    /// Tables that replace the ones above during one type of session
+    #[serde(default)]
+    pub profile: ProfileTables,
+    /// This config with the tables of each profile in place, filled in by [`Config::load`]
+    #[serde(skip)]
+    pub profiles: BTreeMap<SessionKind, Config>,
+}
+
+/// Outputs that only show some of the flags, `[[zone]]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneConfig {
+    /// What the zone shows, in the order they take precedence. The outputs are off while none of
+    /// them is out
+    pub flags: Vec<Priority>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+/// Outputs that show the rev lights instead of the flags, `[rev_lights]` in the config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevLightsConfig {
+    /// RPM where the lights start and where they are full for every gear, starting at first gear.
+    /// Gears left out follow the rev lights of the game
+    #[serde(default)]
+    pub gears: Vec<[u16; 2]>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+/// Outputs that show the engine speed from idle to the limiter, `[rpm]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpmConfig {
+    /// Percentage from idle to the limiter where the lights start to flash
+    #[serde(default = "default_redline")]
+    pub redline: u8,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_redline() -> u8 {
+    95
+}
+
+/// Outputs that warn when the tyres wear out or overheat, `[tyre_warning]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TyreWarningConfig {
+    /// Wear in percent where the tyres turn amber and where they turn red
+    #[serde(default = "default_tyre_warning_wear")]
+    pub wear: [f32; 2],
+    /// Surface temperature in degrees Celsius where the tyres turn amber and where they turn red
+    #[serde(default = "default_tyre_warning_temperature")]
+    pub temperature: [u8; 2],
+    /// Give every corner a light of its own instead of showing the worst tyre
+    #[serde(default)]
+    pub per_corner: bool,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_tyre_warning_wear() -> [f32; 2] {
+    [50.0, 70.0]
+}
+
+fn default_tyre_warning_temperature() -> [u8; 2] {
+    [110, 125]
+}
+
+/// Outputs that warn when the engine or the brakes overheat, `[temperature_warning]` in the
+/// config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemperatureWarningConfig {
+    /// Degrees Celsius where the engine turns amber and where it turns red
+    #[serde(default = "default_engine_temperatures")]
+    pub engine: [u16; 2],
+    /// Degrees where the hottest brake turns amber and red
+    #[serde(default = "default_brake_temperatures")]
+    pub brakes: [u16; 2],
+    /// Degrees below a threshold the temperature has to drop to before the warning goes back
+    #[serde(default = "default_temperature_hysteresis")]
+    pub hysteresis: u16,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_engine_temperatures() -> [u16; 2] {
+    [120, 130]
+}
+
+fn default_brake_temperatures() -> [u16; 2] {
+    [950, 1100]
+}
+
+fn default_temperature_hysteresis() -> u16 {
+    10
+}
+
+/// Outputs that warn when the fuel runs low, `[low_fuel]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LowFuelConfig {
+    /// Warn once the fuel left, in laps as shown on the MFD, drops below this
+    #[serde(default = "default_low_fuel_laps")]
+    pub laps: f32,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_low_fuel_laps() -> f32 {
+    1.0
+}
+
+/// A light for the marshal zones of part of the track, `[[marshal_post]]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarshalPostConfig {
+    /// Distance around the lap where the part of the track starts, in meters
+    pub from: f32,
+    /// Distance where it ends, before `from` when it goes across the line
+    pub to: f32,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+/// Colors the lights by temperature while no flag is out, `[ambient]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmbientConfig {
+    #[serde(default)]
+    pub temperature: AmbientTemperature,
+    /// Makes the lights brighter with the speed or G force instead, while no flag is out
+    pub motion: Option<MotionSource>,
+    /// Speed in km/h at full brightness
+    #[serde(default = "default_top_speed")]
+    pub top_speed: f32,
+    /// Longitudinal G force at full brightness
+    #[serde(default = "default_max_g_force")]
+    pub max_g_force: f32,
+    /// Steps the brightness goes up in, each one sent as it is reached
+    #[serde(default = "default_motion_steps")]
+    pub steps: u8,
+}
+
+impl Default for AmbientConfig {
+    fn default() -> Self {
+        Self {
+            temperature: AmbientTemperature::default(),
+            motion: None,
+            top_speed: default_top_speed(),
+            max_g_force: default_max_g_force(),
+            steps: default_motion_steps(),
+        }
+    }
+}
+
+fn default_top_speed() -> f32 {
+    320.0
+}
+
+fn default_max_g_force() -> f32 {
+    4.0
+}
+
+fn default_motion_steps() -> u8 {
+    20
+}
+
+/// Counts the formation lap down before the start lights, `[countdown]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CountdownConfig {
+    /// Steps the countdown goes down in, like a light going off for every step
+    #[serde(default = "default_countdown_steps")]
+    pub steps: u8,
+}
+
+fn default_countdown_steps() -> u8 {
+    10
+}
+
+/// Strips with a light for every car by its gap to the car ahead, `[timing_tower]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimingTowerConfig {
+    /// Gap to the car ahead in seconds below which a car lights green
+    #[serde(default = "default_timing_tower_battle")]
+    pub battle: f32,
+    /// Gap below which it lights yellow
+    #[serde(default = "default_timing_tower_close")]
+    pub close: f32,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_timing_tower_battle() -> f32 {
+    1.0
+}
+
+fn default_timing_tower_close() -> f32 {
+    3.0
+}
+
+/// Outputs that warn when rain is coming, `[rain]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RainConfig {
+    /// How far ahead in the forecast to look for rain
+    #[serde(default = "default_rain_minutes")]
+    pub minutes: u8,
+    /// Chance of rain in the forecast that counts as rain
+    #[serde(default = "default_rain_percentage")]
+    pub percentage: u8,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_rain_minutes() -> u8 {
+    5
+}
+
+fn default_rain_percentage() -> u8 {
+    50
+}
+
+/// Outputs that show the player's lap against their best lap, `[lap_delta]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LapDeltaConfig {
+    /// Updates a second at most, the game sends the lap data a lot more often
+    #[serde(default = "default_lap_delta_rate")]
+    pub rate: f32,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_lap_delta_rate() -> f32 {
+    2.0
+}
+
+/// Outputs that show the player's gap to the leader or the car ahead, `[gap]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GapConfig {
+    #[serde(default)]
+    pub to: GapTarget,
+    /// Gap in seconds that fills the bar and turns the lights red
+    #[serde(default = "default_gap_full")]
+    pub full: f32,
+    /// Updates a second at most
+    #[serde(default = "default_lap_delta_rate")]
+    pub rate: f32,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+fn default_gap_full() -> f32 {
+    10.0
+}
+
+/// Flashing purple when the fastest lap is set, `[fastest_lap]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FastestLapConfig {
+    /// How long the fastest lap is shown for
+    #[serde(default = "default_fastest_lap_seconds")]
+    pub seconds: f64,
+    /// Ignore fastest laps of the other drivers
+    #[serde(default)]
+    pub only_player: bool,
+}
+
+fn default_fastest_lap_seconds() -> f64 {
+    2.0
+}
+
+/// Flashing when the player's car gets damaged all of a sudden, `[damage]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DamageConfig {
+    /// How long the damage is shown for
+    #[serde(default = "default_damage_seconds")]
+    pub seconds: f64,
+    /// Percent of damage to a tyre or the front wing that has to come at once
+    #[serde(default = "default_damage_threshold")]
+    pub threshold: u8,
+}
+
+fn default_damage_seconds() -> f64 {
+    3.0
+}
+
+fn default_damage_threshold() -> u8 {
+    20
+}
+
+/// A strobe when the player crashes or hits a car, `[collision]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollisionConfig {
+    /// How long the strobe lasts
+    #[serde(default = "default_collision_seconds")]
+    pub seconds: f64,
+    /// Seconds between the white and the red
+    #[serde(default = "default_collision_interval")]
+    pub interval: f64,
+    /// Braking G force that counts as a crash, on top of the collisions the game reports
+    #[serde(default = "default_collision_g_force")]
+    pub g_force: f32,
+}
+
+fn default_collision_seconds() -> f64 {
+    1.5
+}
+
+fn default_collision_interval() -> f64 {
+    0.1
+}
+
+fn default_collision_g_force() -> f32 {
+    8.0
+}
+
+/// How the warnings of the player are shown, `[black_and_white]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlackAndWhiteConfig {
+    /// How long the black and white flag is shown for
+    #[serde(default = "default_black_and_white_seconds")]
+    pub seconds: f64,
+}
+
+fn default_black_and_white_seconds() -> f64 {
+    BLACK_AND_WHITE_SHOW_TIME.as_secs_f64()
+}
+
+/// Green after the start and incidents, `[green_flash]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GreenFlashConfig {
+    /// How long green is shown for, 0 to go straight to no flag
+    #[serde(default = "default_green_flash_seconds")]
+    pub seconds: f64,
+}
+
+fn default_green_flash_seconds() -> f64 {
+    GREEN_FLASH_TIME.as_secs_f64()
+}
+
+/// Sends the flag again every so often while it does not change, `[keepalive]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeepaliveConfig {
+    /// Seconds between sends of the same flag
+    #[serde(default = "default_keepalive_seconds")]
+    pub seconds: f64,
+}
+
+fn default_keepalive_seconds() -> f64 {
+    5.0
+}
+
+/// Holds back messages to outputs that would get too many, `[rate_limit]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Most messages every output gets in a second
+    #[serde(default = "default_rate_limit_per_second")]
+    pub per_second: f64,
+}
+
+fn default_rate_limit_per_second() -> f64 {
+    10.0
+}
+
+/// Flashes when the player gains or loses places, `[position]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PositionConfig {
+    /// How long a position change is shown for
+    #[serde(default = "default_position_seconds")]
+    pub seconds: f64,
+}
+
+fn default_position_seconds() -> f64 {
+    1.0
+}
+
+/// Lights for the pit lane and the pit stop, `[pit]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PitConfig {
+    /// Also show while driving through the pit lane, not only while stopped in the box
+    #[serde(default = "default_pit_lane")]
+    pub lane: bool,
+}
+
+fn default_pit_lane() -> bool {
+    true
+}
+
+/// Flags shown in turn when the race finishes, `[finish_animation]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FinishAnimationConfig {
+    /// Names of the two flags to alternate, like the black and white of the chequered flag
+    #[serde(default = "default_finish_animation_flags")]
+    pub flags: [String; 2],
+    /// How long the flags alternate before settling on the finish
+    #[serde(default = "default_finish_animation_seconds")]
+    pub seconds: f64,
+    /// Seconds each flag is shown for
+    #[serde(default = "default_finish_animation_interval")]
+    pub interval: f64,
+}
+
+fn default_finish_animation_flags() -> [String; 2] {
+    [String::from("finish"), String::from("off")]
+}
+
+fn default_finish_animation_seconds() -> f64 {
+    5.0
+}
+
+fn default_finish_animation_interval() -> f64 {
+    0.5
+}
+
+/// Which penalties are shown, `[penalty]` in the config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PenaltyConfig {
+    /// Kinds of penalties that leave the lights as they are
+    #[serde(default)]
+    pub ignore: Vec<PenaltyKind>,
+}
+
+/// Outputs that show the flags of the second player in F1 split-screen, `[second_player]` in the
+/// config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecondPlayerConfig {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub output: Vec<OutputConfig>,
+}
+
+/// The built-in web dashboard and control API, `[http]` in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Address (with port) the server listens on
+    #[serde(default = "default_http_listen")]
+    pub listen: String,
+    /// Whether the API can force flags and set the pit reminder, or only show the state
+    #[serde(default = "default_http_control")]
+    pub control: bool,
+    /// Origin of pages that may use the API as well, like `"*"` for the Stream Deck plugin
+    #[serde(default)]
+    pub allow_origin: Option<String>,
+}
+
+fn default_http_listen() -> String {
+    String::from("0.0.0.0:8080")
+}
+
+fn default_http_control() -> bool {
+    true
+}
+
+/// `[profile.practice]`, `[profile.quali]`, `[profile.race]` and `[profile.tt]`, any table of the
+/// config but the [fixed ones](FIXED_TABLES).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileTables {
+    pub practice: Option<toml::Table>,
+    /// Qualifying and sprint shootouts
+    pub quali: Option<toml::Table>,
+    pub race: Option<toml::Table>,
+    /// Time trial
+    pub tt: Option<toml::Table>,
+}
+
+/// Tables the program cannot swap during a session. Besides the server and the second player these
+/// are the tables the packets are read with, since the inputs keep their parser on a switch
+const FIXED_TABLES: [&str; 19] = [
+    "http",
+    "second_player",
+    "profile",
+    "rev_lights",
+    "rpm",
+    "tyre_warning",
+    "temperature_warning",
+    "low_fuel",
+    "rain",
+    "lap_delta",
+    "gap",
+    "progress",
+    "damage",
+    "collision",
+    "countdown",
+    "position_board",
+    "timing_tower",
+    "marshal_post",
+    "ambient",
+];
+
+impl Config {
+    /// Reads and parses the config file at `path`, with the [`profiles`](Config::profiles).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn parse(contents: &str) -> io::Result<Self> {
+        let mut config: Config =
+            toml::from_str(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut base: toml::Table =
+            toml::from_str(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        base.remove("profile");
+
+        let tables = config.profile.clone();
+        let profiles = [
+            (SessionKind::Practice, tables.practice),
+            (SessionKind::Qualifying, tables.quali),
+            (SessionKind::Race, tables.race),
+            (SessionKind::TimeTrial, tables.tt),
+        ];
+        for (kind, table) in profiles {
+            let Some(table) = table else {
+                continue;
+            };
+            if let Some(key) = FIXED_TABLES.iter().find(|&&key| table.contains_key(key)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("`{key}` cannot be set in [profile.{}]", kind.name()),
+                ));
+            }
+            let mut merged = base.clone();
+            merged.extend(table);
+            let profile = merged.try_into().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("in [profile.{}]: {e}", kind.name()),
+                )
+            })?;
+            config.profiles.insert(kind, profile);
+        }
+        Ok(config)
+    }
+}
+
+/// Accepts either a single table or an array of tables.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct OneOrMany<T>(std::marker::PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> de::Visitor<'de> for OneOrMany<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a table or an array of tables")
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            T::deserialize(MapAccessDeserializer::new(map)).map(|value| vec![value])
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
+        }
+    }
+
+    deserializer.deserialize_any(OneOrMany(std::marker::PhantomData))
+}
+
+/// A single output, selected with `type = "..."`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum OutputConfig {
+    /// The plain enum strings over UDP
+    Udp {
+        /// Destination IP with port
+        destination: String,
+        /// Codes sent instead of the enum strings
+        #[serde(default)]
+        codes: FlagTable<String>,
+        /// `simhub` sends SimHub's flag properties, `json` a JSON object and `binary` a two byte
+        /// frame instead of the codes
+        #[serde(default)]
+        format: Format,
+        /// Sent instead of just the code, with the placeholders of the flag filled in
+        #[serde(default)]
+        template: Option<String>,
+        /// Flags that blink by sending the off code and the flag in turn
+        #[serde(default)]
+        patterns: FlagTable<Pattern>,
+    },
+    Wled(WledConfig),
+    Mqtt(MqttConfig),
+    Sacn(SacnConfig),
+    Serial(SerialConfig),
+    #[serde(rename = "openrgb")]
+    OpenRgb(OpenRgbConfig),
+    Chroma(ChromaConfig),
+    #[serde(rename = "websocket")]
+    WebSocket(WebSocketConfig),
+    #[cfg(all(feature = "ws2812", target_os = "linux"))]
+    Ws2812(Ws2812Config),
+}
+
+impl OutputConfig {
+    /// The output with the codes it leaves out taken from `codes`, for the outputs that send codes.
+    pub fn with_default_codes(self, codes: &FlagTable<String>) -> Self {
+        match self {
+            OutputConfig::Udp {
+                destination,
+                codes: own,
+                format,
+                template,
+                patterns,
+            } => OutputConfig::Udp {
+                destination,
+                codes: own.or(codes),
+                format,
+                template,
+                patterns,
+            },
+            OutputConfig::Serial(mut config) => {
+                config.codes = config.codes.or(codes);
+                OutputConfig::Serial(config)
+            }
+            config => config,
+        }
+    }
+}
+
+impl fmt::Display for OutputConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputConfig::Udp { destination, .. } => write!(f, "{destination}"),
+            OutputConfig::Wled(config) => write!(f, "WLED at {}", config.host),
+            OutputConfig::Mqtt(config) => {
+                write!(f, "MQTT broker at {}:{}", config.host, config.port)
+            }
+            OutputConfig::Sacn(config) => write!(f, "sACN universe {}", config.universe),
+            OutputConfig::Serial(config) => {
+                write!(f, "{} at {} baud", config.port, config.baud_rate)
+            }
+            OutputConfig::OpenRgb(config) => {
+                write!(f, "OpenRGB server at {}:{}", config.host, config.port)
+            }
+            OutputConfig::Chroma(config) => write!(f, "Razer Chroma at {}", config.url),
+            OutputConfig::WebSocket(config) => write!(f, "WebSocket server on {}", config.listen),
+            #[cfg(all(feature = "ws2812", target_os = "linux"))]
+            OutputConfig::Ws2812(config) => {
+                write!(f, "{} WS2812 leds on {}", config.leds, config.device)
+            }
+        }
+    }
+}
+
+/// A value per flag, any flag left out falls back to a default chosen by the user of the table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlagTable<T> {
+    pub green: Option<T>,
+    pub yellow: Option<T>,
+    /// Falls back to `yellow`
+    pub double_yellow: Option<T>,
+    pub blue: Option<T>,
+    pub vsc: Option<T>,
+    /// Falls back to `vsc`
+    pub vsc_ending: Option<T>,
+    pub sc: Option<T>,
+    /// Falls back to `sc`
+    pub sc_ending: Option<T>,
+    pub red: Option<T>,
+    pub formation_lap: Option<T>,
+    pub penalty: Option<T>,
+    /// Falls back to `penalty`
+    pub drive_through: Option<T>,
+    /// Falls back to `penalty`
+    pub stop_go: Option<T>,
+    pub finish: Option<T>,
+    pub pit_limiter: Option<T>,
+    pub fastest_lap: Option<T>,
+    pub damage: Option<T>,
+    /// Used for the white and the red of the strobe alike
+    pub collision: Option<T>,
+    pub black: Option<T>,
+    pub black_and_white: Option<T>,
+    pub countdown: Option<T>,
+    pub start_lights: Option<T>,
+    /// A single value instead of following the temperature
+    pub ambient: Option<T>,
+    /// A single value instead of following the speed or G force
+    pub motion: Option<T>,
+    pub position_gained: Option<T>,
+    pub position_lost: Option<T>,
+    pub pit_lane: Option<T>,
+    pub pit_stop: Option<T>,
+    pub pit_this_lap: Option<T>,
+    /// Used when no flag is shown
+    pub off: Option<T>,
+}
+
+impl<T> Default for FlagTable<T> {
+    fn default() -> Self {
+        Self {
+            green: None,
+            yellow: None,
+            double_yellow: None,
+            blue: None,
+            vsc: None,
+            vsc_ending: None,
+            sc: None,
+            sc_ending: None,
+            red: None,
+            formation_lap: None,
+            penalty: None,
+            drive_through: None,
+            stop_go: None,
+            finish: None,
+            pit_limiter: None,
+            fastest_lap: None,
+            damage: None,
+            collision: None,
+            black: None,
+            black_and_white: None,
+            countdown: None,
+            start_lights: None,
+            ambient: None,
+            motion: None,
+            position_gained: None,
+            position_lost: None,
+            pit_lane: None,
+            pit_stop: None,
+            pit_this_lap: None,
+            off: None,
+        }
+    }
+}
+
+impl<T: Clone> FlagTable<T> {
+    /// This table with the flags left out taken from `fallback`.
+    pub fn or(self, fallback: &FlagTable<T>) -> Self {
+        Self {
+            green: self.green.or_else(|| fallback.green.clone()),
+            yellow: self.yellow.or_else(|| fallback.yellow.clone()),
+            double_yellow: self
+                .double_yellow
+                .or_else(|| fallback.double_yellow.clone()),
+            blue: self.blue.or_else(|| fallback.blue.clone()),
+            vsc: self.vsc.or_else(|| fallback.vsc.clone()),
+            vsc_ending: self.vsc_ending.or_else(|| fallback.vsc_ending.clone()),
+            sc: self.sc.or_else(|| fallback.sc.clone()),
+            sc_ending: self.sc_ending.or_else(|| fallback.sc_ending.clone()),
+            red: self.red.or_else(|| fallback.red.clone()),
+            formation_lap: self
+                .formation_lap
+                .or_else(|| fallback.formation_lap.clone()),
+            penalty: self.penalty.or_else(|| fallback.penalty.clone()),
+            drive_through: self
+                .drive_through
+                .or_else(|| fallback.drive_through.clone()),
+            stop_go: self.stop_go.or_else(|| fallback.stop_go.clone()),
+            finish: self.finish.or_else(|| fallback.finish.clone()),
+            pit_limiter: self.pit_limiter.or_else(|| fallback.pit_limiter.clone()),
+            fastest_lap: self.fastest_lap.or_else(|| fallback.fastest_lap.clone()),
+            damage: self.damage.or_else(|| fallback.damage.clone()),
+            collision: self.collision.or_else(|| fallback.collision.clone()),
+            black: self.black.or_else(|| fallback.black.clone()),
+            black_and_white: self
+                .black_and_white
+                .or_else(|| fallback.black_and_white.clone()),
+            countdown: self.countdown.or_else(|| fallback.countdown.clone()),
+            start_lights: self.start_lights.or_else(|| fallback.start_lights.clone()),
+            ambient: self.ambient.or_else(|| fallback.ambient.clone()),
+            motion: self.motion.or_else(|| fallback.motion.clone()),
+            position_gained: self
+                .position_gained
+                .or_else(|| fallback.position_gained.clone()),
+            position_lost: self
+                .position_lost
+                .or_else(|| fallback.position_lost.clone()),
+            pit_lane: self.pit_lane.or_else(|| fallback.pit_lane.clone()),
+            pit_stop: self.pit_stop.or_else(|| fallback.pit_stop.clone()),
+            pit_this_lap: self.pit_this_lap.or_else(|| fallback.pit_this_lap.clone()),
+            off: self.off.or_else(|| fallback.off.clone()),
+        }
+    }
+}
+
+impl<T> FlagTable<T> {
+    /// The configured value for `flag`, `None` meaning no flag is shown.
+    pub fn get(&self, flag: Option<Flag>) -> Option<&T> {
+        match flag {
+            None => self.off.as_ref(),
+            Some(Flag::Global(GlobalFlag::Vsc)) => self.vsc.as_ref(),
+            Some(Flag::Global(GlobalFlag::VscEnding)) => {
+                self.vsc_ending.as_ref().or(self.vsc.as_ref())
+            }
+            Some(Flag::Global(GlobalFlag::Sc)) => self.sc.as_ref(),
+            Some(Flag::Global(GlobalFlag::ScEnding)) => {
+                self.sc_ending.as_ref().or(self.sc.as_ref())
+            }
+            Some(Flag::Global(GlobalFlag::Red)) => self.red.as_ref(),
+            Some(Flag::Global(GlobalFlag::FormationLap)) => self.formation_lap.as_ref(),
+            Some(Flag::Local(LocalFlag::Green)) => self.green.as_ref(),
+            Some(Flag::Local(LocalFlag::Yellow)) => self.yellow.as_ref(),
+            Some(Flag::Local(LocalFlag::DoubleYellow)) => {
+                self.double_yellow.as_ref().or(self.yellow.as_ref())
+            }
+            Some(Flag::Local(LocalFlag::Blue)) => self.blue.as_ref(),
+            Some(Flag::Penalty {
+                kind: PenaltyKind::DriveThrough,
+                ..
+            }) => self.drive_through.as_ref().or(self.penalty.as_ref()),
+            Some(Flag::Penalty {
+                kind: PenaltyKind::StopGo,
+                ..
+            }) => self.stop_go.as_ref().or(self.penalty.as_ref()),
+            Some(Flag::Penalty { .. }) => self.penalty.as_ref(),
+            Some(Flag::Finish) => self.finish.as_ref(),
+            Some(Flag::PitLimiter) => self.pit_limiter.as_ref(),
+            Some(Flag::FastestLap) => self.fastest_lap.as_ref(),
+            Some(Flag::Damage) => self.damage.as_ref(),
+            Some(Flag::Collision { .. }) => self.collision.as_ref(),
+            Some(Flag::Black) => self.black.as_ref(),
+            Some(Flag::BlackAndWhite) => self.black_and_white.as_ref(),
+            Some(Flag::Countdown(_)) => self.countdown.as_ref(),
+            Some(Flag::StartLights(_)) => self.start_lights.as_ref(),
+            Some(Flag::Ambient(_)) => self.ambient.as_ref(),
+            Some(Flag::Motion(_)) => self.motion.as_ref(),
+            Some(Flag::PositionGained) => self.position_gained.as_ref(),
+            Some(Flag::PositionLost) => self.position_lost.as_ref(),
+            Some(Flag::PitLane) => self.pit_lane.as_ref(),
+            Some(Flag::PitStop) => self.pit_stop.as_ref(),
+            Some(Flag::PitThisLap) => self.pit_this_lap.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_codes_go_over_the_shared_ones() {
+        let config: Config = toml::from_str(
+            r#"
+            [codes]
+            sc = "4"
+            vsc = "5"
+
+            [[output]]
+            type = "udp"
+            destination = "127.0.0.1:1234"
+            [output.codes]
+            sc = "SC"
+
+            [[output]]
+            type = "wled"
+            host = "127.0.0.1"
+            "#,
+        )
+        .unwrap();
+        let outputs: Vec<OutputConfig> = config
+            .output
+            .into_iter()
+            .map(|output| output.with_default_codes(&config.codes))
+            .collect();
+        let OutputConfig::Udp { codes, .. } = &outputs[0] else {
+            panic!("the first output should be UDP");
+        };
+        assert_eq!(codes.get(Some(Flag::Global(GlobalFlag::Sc))).unwrap(), "SC");
+        assert_eq!(codes.get(Some(Flag::Global(GlobalFlag::Vsc))).unwrap(), "5");
+        // Falls back within the table of the output first
+        assert_eq!(
+            codes.get(Some(Flag::Global(GlobalFlag::ScEnding))).unwrap(),
+            "SC"
+        );
+        assert!(codes.get(None).is_none());
+        assert!(matches!(outputs[1], OutputConfig::Wled(_)));
+    }
+
+    #[test]
+    fn profiles_cannot_change_how_the_packets_are_read() {
+        let config = Config::parse(
+            r#"
+            [profile.race]
+            green_flash = { seconds = 2 }
+            "#,
+        )
+        .unwrap();
+        assert!(config.profiles[&SessionKind::Race].green_flash.is_some());
+
+        let error = Config::parse(
+            r#"
+            [profile.race]
+            rain = { minutes = 10 }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "`rain` cannot be set in [profile.race]");
+    }
+}
@@ -0,0 +1,68 @@
+//! Running unattended, like on a Raspberry Pi next to the rig.
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A file with the id of this process, removed again when dropped.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes the id of this process to `path`, replacing what was there.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Detaches from the terminal and continues in the background, the process that called it exits.
+///
+/// Has to be called before any threads are started, so before the Tokio runtime. Standard input
+/// and output go to `/dev/null` afterwards, so log to a file. The working directory stays the same.
+#[cfg(unix)]
+pub fn detach() -> io::Result<()> {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+
+    fork()?;
+    // SAFETY: no requirements, only fails when already leading a process group
+    if unsafe { libc::setsid() } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // Not leading the new session, so it can never get a terminal again
+    fork()?;
+
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: both are open file descriptors
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Continues in a child process, the parent exits.
+#[cfg(unix)]
+fn fork() -> io::Result<()> {
+    // SAFETY: there is only one thread, so the child gets all of the program
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        _ => std::process::exit(0),
+    }
+}
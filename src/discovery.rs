@@ -0,0 +1,115 @@
+//! Finding light controllers on the network through mDNS.
+use std::fmt;
+use std::io;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use mdns_sd::ServiceDaemon;
+use mdns_sd::ServiceEvent;
+
+use crate::config::OutputConfig;
+use crate::output::wled::WledConfig;
+
+const WLED_SERVICE: &str = "_wled._tcp.local.";
+const ESPHOME_SERVICE: &str = "_esphomelib._tcp.local.";
+
+/// Port of the ESPHome `udp` component when it is not configured otherwise.
+pub const ESPHOME_UDP_PORT: u16 = 18511;
+
+/// The kind of firmware a discovered device runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Wled,
+    Esphome,
+}
+
+/// A device announced on the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub kind: DeviceKind,
+    pub name: String,
+    pub address: Ipv4Addr,
+    /// Port of the announced service, the HTTP API for WLED and the native API for ESPHome
+    pub port: u16,
+}
+
+impl Device {
+    /// An output sending the flags to this device.
+    ///
+    /// ESPHome devices get the plain UDP enum strings on `esphome_port`, the mDNS record only
+    /// knows the port of the native API.
+    pub fn output_config(&self, esphome_port: u16) -> OutputConfig {
+        match self.kind {
+            DeviceKind::Wled => OutputConfig::Wled(WledConfig {
+                host: format!("{}:{}", self.address, self.port),
+                segment: 0,
+                presets: Default::default(),
+                colors: Default::default(),
+            }),
+            DeviceKind::Esphome => OutputConfig::Udp {
+                destination: format!("{}:{esphome_port}", self.address),
+                codes: Default::default(),
+                format: Default::default(),
+                template: None,
+                patterns: Default::default(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            DeviceKind::Wled => "WLED",
+            DeviceKind::Esphome => "ESPHome",
+        };
+        write!(f, "{kind} {} at {}:{}", self.name, self.address, self.port)
+    }
+}
+
+/// Browses for WLED and ESPHome devices for `duration` and returns everything that answered.
+pub async fn browse(duration: Duration) -> io::Result<Vec<Device>> {
+    let daemon = ServiceDaemon::new().map_err(io::Error::other)?;
+    let wled = daemon.browse(WLED_SERVICE).map_err(io::Error::other)?;
+    let esphome = daemon.browse(ESPHOME_SERVICE).map_err(io::Error::other)?;
+
+    let mut devices = Vec::new();
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    loop {
+        let (kind, event) = tokio::select! {
+            event = wled.recv_async() => (DeviceKind::Wled, event),
+            event = esphome.recv_async() => (DeviceKind::Esphome, event),
+            _ = &mut deadline => break,
+        };
+        let service = match event {
+            Ok(ServiceEvent::ServiceResolved(service)) => service,
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+        let Some(address) = service.get_addresses_v4().into_iter().min() else {
+            continue;
+        };
+
+        let name = service
+            .get_fullname()
+            .strip_suffix(&service.ty_domain)
+            .unwrap_or(service.get_fullname())
+            .trim_end_matches('.')
+            .to_string();
+        let device = Device {
+            kind,
+            name,
+            address,
+            port: service.port,
+        };
+        if !devices.contains(&device) {
+            devices.push(device);
+        }
+    }
+
+    // Shutting down only fails when the daemon already stopped
+    let _ = daemon.shutdown();
+    devices.sort_by(|a, b| a.name.cmp(&b.name).then(a.address.cmp(&b.address)));
+    Ok(devices)
+}
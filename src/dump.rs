@@ -0,0 +1,97 @@
+//! Hex dumps of the packets that failed to parse, to attach to a bug report.
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+const PACKET_ID_OFFSET: usize = 6;
+const BYTES_PER_LINE: usize = 16;
+
+/// Writes the first packet of every packet id that failed to parse to a file.
+#[derive(Debug)]
+pub struct PacketDump {
+    file: File,
+    /// Packet ids dumped so far, `None` for packets too short to have one
+    dumped: HashSet<Option<u8>>,
+}
+
+impl PacketDump {
+    /// Creates (or truncates) the file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            file: File::create(path)?,
+            dumped: HashSet::new(),
+        })
+    }
+
+    /// Dumps `packet` with the `error` parsing it, unless a packet with its id was dumped before.
+    pub fn write(&mut self, packet: &[u8], error: &io::Error) -> io::Result<()> {
+        if !self.dumped.insert(packet.get(PACKET_ID_OFFSET).copied()) {
+            return Ok(());
+        }
+        let mut dump = format!("# {error}\n");
+        dump.push_str(&hex_dump(packet));
+        dump.push('\n');
+        self.file.write_all(dump.as_bytes())
+    }
+}
+
+/// `packet` as lines of the offset, 16 bytes in hex and those bytes as ASCII.
+fn hex_dump(packet: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, bytes) in packet.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * BYTES_PER_LINE);
+        for i in 0..BYTES_PER_LINE {
+            match bytes.get(i) {
+                Some(byte) => {
+                    let _ = write!(dump, " {byte:02x}");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(bytes.iter().map(|&byte| match byte {
+            0x20..=0x7e => char::from(byte),
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_dump_pads_the_last_line() {
+        let packet: Vec<u8> = (0x41..0x53).collect();
+        assert_eq!(
+            hex_dump(&packet),
+            "00000000  41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f 50  |ABCDEFGHIJKLMNOP|\n\
+             00000010  51 52                                            |QR|\n"
+        );
+    }
+
+    #[test]
+    fn only_the_first_packet_of_an_id_is_dumped() {
+        let path = std::env::temp_dir().join(format!("f1_light_sync_dump_{}", std::process::id()));
+        let mut dump = PacketDump::create(&path).unwrap();
+        let error = io::Error::new(io::ErrorKind::InvalidData, "broken");
+        let mut packet = [0; 8];
+        packet[PACKET_ID_OFFSET] = 7;
+        dump.write(&packet, &error).unwrap();
+        packet[0] = 1;
+        dump.write(&packet, &error).unwrap();
+        packet[PACKET_ID_OFFSET] = 3;
+        dump.write(&packet, &error).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written.matches("# broken").count(), 2);
+        assert!(written.contains("00 00 00 00 00 00 07 00"));
+        assert!(written.contains("01 00 00 00 00 00 03 00"));
+    }
+}
@@ -0,0 +1,185 @@
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+use f1_game_library_models_25::telemetry_data::EventType;
+use f1_game_library_models_25::telemetry_data::F1Data;
+use f1_game_library_models_25::telemetry_data::PenaltyType;
+use f1_game_library_models_25::telemetry_data::VehicleFiaFlags;
+use tracing::warn;
+
+use crate::flag::Abbreviation;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::flag::PenaltyKind;
+use crate::flag::PitStatus;
+use crate::flag::SessionKind;
+use crate::indicator::Indicator;
+
+/// A change in the game that the [`FlagManager`](crate::FlagManager) reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagEvent {
+    /// Race numbers and name abbreviations of all cars, indexed by vehicle index
+    Participants {
+        numbers: [u8; MAX_CARS_IN_SESSION],
+        abbreviations: [Option<Abbreviation>; MAX_CARS_IN_SESSION],
+    },
+    SetGlobalFlag(GlobalFlag),
+    ResetGlobalFlag,
+    SetLocalFlag(LocalFlag),
+    ResetLocalFlag,
+    /// A penalty was given to the car with this vehicle index
+    Penalty {
+        vehicle_index: usize,
+        kind: PenaltyKind,
+    },
+    /// The drive-through or stop-go the car with this vehicle index still has to serve, `None`
+    /// once it served them all
+    UnservedPenalty {
+        vehicle_index: usize,
+        kind: Option<PenaltyKind>,
+    },
+    /// The player gained or lost places
+    PositionChange {
+        gained: bool,
+    },
+    /// The car with this vehicle index set the fastest lap, `player` when that is the player
+    FastestLap {
+        vehicle_index: usize,
+        player: bool,
+    },
+    /// The player's car got damaged all of a sudden
+    Damage,
+    /// The player hit another car or braked harder than braking can
+    Collision,
+    Finish,
+    /// Percentage of the formation lap the player has left, 0 once it is over
+    Countdown(u8),
+    /// This many of the five start lights are lit
+    StartLights(u8),
+    /// The start lights went out, the race started
+    LightsOut,
+    /// The player was disqualified or retired
+    BlackFlag,
+    /// The player got a warning
+    BlackAndWhite,
+    /// The player turned the pit limiter on or off
+    PitLimiter(bool),
+    /// The player entered or left the pit lane or the box
+    PitStatus(PitStatus),
+    /// The temperature in °C shown while no flag is out
+    Ambient(i8),
+    /// Percentage of the speed or G force at full brightness, shown while no flag is out
+    Motion(u8),
+    /// A session started or ended, clear all state
+    Reset,
+    /// The type of the session, sent when it is first known or changes
+    Session(SessionKind),
+    /// Something changed for the indicators shown next to the flags
+    Indicator(Indicator),
+}
+
+impl FlagEvent {
+    /// Translates an F1 25 telemetry packet into the event it represents, if any.
+    pub fn from_packet(packet: &F1Data) -> Option<Self> {
+        match packet {
+            F1Data::ParticipantData(data) => Some(FlagEvent::Participants {
+                numbers: data.participants.map(|v| v.race_number),
+                abbreviations: data.participants.map(|v| abbreviation(&v.name)),
+            }),
+            F1Data::EventData(data) => match data.r#type {
+                EventType::SafetyCar(safetycar) => {
+                    match (safetycar.safety_car_type, safetycar.event_type) {
+                        // The formation lap lasts until the lights go out
+                        (3, 0..=3) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::FormationLap)),
+                        (0, _) | (_, 2) | (_, 3) => Some(FlagEvent::ResetGlobalFlag),
+                        (1, 0) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Sc)),
+                        (1, 1) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::ScEnding)),
+                        (2, 0) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Vsc)),
+                        (2, 1) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::VscEnding)),
+                        (safety_car_type, event_type) => {
+                            warn!(safety_car_type, event_type, "Unknown safety car event");
+                            None
+                        }
+                    }
+                }
+                EventType::Penalty(penalty)
+                    if penalty.vehicle_index == data.m_header.player_car_index
+                        && matches!(
+                            penalty.penalty_type,
+                            PenaltyType::Disqualified | PenaltyType::Retired
+                        ) =>
+                {
+                    Some(FlagEvent::BlackFlag)
+                }
+                EventType::Penalty(penalty)
+                    if penalty.vehicle_index == data.m_header.player_car_index
+                        && matches!(penalty.penalty_type, PenaltyType::Warning) =>
+                {
+                    Some(FlagEvent::BlackAndWhite)
+                }
+                EventType::Penalty(penalty) => Some(FlagEvent::Penalty {
+                    vehicle_index: penalty.vehicle_index,
+                    kind: match penalty.penalty_type {
+                        PenaltyType::TimePenalty => PenaltyKind::Time,
+                        PenaltyType::DriveThrough => PenaltyKind::DriveThrough,
+                        PenaltyType::StopGo => PenaltyKind::StopGo,
+                        PenaltyType::Warning => PenaltyKind::Warning,
+                        PenaltyType::ThisLapInvalidated
+                        | PenaltyType::ThisAndNextLapInvalidated
+                        | PenaltyType::ThisLapInvalidatedWithoutReason
+                        | PenaltyType::ThisAndNextLapInvalidatedWithoutReason
+                        | PenaltyType::ThisAndPreviousLapInvalidated
+                        | PenaltyType::ThisAndPreviousLapInvalidatedWithoutReason => {
+                            PenaltyKind::LapInvalidated
+                        }
+                        _ => PenaltyKind::Other,
+                    },
+                }),
+                EventType::FastestLap(fastest_lap) => Some(FlagEvent::FastestLap {
+                    vehicle_index: fastest_lap.vehicle_index,
+                    player: fastest_lap.vehicle_index == data.m_header.player_car_index,
+                }),
+                EventType::Retirement(retirement)
+                    if retirement.vehicle_index == data.m_header.player_car_index =>
+                {
+                    Some(FlagEvent::BlackFlag)
+                }
+                EventType::Collision(collision)
+                    if [collision.primary_car_index, collision.secondary_car_index]
+                        .contains(&data.m_header.player_car_index) =>
+                {
+                    Some(FlagEvent::Collision)
+                }
+                EventType::ChequeredFlag(_) => Some(FlagEvent::Finish),
+                EventType::RedFlag(_) => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Red)),
+                EventType::StartLights(lights) => Some(FlagEvent::StartLights(lights.num_lights)),
+                EventType::LightsOut(_) => Some(FlagEvent::LightsOut),
+                EventType::SessionStart(_) | EventType::SessionEnd(_) => Some(FlagEvent::Reset),
+                _ => None,
+            },
+            F1Data::ClassificationData(_) => Some(FlagEvent::Reset),
+            F1Data::CarStatusData(data) => {
+                let driver_index = data.header.player_car_index;
+                match data.car_status_data.get(driver_index)?.vehicle_fia_flags {
+                    VehicleFiaFlags::InvalidUnknown => {
+                        warn!("Unknown local flag received");
+                        None
+                    }
+                    VehicleFiaFlags::None => Some(FlagEvent::ResetLocalFlag),
+                    VehicleFiaFlags::Green => Some(FlagEvent::SetLocalFlag(LocalFlag::Green)),
+                    VehicleFiaFlags::Blue => Some(FlagEvent::SetLocalFlag(LocalFlag::Blue)),
+                    VehicleFiaFlags::Yellow => Some(FlagEvent::SetLocalFlag(LocalFlag::Yellow)),
+                    VehicleFiaFlags::Red => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Red)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The abbreviation of a NUL terminated name from the participants packet.
+pub(crate) fn abbreviation(name: &[u8]) -> Option<Abbreviation> {
+    let end = name
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(name.len());
+    Abbreviation::of(&String::from_utf8_lossy(&name[..end]))
+}
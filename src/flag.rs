@@ -0,0 +1,370 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+/// Flags that apply to the whole track and take precedence over everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GlobalFlag {
+    Vsc,
+    /// The virtual safety car period ends, racing resumes soon
+    VscEnding,
+    Sc,
+    /// The safety car comes in this lap
+    ScEnding,
+    Red,
+    /// Behind the safety car before the start, until the lights go out
+    FormationLap,
+}
+
+impl GlobalFlag {
+    /// Lowercase name, as used in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            GlobalFlag::Vsc => "vsc",
+            GlobalFlag::VscEnding => "vsc_ending",
+            GlobalFlag::Sc => "sc",
+            GlobalFlag::ScEnding => "sc_ending",
+            GlobalFlag::Red => "red",
+            GlobalFlag::FormationLap => "formation_lap",
+        }
+    }
+}
+
+/// Flags that only apply to the player's car.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LocalFlag {
+    Green,
+    Yellow,
+    /// Waved double yellow, for games that tell it apart from a single yellow
+    DoubleYellow,
+    Blue,
+}
+
+impl LocalFlag {
+    /// Lowercase name, as used in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            LocalFlag::Green => "green",
+            LocalFlag::Yellow => "yellow",
+            LocalFlag::DoubleYellow => "double_yellow",
+            LocalFlag::Blue => "blue",
+        }
+    }
+}
+
+/// What a penalty asks of the driver, as far as the lights tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyKind {
+    /// Seconds added to the race time
+    Time,
+    DriveThrough,
+    StopGo,
+    Warning,
+    /// The lap time does not count, sometimes together with the next or previous lap
+    LapInvalidated,
+    /// Grid penalties, disqualifications and the other messages of the stewards
+    Other,
+}
+
+/// Where the player is in a pit stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PitStatus {
+    None,
+    PitLane,
+    /// Stopped in the box
+    PitStop,
+}
+
+/// Type of session, for the profiles of the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionKind {
+    Practice,
+    /// Sprint shootouts included
+    Qualifying,
+    Race,
+    TimeTrial,
+}
+
+impl SessionKind {
+    /// Lowercase name, as used for the profiles in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            SessionKind::Practice => "practice",
+            SessionKind::Qualifying => "quali",
+            SessionKind::Race => "race",
+            SessionKind::TimeTrial => "tt",
+        }
+    }
+}
+
+/// First three letters of a driver's last name in capitals, like `HAM`.
+///
+/// Only ASCII letters are kept, the most light controllers can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Abbreviation([u8; 3]);
+
+impl Abbreviation {
+    /// The abbreviation of a driver name like `Lewis HAMILTON`, `None` for names shorter than
+    /// three letters.
+    pub fn of(name: &str) -> Option<Self> {
+        let last_name = name.split_whitespace().last()?;
+        let mut letters = last_name
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|letter| letter.to_ascii_uppercase());
+        Some(Self([letters.next()?, letters.next()?, letters.next()?]))
+    }
+}
+
+impl fmt::Display for Abbreviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0
+            .iter()
+            .try_for_each(|&letter| write!(f, "{}", char::from(letter)))
+    }
+}
+
+/// Everything that can be shown on the lights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Global(GlobalFlag),
+    Local(LocalFlag),
+    /// Penalty for the driver with the given race number (or vehicle index if unknown)
+    Penalty {
+        number: usize,
+        kind: PenaltyKind,
+        driver: Option<Abbreviation>,
+    },
+    Finish,
+    /// The pit limiter is on, shown over all flags until it is turned off
+    PitLimiter,
+    /// Someone set the fastest lap of the session
+    FastestLap,
+    /// The player's car just got damaged, like losing the front wing or a puncture
+    Damage,
+    /// The player crashed or hit a car, strobing white and red
+    Collision {
+        red: bool,
+    },
+    /// The player was disqualified or retired, for the rest of the session
+    Black,
+    /// The player was warned for track limits or unsportsmanlike behavior
+    BlackAndWhite,
+    /// Percentage of the formation lap the player has left
+    Countdown(u8),
+    /// This many of the five start lights are lit
+    StartLights(u8),
+    /// No flag is out, the lights show the temperature in °C
+    Ambient(i8),
+    /// No flag is out, the lights are as bright as this percentage of the speed or G force
+    Motion(u8),
+    /// The player gained places
+    PositionGained,
+    /// The player lost places
+    PositionLost,
+    /// The player is driving through the pit lane
+    PitLane,
+    /// The player is stopped in the box
+    PitStop,
+    /// The player should come in at the end of the lap
+    PitThisLap,
+}
+
+impl Flag {
+    /// Lowercase name, as used in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Flag::Global(global_flag) => global_flag.name(),
+            Flag::Local(local_flag) => local_flag.name(),
+            Flag::Penalty {
+                kind: PenaltyKind::DriveThrough,
+                ..
+            } => "drive_through",
+            Flag::Penalty {
+                kind: PenaltyKind::StopGo,
+                ..
+            } => "stop_go",
+            Flag::Penalty { .. } => "penalty",
+            Flag::Finish => "finish",
+            Flag::PitLimiter => "pit_limiter",
+            Flag::FastestLap => "fastest_lap",
+            Flag::Damage => "damage",
+            Flag::Collision { .. } => "collision",
+            Flag::Black => "black",
+            Flag::BlackAndWhite => "black_and_white",
+            Flag::Countdown(_) => "countdown",
+            Flag::StartLights(_) => "start_lights",
+            Flag::Ambient(_) => "ambient",
+            Flag::Motion(_) => "motion",
+            Flag::PositionGained => "position_gained",
+            Flag::PositionLost => "position_lost",
+            Flag::PitLane => "pit_lane",
+            Flag::PitStop => "pit_stop",
+            Flag::PitThisLap => "pit_this_lap",
+        }
+    }
+
+    /// The flag with the name used in the config, `None` for `off`. Flags that carry a value get
+    /// an example one, like car 1 for penalties.
+    pub fn from_name(name: &str) -> Result<Option<Self>, String> {
+        Ok(match name {
+            "green" => Some(Flag::Local(LocalFlag::Green)),
+            "yellow" => Some(Flag::Local(LocalFlag::Yellow)),
+            "double_yellow" => Some(Flag::Local(LocalFlag::DoubleYellow)),
+            "blue" => Some(Flag::Local(LocalFlag::Blue)),
+            "vsc" => Some(Flag::Global(GlobalFlag::Vsc)),
+            "vsc_ending" => Some(Flag::Global(GlobalFlag::VscEnding)),
+            "sc" => Some(Flag::Global(GlobalFlag::Sc)),
+            "sc_ending" => Some(Flag::Global(GlobalFlag::ScEnding)),
+            "red" => Some(Flag::Global(GlobalFlag::Red)),
+            "formation_lap" => Some(Flag::Global(GlobalFlag::FormationLap)),
+            "penalty" => Some(Flag::Penalty {
+                number: 1,
+                driver: None,
+                kind: PenaltyKind::Time,
+            }),
+            "drive_through" => Some(Flag::Penalty {
+                number: 1,
+                driver: None,
+                kind: PenaltyKind::DriveThrough,
+            }),
+            "stop_go" => Some(Flag::Penalty {
+                number: 1,
+                driver: None,
+                kind: PenaltyKind::StopGo,
+            }),
+            "finish" => Some(Flag::Finish),
+            "pit_limiter" => Some(Flag::PitLimiter),
+            "fastest_lap" => Some(Flag::FastestLap),
+            "damage" => Some(Flag::Damage),
+            "collision" => Some(Flag::Collision { red: false }),
+            "black" => Some(Flag::Black),
+            "black_and_white" => Some(Flag::BlackAndWhite),
+            "countdown" => Some(Flag::Countdown(100)),
+            "start_lights" => Some(Flag::StartLights(5)),
+            "ambient" => Some(Flag::Ambient(20)),
+            "motion" => Some(Flag::Motion(100)),
+            "position_gained" => Some(Flag::PositionGained),
+            "position_lost" => Some(Flag::PositionLost),
+            "pit_lane" => Some(Flag::PitLane),
+            "pit_stop" => Some(Flag::PitStop),
+            "pit_this_lap" => Some(Flag::PitThisLap),
+            "off" => None,
+            name => return Err(format!("unknown flag `{name}`")),
+        })
+    }
+
+    /// The string sent to the light controller for this flag, unless the output has codes of its
+    /// own. It starts with a number from 0 to 49, which leaves 50 to 99 to the
+    /// [indicators](crate::indicator::Indicator).
+    pub fn to_enum_str(self) -> String {
+        match self {
+            Flag::Global(global_flag) => String::from(match global_flag {
+                GlobalFlag::Vsc => "5",
+                GlobalFlag::VscEnding => "14",
+                GlobalFlag::Sc => "4",
+                GlobalFlag::ScEnding => "13",
+                GlobalFlag::Red => "12",
+                GlobalFlag::FormationLap => "15",
+            }),
+            Flag::Local(local_flag) => String::from(match local_flag {
+                LocalFlag::Green => "1",
+                LocalFlag::Yellow => "2",
+                LocalFlag::DoubleYellow => "3",
+                LocalFlag::Blue => "8",
+            }),
+            Flag::Penalty { number, kind, .. } => match kind {
+                PenaltyKind::DriveThrough => format!("17,{number}"),
+                PenaltyKind::StopGo => format!("18,{number}"),
+                _ => format!("11,{number}"),
+            },
+            Flag::Finish => String::from("16"),
+            Flag::PitLimiter => String::from("6"),
+            Flag::FastestLap => String::from("9"),
+            Flag::Damage => String::from("26"),
+            Flag::Collision { red } => format!("28,{}", u8::from(red)),
+            Flag::Black => String::from("7"),
+            Flag::BlackAndWhite => String::from("10"),
+            Flag::Countdown(left) => format!("22,{left}"),
+            Flag::StartLights(lights) => format!("19,{lights}"),
+            Flag::Ambient(temperature) => format!("0,{temperature}"),
+            Flag::Motion(percent) => format!("27,{percent}"),
+            Flag::PositionGained => String::from("20"),
+            Flag::PositionLost => String::from("21"),
+            Flag::PitLane => String::from("23"),
+            Flag::PitStop => String::from("24"),
+            Flag::PitThisLap => String::from("25"),
+        }
+    }
+}
+
+impl From<LocalFlag> for Flag {
+    fn from(value: LocalFlag) -> Self {
+        Self::Local(value)
+    }
+}
+
+impl From<GlobalFlag> for Flag {
+    fn from(value: GlobalFlag) -> Self {
+        Self::Global(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_flag_is_found_by_its_name() {
+        let flags = [
+            Flag::Local(LocalFlag::Green),
+            Flag::Local(LocalFlag::Yellow),
+            Flag::Local(LocalFlag::DoubleYellow),
+            Flag::Local(LocalFlag::Blue),
+            Flag::Global(GlobalFlag::Vsc),
+            Flag::Global(GlobalFlag::VscEnding),
+            Flag::Global(GlobalFlag::Sc),
+            Flag::Global(GlobalFlag::ScEnding),
+            Flag::Global(GlobalFlag::Red),
+            Flag::Global(GlobalFlag::FormationLap),
+            Flag::Penalty {
+                number: 44,
+                driver: None,
+                kind: PenaltyKind::Time,
+            },
+            Flag::Penalty {
+                number: 44,
+                driver: None,
+                kind: PenaltyKind::DriveThrough,
+            },
+            Flag::Penalty {
+                number: 44,
+                driver: None,
+                kind: PenaltyKind::StopGo,
+            },
+            Flag::Finish,
+            Flag::PitLimiter,
+            Flag::FastestLap,
+            Flag::Damage,
+            Flag::Collision { red: true },
+            Flag::Black,
+            Flag::BlackAndWhite,
+            Flag::Countdown(3),
+            Flag::StartLights(2),
+            Flag::Ambient(-5),
+            Flag::Motion(50),
+            Flag::PositionGained,
+            Flag::PositionLost,
+            Flag::PitLane,
+            Flag::PitStop,
+            Flag::PitThisLap,
+        ];
+        for flag in flags {
+            let found = Flag::from_name(flag.name()).unwrap();
+            assert_eq!(found.map(Flag::name), Some(flag.name()), "{flag:?}");
+        }
+        assert_eq!(Flag::from_name("off"), Ok(None));
+        assert!(Flag::from_name("checkered").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! The temperatures of the session packet, laid out the same since F1 23.
+use std::io;
+
+use serde::Deserialize;
+
+use super::too_short;
+use crate::event::FlagEvent;
+
+const TRACK_TEMPERATURE_OFFSET: usize = 30;
+const AIR_TEMPERATURE_OFFSET: usize = 31;
+
+/// Which temperature colors the lights while no flag is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmbientTemperature {
+    #[default]
+    Track,
+    Air,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AmbientTracker {
+    /// Only reports when set
+    temperature: Option<AmbientTemperature>,
+    /// Last temperature passed on
+    reported: Option<i8>,
+}
+
+impl AmbientTracker {
+    pub(crate) fn set_temperature(&mut self, temperature: AmbientTemperature) {
+        self.temperature = Some(temperature);
+    }
+
+    /// Reads the temperature from a session packet, the event when it changed.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let offset = match self.temperature {
+            Some(AmbientTemperature::Track) => TRACK_TEMPERATURE_OFFSET,
+            Some(AmbientTemperature::Air) => AIR_TEMPERATURE_OFFSET,
+            None => return Ok(None),
+        };
+        let temperature = *data.get(offset).ok_or_else(too_short)? as i8;
+        if self.reported == Some(temperature) {
+            return Ok(None);
+        }
+        self.reported = Some(temperature);
+        Ok(Some(FlagEvent::Ambient(temperature)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(track: i8, air: i8) -> Vec<u8> {
+        let mut data = vec![0; AIR_TEMPERATURE_OFFSET + 1];
+        data[TRACK_TEMPERATURE_OFFSET] = track as u8;
+        data[AIR_TEMPERATURE_OFFSET] = air as u8;
+        data
+    }
+
+    #[test]
+    fn follows_the_temperature_picked() {
+        let mut tracker = AmbientTracker::default();
+        assert_eq!(tracker.session(&session(35, 25)).unwrap(), None);
+
+        tracker.set_temperature(AmbientTemperature::Air);
+        assert_eq!(
+            tracker.session(&session(35, 25)).unwrap(),
+            Some(FlagEvent::Ambient(25))
+        );
+        // Only the track got warmer
+        assert_eq!(tracker.session(&session(40, 25)).unwrap(), None);
+        // Below freezing in a winter mod
+        assert_eq!(
+            tracker.session(&session(-2, -5)).unwrap(),
+            Some(FlagEvent::Ambient(-5))
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! The cars in the order of the race, for a strip with a light for each car.
+use std::io;
+
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+
+use super::GameYear;
+use super::HEADER_SIZE;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+use crate::indicator::Interval;
+use crate::indicator::NO_CAR;
+
+const TEAM_ID_OFFSET: usize = 3;
+const PARTICIPANT_SIZE: usize = 57;
+const F1_24_PARTICIPANT_SIZE: usize = 60;
+const F1_23_PARTICIPANT_SIZE: usize = 58;
+/// After the distances and the safety car delta
+const CAR_POSITION_OFFSET: usize = 32;
+const F1_23_CAR_POSITION_OFFSET: usize = 30;
+/// Milliseconds part, the minutes part follows from F1 24 on
+const DELTA_TO_CAR_IN_FRONT_OFFSET: usize = 14;
+const DELTA_TO_CAR_IN_FRONT_MINUTES_OFFSET: usize = 16;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+/// Orders the teams of the participants by the race positions of the lap data.
+#[derive(Debug, Clone)]
+pub(crate) struct PositionBoardTracker {
+    /// Only reports when set
+    enabled: bool,
+    /// Team of every car by vehicle index, until the participants came [`NO_CAR`]
+    teams: [u8; MAX_CARS_IN_SESSION],
+    /// Number of cars in the session
+    cars: usize,
+    /// Last board passed on
+    reported: Option<[u8; MAX_CARS_IN_SESSION]>,
+}
+
+impl Default for PositionBoardTracker {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            teams: [NO_CAR; MAX_CARS_IN_SESSION],
+            cars: 0,
+            reported: None,
+        }
+    }
+}
+
+impl PositionBoardTracker {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Reads the team of every car from a participants packet.
+    pub(crate) fn participants(&mut self, data: &[u8], game_year: GameYear) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let size = match game_year {
+            GameYear::F1_23 => F1_23_PARTICIPANT_SIZE,
+            GameYear::F1_24 => F1_24_PARTICIPANT_SIZE,
+            GameYear::F1_25 => PARTICIPANT_SIZE,
+        };
+        let cars = usize::from(*data.get(HEADER_SIZE).ok_or_else(too_short)?);
+        let participants = data.get(HEADER_SIZE + 1..).ok_or_else(too_short)?;
+        if participants.len() < MAX_CARS_IN_SESSION * size {
+            return Err(too_short());
+        }
+
+        self.cars = cars.min(MAX_CARS_IN_SESSION);
+        for (i, participant) in participants
+            .chunks_exact(size)
+            .take(MAX_CARS_IN_SESSION)
+            .enumerate()
+        {
+            self.teams[i] = participant[TEAM_ID_OFFSET];
+        }
+        Ok(())
+    }
+
+    /// Reads the race positions from a lap data packet, the event when the order changed.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        if !self.enabled || self.cars == 0 {
+            return Ok(None);
+        }
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_CAR_POSITION_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (CAR_POSITION_OFFSET, LAP_DATA_SIZE),
+        };
+        let cars = data.get(HEADER_SIZE..).ok_or_else(too_short)?;
+        if cars.len() < MAX_CARS_IN_SESSION * size {
+            return Err(too_short());
+        }
+
+        let mut board = [NO_CAR; MAX_CARS_IN_SESSION];
+        for (i, car) in cars.chunks_exact(size).take(self.cars).enumerate() {
+            // Positions start at 1, 0 for cars without one
+            if let Some(place) = usize::from(car[offset]).checked_sub(1)
+                && place < MAX_CARS_IN_SESSION
+            {
+                board[place] = self.teams[i];
+            }
+        }
+
+        if self.reported == Some(board) {
+            return Ok(None);
+        }
+        self.reported = Some(board);
+        Ok(Some(FlagEvent::Indicator(Indicator::PositionBoard(board))))
+    }
+}
+
+/// Colors the cars in the order of the race by their gap to the car ahead.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TimingTowerTracker {
+    /// Gaps in milliseconds below which a car is in a battle and close, only reports when set
+    thresholds: Option<(u32, u32)>,
+    /// Last tower passed on
+    reported: Option<[Option<Interval>; MAX_CARS_IN_SESSION]>,
+}
+
+impl TimingTowerTracker {
+    pub(crate) fn set_thresholds(&mut self, battle: u32, close: u32) {
+        self.thresholds = Some((battle, close));
+    }
+
+    /// Reads the race positions and the gaps to the car ahead from a lap data packet, the event
+    /// when any of the cars changed.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some((battle, close)) = self.thresholds else {
+            return Ok(None);
+        };
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_CAR_POSITION_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (CAR_POSITION_OFFSET, LAP_DATA_SIZE),
+        };
+        let cars = data.get(HEADER_SIZE..).ok_or_else(too_short)?;
+        if cars.len() < MAX_CARS_IN_SESSION * size {
+            return Err(too_short());
+        }
+
+        let mut tower = [None; MAX_CARS_IN_SESSION];
+        for car in cars.chunks_exact(size).take(MAX_CARS_IN_SESSION) {
+            let Some(place) = usize::from(car[offset]).checked_sub(1) else {
+                continue;
+            };
+            if place >= MAX_CARS_IN_SESSION {
+                continue;
+            }
+            let gap = u32::from(u16::from_le_bytes([
+                car[DELTA_TO_CAR_IN_FRONT_OFFSET],
+                car[DELTA_TO_CAR_IN_FRONT_OFFSET + 1],
+            ]));
+            // Only F1 24 and later count the minutes apart
+            let gap = match game_year {
+                GameYear::F1_23 => gap,
+                GameYear::F1_24 | GameYear::F1_25 => {
+                    gap + u32::from(car[DELTA_TO_CAR_IN_FRONT_MINUTES_OFFSET]) * 60_000
+                }
+            };
+            // The leader has nobody ahead
+            tower[place] = Some(if place == 0 || gap >= close {
+                Interval::Clear
+            } else if gap < battle {
+                Interval::Battle
+            } else {
+                Interval::Close
+            });
+        }
+
+        if self.reported == Some(tower) {
+            return Ok(None);
+        }
+        self.reported = Some(tower);
+        Ok(Some(FlagEvent::Indicator(Indicator::TimingTower(tower))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    /// Participants of `teams`, the rest of the cars empty.
+    fn participants(teams: &[u8]) -> Vec<u8> {
+        let mut participants = vec![0; 1 + MAX_CARS_IN_SESSION * PARTICIPANT_SIZE];
+        participants[0] = teams.len() as u8;
+        for (i, &team) in teams.iter().enumerate() {
+            participants[1 + i * PARTICIPANT_SIZE + TEAM_ID_OFFSET] = team;
+        }
+        test_packet(&participants)
+    }
+
+    /// Lap data of cars in `positions` with the gap in milliseconds to the car ahead.
+    fn lap_data(cars: &[(u8, u32)]) -> Vec<u8> {
+        let mut lap_data = vec![0; MAX_CARS_IN_SESSION * LAP_DATA_SIZE];
+        for (car, &(position, gap)) in lap_data.chunks_exact_mut(LAP_DATA_SIZE).zip(cars) {
+            car[CAR_POSITION_OFFSET] = position;
+            car[DELTA_TO_CAR_IN_FRONT_OFFSET..DELTA_TO_CAR_IN_FRONT_OFFSET + 2]
+                .copy_from_slice(&((gap % 60_000) as u16).to_le_bytes());
+            car[DELTA_TO_CAR_IN_FRONT_MINUTES_OFFSET] = (gap / 60_000) as u8;
+        }
+        test_packet(&lap_data)
+    }
+
+    #[test]
+    fn teams_go_in_the_order_of_the_race() {
+        let game_year = GameYear::F1_25;
+        let mut tracker = PositionBoardTracker::default();
+        tracker.enable();
+        tracker
+            .participants(&participants(&[10, 11, 12]), game_year)
+            .unwrap();
+        // The fourth car is not in the session
+        let packet = lap_data(&[(3, 0), (1, 0), (2, 0), (4, 0)]);
+        let mut board = [NO_CAR; MAX_CARS_IN_SESSION];
+        board[..3].copy_from_slice(&[11, 12, 10]);
+        assert_eq!(
+            tracker.lap_data(&packet, game_year).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::PositionBoard(board)))
+        );
+        assert_eq!(tracker.lap_data(&packet, game_year).unwrap(), None);
+    }
+
+    #[test]
+    fn nothing_before_the_participants() {
+        let mut tracker = PositionBoardTracker::default();
+        tracker.enable();
+        let packet = lap_data(&[(1, 0)]);
+        assert_eq!(tracker.lap_data(&packet, GameYear::F1_25).unwrap(), None);
+    }
+
+    #[test]
+    fn intervals_follow_the_gap_to_the_car_ahead() {
+        let mut tracker = TimingTowerTracker::default();
+        tracker.set_thresholds(500, 1_000);
+        // The leader's gap does not count, nor does the car without a position
+        let packet = lap_data(&[(2, 60_400), (1, 100), (4, 700), (3, 400), (0, 100)]);
+        let mut tower = [None; MAX_CARS_IN_SESSION];
+        tower[..4].copy_from_slice(&[
+            Some(Interval::Clear),
+            Some(Interval::Clear),
+            Some(Interval::Battle),
+            Some(Interval::Close),
+        ]);
+        assert_eq!(
+            tracker.lap_data(&packet, GameYear::F1_25).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::TimingTower(tower)))
+        );
+        assert_eq!(tracker.lap_data(&packet, GameYear::F1_25).unwrap(), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Crashes of the player from the motion packets, laid out the same since F1 23. Collisions with
+//! other cars come with an event of their own.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+
+const CAR_MOTION_SIZE: usize = 60;
+const G_FORCE_LONGITUDINAL_OFFSET: usize = 40;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CrashTracker {
+    /// Braking G force that counts as a crash, only reports when set
+    g_force: Option<f32>,
+    /// Whether the last packet was past it, so a crash is only reported once
+    crashing: bool,
+}
+
+impl CrashTracker {
+    pub(crate) fn set_g_force(&mut self, g_force: f32) {
+        self.g_force = Some(g_force);
+    }
+
+    /// Reads the longitudinal G force from a motion packet, the event when the player just
+    /// slowed down harder than the threshold.
+    pub(crate) fn motion(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some(threshold) = self.g_force else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_MOTION_SIZE)?;
+        let g_force = &car[G_FORCE_LONGITUDINAL_OFFSET..G_FORCE_LONGITUDINAL_OFFSET + 4];
+        let crashing = -f32::from_le_bytes(g_force.try_into().unwrap()) >= threshold;
+
+        let crashed = crashing && !self.crashing;
+        self.crashing = crashing;
+        Ok(crashed.then_some(FlagEvent::Collision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn motion(g_force: f32) -> Vec<u8> {
+        let mut car = [0; CAR_MOTION_SIZE];
+        car[G_FORCE_LONGITUDINAL_OFFSET..G_FORCE_LONGITUDINAL_OFFSET + 4]
+            .copy_from_slice(&g_force.to_le_bytes());
+        test_packet(&car)
+    }
+
+    #[test]
+    fn a_crash_is_reported_once_while_braking_past_the_threshold() {
+        let mut tracker = CrashTracker::default();
+        assert_eq!(tracker.motion(&motion(-20.0)).unwrap(), None);
+
+        tracker.set_g_force(8.0);
+        // Accelerating as hard does not count
+        assert_eq!(tracker.motion(&motion(8.0)).unwrap(), None);
+        assert_eq!(
+            tracker.motion(&motion(-8.0)).unwrap(),
+            Some(FlagEvent::Collision)
+        );
+        assert_eq!(tracker.motion(&motion(-12.0)).unwrap(), None);
+        tracker.motion(&motion(-1.0)).unwrap();
+        assert_eq!(
+            tracker.motion(&motion(-9.0)).unwrap(),
+            Some(FlagEvent::Collision)
+        );
+    }
+}
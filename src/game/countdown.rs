@@ -0,0 +1,159 @@
+//! How much of the formation lap the player has left, from the session and lap data.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use super::too_short;
+use crate::event::FlagEvent;
+
+const TRACK_LENGTH_OFFSET: usize = 33;
+const SAFETY_CAR_STATUS_OFFSET: usize = 153;
+const SAFETY_CAR_FORMATION_LAP: u8 = 3;
+const TOTAL_DISTANCE_OFFSET: usize = 24;
+const F1_23_TOTAL_DISTANCE_OFFSET: usize = 22;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+/// Counts the formation lap down by the distance the player drove since it started.
+///
+/// The game has no time until the lights go out, but once back on the grid the start lights follow
+/// within seconds.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CountdownTracker {
+    /// Steps the countdown goes down in, only reports when set
+    steps: Option<u8>,
+    formation_lap: bool,
+    track_length: f32,
+    /// Total distance of the player when the formation lap started
+    start: Option<f32>,
+    /// Last percentage passed on
+    reported: Option<u8>,
+}
+
+impl CountdownTracker {
+    pub(crate) fn set_steps(&mut self, steps: u8) {
+        self.steps = Some(steps.max(1));
+    }
+
+    /// Reads whether the formation lap is on from a session packet, the event when it ended.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        if self.steps.is_none() {
+            return Ok(None);
+        }
+        let status = *data.get(SAFETY_CAR_STATUS_OFFSET).ok_or_else(too_short)?;
+        let track_length = &data[TRACK_LENGTH_OFFSET..TRACK_LENGTH_OFFSET + 2];
+        self.track_length = u16::from_le_bytes(track_length.try_into().unwrap()).into();
+        self.formation_lap = status == SAFETY_CAR_FORMATION_LAP;
+        if self.formation_lap {
+            return Ok(None);
+        }
+        self.start = None;
+        Ok(self.report(0))
+    }
+
+    /// Reads how far the player drove from a lap data packet, the event when the countdown went a
+    /// step down.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some(steps) = self.steps else {
+            return Ok(None);
+        };
+        if !self.formation_lap || self.track_length <= 0.0 {
+            return Ok(None);
+        }
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_TOTAL_DISTANCE_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (TOTAL_DISTANCE_OFFSET, LAP_DATA_SIZE),
+        };
+        let car = player_car(data, size)?;
+        let distance = f32::from_le_bytes(car[offset..offset + 4].try_into().unwrap());
+        let start = *self.start.get_or_insert(distance);
+        let left = (1.0 - (distance - start) / self.track_length).clamp(0.0, 1.0);
+        // Rounded up, so the last step only goes when the player is back on the grid
+        let step = (left * f32::from(steps)).ceil() as u8;
+        Ok(self.report((u16::from(step) * 100 / u16::from(steps)) as u8))
+    }
+
+    fn report(&mut self, left: u8) -> Option<FlagEvent> {
+        // Nothing to end when the countdown never started
+        if self.reported.unwrap_or(0) == left {
+            return None;
+        }
+        self.reported = Some(left);
+        Some(FlagEvent::Countdown(left))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn session(length: u16, safety_car_status: u8) -> Vec<u8> {
+        let mut data = vec![0; SAFETY_CAR_STATUS_OFFSET + 1];
+        data[TRACK_LENGTH_OFFSET..TRACK_LENGTH_OFFSET + 2].copy_from_slice(&length.to_le_bytes());
+        data[SAFETY_CAR_STATUS_OFFSET] = safety_car_status;
+        data
+    }
+
+    fn lap_data(total_distance: f32) -> Vec<u8> {
+        let mut car = [0; LAP_DATA_SIZE];
+        car[TOTAL_DISTANCE_OFFSET..TOTAL_DISTANCE_OFFSET + 4]
+            .copy_from_slice(&total_distance.to_le_bytes());
+        test_packet(&car)
+    }
+
+    #[test]
+    fn counts_down_from_where_the_formation_lap_started() {
+        let mut tracker = CountdownTracker::default();
+        tracker.set_steps(4);
+        let game_year = GameYear::F1_25;
+        assert_eq!(
+            tracker
+                .session(&session(4000, SAFETY_CAR_FORMATION_LAP))
+                .unwrap(),
+            None
+        );
+        // The grid is behind the line
+        assert_eq!(
+            tracker.lap_data(&lap_data(-300.0), game_year).unwrap(),
+            Some(FlagEvent::Countdown(100))
+        );
+        assert_eq!(tracker.lap_data(&lap_data(699.0), game_year).unwrap(), None);
+        assert_eq!(
+            tracker.lap_data(&lap_data(800.0), game_year).unwrap(),
+            Some(FlagEvent::Countdown(75))
+        );
+        // The last step only goes back on the grid
+        assert_eq!(
+            tracker.lap_data(&lap_data(3650.0), game_year).unwrap(),
+            Some(FlagEvent::Countdown(25))
+        );
+        assert_eq!(
+            tracker.lap_data(&lap_data(3700.0), game_year).unwrap(),
+            Some(FlagEvent::Countdown(0))
+        );
+    }
+
+    #[test]
+    fn ends_with_the_formation_lap() {
+        let mut tracker = CountdownTracker::default();
+        tracker.set_steps(10);
+        let game_year = GameYear::F1_25;
+        // Nothing to end without a formation lap
+        assert_eq!(tracker.session(&session(4000, 0)).unwrap(), None);
+        assert_eq!(tracker.lap_data(&lap_data(0.0), game_year).unwrap(), None);
+
+        tracker
+            .session(&session(4000, SAFETY_CAR_FORMATION_LAP))
+            .unwrap();
+        tracker.lap_data(&lap_data(0.0), game_year).unwrap();
+        assert_eq!(
+            tracker.session(&session(4000, 0)).unwrap(),
+            Some(FlagEvent::Countdown(0))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Sudden damage to the player's car from the car damage, like losing the front wing or a
+//! puncture.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use crate::event::FlagEvent;
+
+/// Damage of each tyre in percent, after their wear
+const TYRES_DAMAGE_OFFSET: usize = 16;
+/// Left and right front wing, F1 25 added the blisters in front of them
+const FRONT_WING_DAMAGE_OFFSET: usize = 28;
+const F1_24_FRONT_WING_DAMAGE_OFFSET: usize = 24;
+const CAR_DAMAGE_SIZE: usize = 46;
+const F1_24_CAR_DAMAGE_SIZE: usize = 42;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DamageTracker {
+    /// Percentage points of damage that have to come at once, only reports when set
+    threshold: Option<u8>,
+    /// Damage of the tyres and the front wing in the last packet
+    last: Option<[u8; 6]>,
+}
+
+impl DamageTracker {
+    pub(crate) fn set_threshold(&mut self, threshold: u8) {
+        self.threshold = Some(threshold);
+    }
+
+    /// Reads the damage from a car damage packet, the event when a tyre or the front wing got
+    /// damaged by at least the threshold since the last packet.
+    pub(crate) fn car_damage(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some(threshold) = self.threshold else {
+            return Ok(None);
+        };
+        let (wing_offset, size) = match game_year {
+            GameYear::F1_25 => (FRONT_WING_DAMAGE_OFFSET, CAR_DAMAGE_SIZE),
+            GameYear::F1_23 | GameYear::F1_24 => {
+                (F1_24_FRONT_WING_DAMAGE_OFFSET, F1_24_CAR_DAMAGE_SIZE)
+            }
+        };
+        let car = player_car(data, size)?;
+        let mut damage = [0; 6];
+        damage[..4].copy_from_slice(&car[TYRES_DAMAGE_OFFSET..TYRES_DAMAGE_OFFSET + 4]);
+        damage[4..].copy_from_slice(&car[wing_offset..wing_offset + 2]);
+
+        // Joining a session with a damaged car is nothing new
+        let sudden = self.last.is_some_and(|last| {
+            damage
+                .iter()
+                .zip(last)
+                .any(|(&now, before)| now.saturating_sub(before) >= threshold)
+        });
+        self.last = Some(damage);
+        Ok(sudden.then_some(FlagEvent::Damage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    /// Car damage with the damage of the tyres and the left and right front wing.
+    fn damage(game_year: GameYear, tyres: [u8; 4], wing: [u8; 2]) -> Vec<u8> {
+        let (wing_offset, size) = match game_year {
+            GameYear::F1_25 => (FRONT_WING_DAMAGE_OFFSET, CAR_DAMAGE_SIZE),
+            GameYear::F1_23 | GameYear::F1_24 => {
+                (F1_24_FRONT_WING_DAMAGE_OFFSET, F1_24_CAR_DAMAGE_SIZE)
+            }
+        };
+        let mut car = vec![0; size];
+        car[TYRES_DAMAGE_OFFSET..TYRES_DAMAGE_OFFSET + 4].copy_from_slice(&tyres);
+        car[wing_offset..wing_offset + 2].copy_from_slice(&wing);
+        test_packet(&car)
+    }
+
+    #[test]
+    fn damage_has_to_come_at_once() {
+        let mut tracker = DamageTracker::default();
+        tracker.set_threshold(20);
+        let year = GameYear::F1_25;
+        // Joining with a damaged car
+        let packet = damage(year, [0; 4], [50, 0]);
+        assert_eq!(tracker.car_damage(&packet, year).unwrap(), None);
+        // Bit by bit up to over the threshold
+        let packet = damage(year, [0; 4], [65, 0]);
+        assert_eq!(tracker.car_damage(&packet, year).unwrap(), None);
+        let packet = damage(year, [0; 4], [80, 0]);
+        assert_eq!(tracker.car_damage(&packet, year).unwrap(), None);
+        // A puncture
+        let packet = damage(year, [0, 0, 100, 0], [80, 0]);
+        assert_eq!(
+            tracker.car_damage(&packet, year).unwrap(),
+            Some(FlagEvent::Damage)
+        );
+        // Repairs in the pits are not damage
+        let packet = damage(year, [0; 4], [0, 0]);
+        assert_eq!(tracker.car_damage(&packet, year).unwrap(), None);
+    }
+
+    #[test]
+    fn f1_24_has_the_front_wing_earlier() {
+        let mut tracker = DamageTracker::default();
+        tracker.set_threshold(20);
+        let year = GameYear::F1_24;
+        let packet = damage(year, [0; 4], [0, 0]);
+        assert_eq!(tracker.car_damage(&packet, year).unwrap(), None);
+        let packet = damage(year, [0; 4], [0, 40]);
+        assert_eq!(
+            tracker.car_damage(&packet, year).unwrap(),
+            Some(FlagEvent::Damage)
+        );
+    }
+}
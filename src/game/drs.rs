@@ -0,0 +1,88 @@
+//! The player's DRS from the car status and car telemetry, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Drs;
+use crate::indicator::Indicator;
+
+pub(crate) const CAR_TELEMETRY_PACKET: u8 = 6;
+
+const CAR_STATUS_SIZE: usize = 55;
+const DRS_ALLOWED_OFFSET: usize = 22;
+const CAR_TELEMETRY_SIZE: usize = 60;
+const DRS_OFFSET: usize = 18;
+
+/// Combines whether DRS is allowed (car status) and open (car telemetry).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DrsTracker {
+    allowed: bool,
+    /// Last state passed on
+    reported: Option<Drs>,
+}
+
+impl DrsTracker {
+    /// Reads whether DRS is allowed from a car status packet.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<()> {
+        self.allowed = player_car(data, CAR_STATUS_SIZE)?[DRS_ALLOWED_OFFSET] == 1;
+        Ok(())
+    }
+
+    /// Reads whether DRS is open from a car telemetry packet, the event when the state changed.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let drs = match player_car(data, CAR_TELEMETRY_SIZE)?[DRS_OFFSET] {
+            1 => Drs::Open,
+            _ if self.allowed => Drs::Available,
+            _ => Drs::Unavailable,
+        };
+        if self.reported == Some(drs) {
+            return Ok(None);
+        }
+        self.reported = Some(drs);
+        Ok(Some(FlagEvent::Indicator(Indicator::Drs(drs))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn telemetry(open: bool) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[DRS_OFFSET] = u8::from(open);
+        test_packet(&car)
+    }
+
+    fn status(allowed: bool) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[DRS_ALLOWED_OFFSET] = u8::from(allowed);
+        test_packet(&car)
+    }
+
+    fn drs(drs: Drs) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Drs(drs)))
+    }
+
+    #[test]
+    fn open_goes_over_allowed() {
+        let mut tracker = DrsTracker::default();
+        assert_eq!(
+            tracker.car_telemetry(&telemetry(false)).unwrap(),
+            drs(Drs::Unavailable)
+        );
+        tracker.car_status(&status(true)).unwrap();
+        assert_eq!(
+            tracker.car_telemetry(&telemetry(false)).unwrap(),
+            drs(Drs::Available)
+        );
+        assert_eq!(tracker.car_telemetry(&telemetry(false)).unwrap(), None);
+        assert_eq!(
+            tracker.car_telemetry(&telemetry(true)).unwrap(),
+            drs(Drs::Open)
+        );
+        // Still open until the player brakes, even once it is no longer allowed
+        tracker.car_status(&status(false)).unwrap();
+        assert_eq!(tracker.car_telemetry(&telemetry(true)).unwrap(), None);
+    }
+}
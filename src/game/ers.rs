@@ -0,0 +1,88 @@
+//! The player's ERS from the car status, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::ErsMode;
+use crate::indicator::Indicator;
+
+const CAR_STATUS_SIZE: usize = 55;
+const ERS_STORE_ENERGY_OFFSET: usize = 37;
+const ERS_DEPLOY_MODE_OFFSET: usize = 41;
+/// The battery holds 4 MJ
+const ERS_MAX_ENERGY: f32 = 4_000_000.0;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ErsTracker {
+    /// Last state passed on
+    reported: Option<(ErsMode, u8)>,
+}
+
+impl ErsTracker {
+    /// Reads the deploy mode and battery from a car status packet, the event when either changed.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let car = player_car(data, CAR_STATUS_SIZE)?;
+
+        let energy = &car[ERS_STORE_ENERGY_OFFSET..ERS_STORE_ENERGY_OFFSET + 4];
+        let energy = f32::from_le_bytes(energy.try_into().unwrap());
+        let charge = (energy / ERS_MAX_ENERGY * 100.0).clamp(0.0, 100.0).round() as u8;
+        let mode = match car[ERS_DEPLOY_MODE_OFFSET] {
+            1 => ErsMode::Medium,
+            2 => ErsMode::Hotlap,
+            3 => ErsMode::Overtake,
+            _ => ErsMode::None,
+        };
+
+        if self.reported == Some((mode, charge)) {
+            return Ok(None);
+        }
+        self.reported = Some((mode, charge));
+        Ok(Some(FlagEvent::Indicator(Indicator::Ers { mode, charge })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn status(energy: f32, mode: u8) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[ERS_STORE_ENERGY_OFFSET..ERS_STORE_ENERGY_OFFSET + 4]
+            .copy_from_slice(&energy.to_le_bytes());
+        car[ERS_DEPLOY_MODE_OFFSET] = mode;
+        test_packet(&car)
+    }
+
+    fn ers(mode: ErsMode, charge: u8) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Ers { mode, charge }))
+    }
+
+    #[test]
+    fn charge_is_a_rounded_percentage_of_the_battery() {
+        let mut tracker = ErsTracker::default();
+        assert_eq!(
+            tracker.car_status(&status(1_990_000.0, 2)).unwrap(),
+            ers(ErsMode::Hotlap, 50)
+        );
+        // Less than half a percent more
+        assert_eq!(tracker.car_status(&status(2_010_000.0, 2)).unwrap(), None);
+        assert_eq!(
+            tracker.car_status(&status(2_010_000.0, 3)).unwrap(),
+            ers(ErsMode::Overtake, 50)
+        );
+    }
+
+    #[test]
+    fn odd_values_stay_in_range() {
+        let mut tracker = ErsTracker::default();
+        assert_eq!(
+            tracker.car_status(&status(4_500_000.0, 9)).unwrap(),
+            ers(ErsMode::None, 100)
+        );
+        assert_eq!(
+            tracker.car_status(&status(-10.0, 1)).unwrap(),
+            ers(ErsMode::Medium, 0)
+        );
+    }
+}
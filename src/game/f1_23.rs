@@ -0,0 +1,47 @@
+//! F1 23 packets.
+//!
+//! F1 23 has no safety car event, the safety car only shows up in the session packet. Everything
+//! else is read like F1 24, with the participants again a bit shorter.
+use std::io;
+
+use super::f1_24::CAR_STATUS_PACKET;
+use super::f1_24::EVENT_PACKET;
+use super::f1_24::FINAL_CLASSIFICATION_PACKET;
+use super::f1_24::PARTICIPANTS_PACKET;
+use super::f1_24::SESSION_PACKET;
+use super::f1_24::driver_numbers;
+use super::packet_id;
+use super::parse_f1_25;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+
+const PARTICIPANT_SIZE: usize = 58;
+/// After the header, the session info and the 21 marshal zones
+const SAFETY_CAR_STATUS_OFFSET: usize = 153;
+
+/// Parses the packet, `safety_car_status` is the status of the previous session packet.
+pub(crate) fn parse(data: &[u8], safety_car_status: &mut u8) -> io::Result<Option<FlagEvent>> {
+    match packet_id(data)? {
+        SESSION_PACKET => {
+            let status = *data.get(SAFETY_CAR_STATUS_OFFSET).ok_or_else(too_short)?;
+            if status == *safety_car_status {
+                return Ok(None);
+            }
+
+            // Only report changes, a reset on every packet would clear a red flag
+            *safety_car_status = status;
+            Ok(match status {
+                0 => Some(FlagEvent::ResetGlobalFlag),
+                1 => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Sc)),
+                2 => Some(FlagEvent::SetGlobalFlag(GlobalFlag::Vsc)),
+                3 => Some(FlagEvent::SetGlobalFlag(GlobalFlag::FormationLap)),
+                _ => None,
+            })
+        }
+        EVENT_PACKET | CAR_STATUS_PACKET => parse_f1_25(data),
+        PARTICIPANTS_PACKET => driver_numbers(data, PARTICIPANT_SIZE).map(Some),
+        FINAL_CLASSIFICATION_PACKET => Ok(Some(FlagEvent::Reset)),
+        _ => Ok(None),
+    }
+}
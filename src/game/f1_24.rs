@@ -0,0 +1,60 @@
+//! F1 24 packets.
+//!
+//! Events and car status are laid out like in F1 25, so those go through the F1 25 models. The
+//! participants got shorter names in F1 25 and are read by hand.
+use std::io;
+
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+
+use super::HEADER_SIZE;
+use super::packet_id;
+use super::parse_f1_25;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::event::abbreviation;
+
+pub(crate) const SESSION_PACKET: u8 = 1;
+pub(crate) const EVENT_PACKET: u8 = 3;
+pub(crate) const PARTICIPANTS_PACKET: u8 = 4;
+pub(crate) const CAR_STATUS_PACKET: u8 = 7;
+pub(crate) const FINAL_CLASSIFICATION_PACKET: u8 = 8;
+
+const PARTICIPANT_SIZE: usize = 60;
+const RACE_NUMBER_OFFSET: usize = 5;
+/// After the nationality, 48 bytes in F1 23 and F1 24
+const NAME_OFFSET: usize = 7;
+const NAME_SIZE: usize = 48;
+
+pub(crate) fn parse(data: &[u8]) -> io::Result<Option<FlagEvent>> {
+    match packet_id(data)? {
+        EVENT_PACKET | CAR_STATUS_PACKET => parse_f1_25(data),
+        PARTICIPANTS_PACKET => driver_numbers(data, PARTICIPANT_SIZE).map(Some),
+        FINAL_CLASSIFICATION_PACKET => Ok(Some(FlagEvent::Reset)),
+        _ => Ok(None),
+    }
+}
+
+/// Reads the race numbers and names from a participants packet with participants of
+/// `participant_size`.
+pub(crate) fn driver_numbers(data: &[u8], participant_size: usize) -> io::Result<FlagEvent> {
+    // The participants follow the number of active cars
+    let participants = data.get(HEADER_SIZE + 1..).ok_or_else(too_short)?;
+    if participants.len() < MAX_CARS_IN_SESSION * participant_size {
+        return Err(too_short());
+    }
+
+    let mut numbers = [0; MAX_CARS_IN_SESSION];
+    let mut abbreviations = [None; MAX_CARS_IN_SESSION];
+    for (i, participant) in participants
+        .chunks_exact(participant_size)
+        .take(MAX_CARS_IN_SESSION)
+        .enumerate()
+    {
+        numbers[i] = participant[RACE_NUMBER_OFFSET];
+        abbreviations[i] = abbreviation(&participant[NAME_OFFSET..NAME_OFFSET + NAME_SIZE]);
+    }
+    Ok(FlagEvent::Participants {
+        numbers,
+        abbreviations,
+    })
+}
@@ -0,0 +1,160 @@
+//! The player's gap to the leader or to the car ahead, from the lap data.
+use std::io;
+
+use serde::Deserialize;
+
+use super::GameYear;
+use super::player_car;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const SESSION_TIME_OFFSET: usize = 15;
+/// Milliseconds and minutes part of the gaps to the car ahead and to the leader, F1 23 has no
+/// minutes parts
+const GAP_OFFSETS: [(usize, Option<usize>); 2] = [(14, Some(16)), (17, Some(19))];
+const F1_23_GAP_OFFSETS: [(usize, Option<usize>); 2] = [(14, None), (16, None)];
+const CAR_POSITION_OFFSET: usize = 32;
+const F1_23_CAR_POSITION_OFFSET: usize = 30;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+/// Which car the gap of the player is to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapTarget {
+    #[default]
+    Leader,
+    CarAhead,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GapTracker {
+    /// The car, the gap in milliseconds that fills the lights and the seconds of session time
+    /// between updates, only reports when set
+    settings: Option<(GapTarget, u32, f32)>,
+    /// Session time of the last report and what it was
+    reported: Option<(f32, Indicator)>,
+}
+
+impl GapTracker {
+    pub(crate) fn set_target(&mut self, target: GapTarget, full: u32, interval: f32) {
+        self.settings = Some((target, full, interval));
+    }
+
+    /// Reads the player's gap from a lap data packet, the event when it is due for an update.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some((target, full, interval)) = self.settings else {
+            return Ok(None);
+        };
+        let (offsets, position_offset, size) = match game_year {
+            GameYear::F1_23 => (
+                F1_23_GAP_OFFSETS,
+                F1_23_CAR_POSITION_OFFSET,
+                F1_23_LAP_DATA_SIZE,
+            ),
+            GameYear::F1_24 | GameYear::F1_25 => (GAP_OFFSETS, CAR_POSITION_OFFSET, LAP_DATA_SIZE),
+        };
+        let session_time = data
+            .get(SESSION_TIME_OFFSET..SESSION_TIME_OFFSET + 4)
+            .ok_or_else(too_short)?;
+        let session_time = f32::from_le_bytes(session_time.try_into().unwrap());
+
+        let car = player_car(data, size)?;
+        let (ms_offset, minutes_offset) = match target {
+            GapTarget::CarAhead => offsets[0],
+            GapTarget::Leader => offsets[1],
+        };
+        let gap = u32::from(u16::from_le_bytes([car[ms_offset], car[ms_offset + 1]]))
+            + minutes_offset.map_or(0, |offset| u32::from(car[offset]) * 60_000);
+        let gap = match (car[position_offset], target) {
+            // Without a position yet, or leading with nobody ahead
+            (0, _) | (1, GapTarget::CarAhead) => None,
+            (1, GapTarget::Leader) => Some(0),
+            _ => Some(gap),
+        };
+
+        let indicator = Indicator::Gap { gap, full };
+        match self.reported {
+            Some((_, reported)) if reported == indicator => return Ok(None),
+            Some((time, _)) if session_time - time < interval => return Ok(None),
+            _ => {}
+        }
+        self.reported = Some((session_time, indicator));
+        Ok(Some(FlagEvent::Indicator(indicator)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    /// Lap data of the player in `position`, with the gap to the car ahead and to the leader.
+    fn lap_data(session_time: f32, position: u8, ahead: u32, leader: u32) -> Vec<u8> {
+        let mut car = [0; LAP_DATA_SIZE];
+        for ((ms_offset, minutes_offset), gap) in GAP_OFFSETS.into_iter().zip([ahead, leader]) {
+            car[ms_offset..ms_offset + 2].copy_from_slice(&((gap % 60_000) as u16).to_le_bytes());
+            car[minutes_offset.unwrap()] = (gap / 60_000) as u8;
+        }
+        car[CAR_POSITION_OFFSET] = position;
+        let mut data = test_packet(&car);
+        data[SESSION_TIME_OFFSET..SESSION_TIME_OFFSET + 4]
+            .copy_from_slice(&session_time.to_le_bytes());
+        data
+    }
+
+    fn gap(gap: Option<u32>) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Gap { gap, full: 10_000 }))
+    }
+
+    #[test]
+    fn gaps_of_a_minute_and_more_add_the_minutes() {
+        let mut tracker = GapTracker::default();
+        tracker.set_target(GapTarget::Leader, 10_000, 0.0);
+        let packet = lap_data(0.0, 12, 1_500, 65_250);
+        assert_eq!(
+            tracker.lap_data(&packet, GameYear::F1_25).unwrap(),
+            gap(Some(65_250))
+        );
+    }
+
+    #[test]
+    fn the_leader_has_no_car_ahead() {
+        let game_year = GameYear::F1_25;
+        let mut leader = GapTracker::default();
+        leader.set_target(GapTarget::Leader, 10_000, 0.0);
+        let mut ahead = GapTracker::default();
+        ahead.set_target(GapTarget::CarAhead, 10_000, 0.0);
+
+        let packet = lap_data(0.0, 1, 0, 0);
+        assert_eq!(leader.lap_data(&packet, game_year).unwrap(), gap(Some(0)));
+        assert_eq!(ahead.lap_data(&packet, game_year).unwrap(), gap(None));
+        // Nobody has a position before the start
+        let packet = lap_data(1.0, 0, 800, 800);
+        assert_eq!(leader.lap_data(&packet, game_year).unwrap(), gap(None));
+        assert_eq!(ahead.lap_data(&packet, game_year).unwrap(), None);
+    }
+
+    #[test]
+    fn updates_wait_for_the_interval() {
+        let game_year = GameYear::F1_25;
+        let mut tracker = GapTracker::default();
+        tracker.set_target(GapTarget::CarAhead, 10_000, 1.0);
+        let mut update = |session_time, ahead| {
+            tracker
+                .lap_data(&lap_data(session_time, 2, ahead, ahead), game_year)
+                .unwrap()
+        };
+
+        assert_eq!(update(10.0, 2_000), gap(Some(2_000)));
+        assert_eq!(update(10.5, 1_800), None);
+        assert_eq!(update(11.0, 1_700), gap(Some(1_700)));
+        // The same gap is not sent again
+        assert_eq!(update(13.0, 1_700), None);
+    }
+}
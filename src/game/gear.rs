@@ -0,0 +1,55 @@
+//! The player's gear from the car telemetry, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const CAR_TELEMETRY_SIZE: usize = 60;
+const GEAR_OFFSET: usize = 15;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GearTracker {
+    /// Last gear passed on
+    reported: Option<i8>,
+}
+
+impl GearTracker {
+    /// Reads the gear from a car telemetry packet, the event when the player shifted.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+        let gear = car[GEAR_OFFSET] as i8;
+
+        if self.reported == Some(gear) {
+            return Ok(None);
+        }
+        self.reported = Some(gear);
+        Ok(Some(FlagEvent::Indicator(Indicator::Gear(gear))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn telemetry(gear: i8) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[GEAR_OFFSET] = gear as u8;
+        test_packet(&car)
+    }
+
+    #[test]
+    fn reverse_is_below_neutral() {
+        let mut tracker = GearTracker::default();
+        assert_eq!(
+            tracker.car_telemetry(&telemetry(-1)).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::Gear(-1)))
+        );
+        assert_eq!(tracker.car_telemetry(&telemetry(-1)).unwrap(), None);
+        assert_eq!(
+            tracker.car_telemetry(&telemetry(0)).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::Gear(0)))
+        );
+    }
+}
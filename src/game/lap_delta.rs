@@ -0,0 +1,237 @@
+//! The player's lap time against the best lap, from the lap data.
+use std::io;
+
+use super::GameYear;
+use super::HEADER_SIZE;
+use super::player_car;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const SESSION_TIME_OFFSET: usize = 15;
+const LAST_LAP_TIME_OFFSET: usize = 0;
+const CURRENT_LAP_TIME_OFFSET: usize = 4;
+/// Lap distance, lap number and whether the lap is invalid
+const LAP_OFFSETS: [usize; 3] = [20, 33, 37];
+const F1_23_LAP_OFFSETS: [usize; 3] = [18, 31, 35];
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+/// Compares the lap the player is on with the best lap of the player at the same distance.
+///
+/// Every lap is recorded as time against distance and becomes the reference once it is the
+/// fastest valid lap. The session best is the fastest last lap of all cars.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LapDeltaTracker {
+    /// Seconds of session time between updates, only reports when set
+    interval: Option<f32>,
+    lap: u8,
+    invalid: bool,
+    /// Distance and time of the lap so far, in meters and milliseconds
+    trace: Vec<(f32, u32)>,
+    /// Time and trace of the best lap
+    best: Option<(u32, Vec<(f32, u32)>)>,
+    session_best: Option<u32>,
+    /// Session time of the last report and what it was
+    reported: Option<(f32, Indicator)>,
+}
+
+impl LapDeltaTracker {
+    pub(crate) fn set_interval(&mut self, interval: f32) {
+        self.interval = Some(interval);
+    }
+
+    /// Reads the lap times from a lap data packet, the event when the delta is due for an update.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some(interval) = self.interval else {
+            return Ok(None);
+        };
+        let ([distance_offset, lap_offset, invalid_offset], size) = match game_year {
+            GameYear::F1_23 => (F1_23_LAP_OFFSETS, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (LAP_OFFSETS, LAP_DATA_SIZE),
+        };
+        let session_time = data
+            .get(SESSION_TIME_OFFSET..SESSION_TIME_OFFSET + 4)
+            .ok_or_else(too_short)?;
+        let session_time = f32::from_le_bytes(session_time.try_into().unwrap());
+        let u32_at = |car: &[u8], offset: usize| {
+            u32::from_le_bytes(car[offset..offset + 4].try_into().unwrap())
+        };
+        let cars = data.get(HEADER_SIZE..).ok_or_else(too_short)?;
+        for car in cars.chunks_exact(size) {
+            let last_lap = u32_at(car, LAST_LAP_TIME_OFFSET);
+            if last_lap > 0 && self.session_best.is_none_or(|best| last_lap < best) {
+                self.session_best = Some(last_lap);
+            }
+        }
+
+        let car = player_car(data, size)?;
+        let distance = f32::from_le_bytes(
+            car[distance_offset..distance_offset + 4]
+                .try_into()
+                .unwrap(),
+        );
+        let time = u32_at(car, CURRENT_LAP_TIME_OFFSET);
+        let lap = car[lap_offset];
+        if lap != self.lap {
+            let last_lap = u32_at(car, LAST_LAP_TIME_OFFSET);
+            let trace = std::mem::take(&mut self.trace);
+            let faster = self.best.as_ref().is_none_or(|(best, _)| last_lap < *best);
+            // The lap the session started on has no time of its own
+            if self.lap != 0 && !self.invalid && last_lap > 0 && faster {
+                self.best = Some((last_lap, trace));
+            }
+            self.lap = lap;
+            self.invalid = false;
+        }
+        self.invalid |= car[invalid_offset] == 1;
+        // Before crossing the line for the first time the distance is negative
+        if distance >= 0.0 && self.trace.last().is_none_or(|&(last, _)| distance > last) {
+            self.trace.push((distance, time));
+        }
+
+        let indicator = match &self.best {
+            Some((best, trace)) => match delta(trace, distance, time) {
+                Some(delta) => Indicator::LapDelta {
+                    delta: Some(delta),
+                    purple: self.session_best.is_some_and(|session_best| {
+                        i64::from(*best) + i64::from(delta) < i64::from(session_best)
+                    }),
+                },
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        match self.reported {
+            Some((_, reported)) if reported == indicator => return Ok(None),
+            Some((time, _)) if session_time - time < interval => return Ok(None),
+            _ => {}
+        }
+        self.reported = Some((session_time, indicator));
+        Ok(Some(FlagEvent::Indicator(indicator)))
+    }
+}
+
+/// Milliseconds the player is behind the best lap `trace` at `distance`, negative when ahead.
+fn delta(trace: &[(f32, u32)], distance: f32, time: u32) -> Option<i32> {
+    let after = trace.partition_point(|&(d, _)| d < distance);
+    let (d1, t1) = *trace.get(after)?;
+    let (d0, t0) = match after.checked_sub(1) {
+        Some(before) => trace[before],
+        None => (0.0, 0),
+    };
+    let part = match d1 - d0 {
+        0.0 => 1.0,
+        length => (distance - d0) / length,
+    };
+    let best = t0 as f32 + (t1 as f32 - t0 as f32) * part;
+    Some((time as f32 - best) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    /// The player's lap so far, with the time of the last lap.
+    #[derive(Clone, Copy)]
+    struct Lap {
+        number: u8,
+        last: u32,
+        distance: f32,
+        time: u32,
+        invalid: bool,
+    }
+
+    fn lap_data(session_time: f32, lap: Lap) -> Vec<u8> {
+        let [distance_offset, lap_offset, invalid_offset] = LAP_OFFSETS;
+        let mut car = [0; LAP_DATA_SIZE];
+        car[LAST_LAP_TIME_OFFSET..LAST_LAP_TIME_OFFSET + 4]
+            .copy_from_slice(&lap.last.to_le_bytes());
+        car[CURRENT_LAP_TIME_OFFSET..CURRENT_LAP_TIME_OFFSET + 4]
+            .copy_from_slice(&lap.time.to_le_bytes());
+        car[distance_offset..distance_offset + 4].copy_from_slice(&lap.distance.to_le_bytes());
+        car[lap_offset] = lap.number;
+        car[invalid_offset] = u8::from(lap.invalid);
+        let mut data = test_packet(&car);
+        data[SESSION_TIME_OFFSET..SESSION_TIME_OFFSET + 4]
+            .copy_from_slice(&session_time.to_le_bytes());
+        data
+    }
+
+    fn lap(number: u8, last: u32, distance: f32, time: u32) -> Lap {
+        Lap {
+            number,
+            last,
+            distance,
+            time,
+            invalid: false,
+        }
+    }
+
+    fn lap_delta(delta: i32, purple: bool) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::LapDelta {
+            delta: Some(delta),
+            purple,
+        }))
+    }
+
+    #[test]
+    fn delta_is_interpolated_along_the_best_lap() {
+        let trace = [(100.0, 1_000), (200.0, 2_000)];
+        assert_eq!(delta(&trace, 150.0, 1_600), Some(100));
+        // From the line to the first point
+        assert_eq!(delta(&trace, 50.0, 400), Some(-100));
+        assert_eq!(delta(&trace, 200.0, 2_000), Some(0));
+        // Further than the best lap got
+        assert_eq!(delta(&trace, 250.0, 2_500), None);
+    }
+
+    #[test]
+    fn invalid_laps_do_not_become_the_best() {
+        let mut tracker = LapDeltaTracker::default();
+        tracker.set_interval(0.0);
+        let game_year = GameYear::F1_25;
+        let mut update = |lap| tracker.lap_data(&lap_data(0.0, lap), game_year).unwrap();
+
+        assert_eq!(update(lap(1, 0, 0.0, 0)), None);
+        assert_eq!(update(lap(1, 0, 1000.0, 10_000)), None);
+        assert_eq!(update(lap(2, 20_000, 0.0, 0)), lap_delta(0, false));
+        // Ahead of the best lap, which is the fastest of the session
+        let cut = Lap {
+            invalid: true,
+            ..lap(2, 20_000, 500.0, 4_000)
+        };
+        assert_eq!(update(cut), lap_delta(-1_000, true));
+        assert_eq!(update(lap(3, 18_000, 0.0, 0)), lap_delta(0, false));
+        // Still against the first lap
+        assert_eq!(
+            update(lap(3, 18_000, 500.0, 6_000)),
+            lap_delta(1_000, false)
+        );
+    }
+
+    #[test]
+    fn updates_wait_for_the_interval() {
+        let mut tracker = LapDeltaTracker::default();
+        tracker.set_interval(0.5);
+        let game_year = GameYear::F1_25;
+        let mut update = |session_time, lap| {
+            tracker
+                .lap_data(&lap_data(session_time, lap), game_year)
+                .unwrap()
+        };
+
+        update(0.0, lap(1, 0, 1000.0, 10_000));
+        assert_eq!(update(1.0, lap(2, 20_000, 0.0, 0)), lap_delta(0, false));
+        assert_eq!(update(1.2, lap(2, 20_000, 500.0, 5_100)), None);
+        assert_eq!(
+            update(1.5, lap(2, 20_000, 600.0, 6_200)),
+            lap_delta(200, false)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! The fuel left in the player's car from the car status, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const CAR_STATUS_SIZE: usize = 55;
+const FUEL_REMAINING_LAPS_OFFSET: usize = 13;
+
+/// Warns when the fuel left drops below a number of laps.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LowFuelTracker {
+    /// Only warns when set
+    laps: Option<f32>,
+    /// Last state passed on
+    reported: Option<bool>,
+}
+
+impl LowFuelTracker {
+    pub(crate) fn set_laps(&mut self, laps: f32) {
+        self.laps = Some(laps);
+    }
+
+    /// Reads the fuel left from a car status packet, the event when the warning changed.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some(laps) = self.laps else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_STATUS_SIZE)?;
+        let fuel = &car[FUEL_REMAINING_LAPS_OFFSET..FUEL_REMAINING_LAPS_OFFSET + 4];
+        let low = f32::from_le_bytes(fuel.try_into().unwrap()) < laps;
+
+        if self.reported == Some(low) {
+            return Ok(None);
+        }
+        self.reported = Some(low);
+        Ok(Some(FlagEvent::Indicator(Indicator::LowFuel(low))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn status(laps: f32) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[FUEL_REMAINING_LAPS_OFFSET..FUEL_REMAINING_LAPS_OFFSET + 4]
+            .copy_from_slice(&laps.to_le_bytes());
+        test_packet(&car)
+    }
+
+    #[test]
+    fn warns_only_below_the_threshold() {
+        let mut tracker = LowFuelTracker::default();
+        assert_eq!(tracker.car_status(&status(-3.0)).unwrap(), None);
+
+        tracker.set_laps(1.5);
+        assert_eq!(
+            tracker.car_status(&status(1.5)).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::LowFuel(false)))
+        );
+        assert_eq!(
+            tracker.car_status(&status(1.49)).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::LowFuel(true)))
+        );
+        // Short of fuel for the whole race
+        assert_eq!(tracker.car_status(&status(-2.0)).unwrap(), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Lights around the room for the marshal posts of the track, lit by the yellow marshal zones of
+//! the session packet.
+use std::io;
+
+use super::track::marshal_zones;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+/// Most posts there are lights for, one bit each.
+pub const MAX_MARSHAL_POSTS: usize = 32;
+
+/// Lights the posts that cover a yellow marshal zone.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MarshalPostTracker {
+    /// Where every post starts and ends around the lap, in meters
+    posts: Vec<(f32, f32)>,
+    /// Last posts passed on
+    reported: Option<u32>,
+}
+
+impl MarshalPostTracker {
+    pub(crate) fn set_posts(&mut self, posts: Vec<(f32, f32)>) {
+        self.posts = posts;
+    }
+
+    /// Reads the marshal zones from a session packet, the event when other posts are lit.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        if self.posts.is_empty() {
+            return Ok(None);
+        }
+        let (length, zones) = marshal_zones(data)?;
+        if zones.is_empty() || length <= 0.0 {
+            return Ok(None);
+        }
+
+        let mut lit = 0;
+        for (i, &(start, yellow)) in zones.iter().enumerate() {
+            if !yellow {
+                continue;
+            }
+            let end = zones.get(i + 1).map_or(zones[0].0, |zone| zone.0);
+            let zone = (start * length, end * length);
+            for (post, &range) in self.posts.iter().enumerate() {
+                if overlap(zone, range, length) {
+                    lit |= 1 << post;
+                }
+            }
+        }
+
+        if self.reported == Some(lit) {
+            return Ok(None);
+        }
+        self.reported = Some(lit);
+        Ok(Some(FlagEvent::Indicator(Indicator::MarshalPosts(lit))))
+    }
+}
+
+/// Whether two stretches of a lap of `length` meters overlap, either can go across the line.
+fn overlap(a: (f32, f32), b: (f32, f32), length: f32) -> bool {
+    let stretch = |(start, end): (f32, f32)| {
+        let start = start.rem_euclid(length);
+        let covered = (end - start).rem_euclid(length);
+        // Starting and ending at the same place is the whole lap
+        (start, if covered == 0.0 { length } else { covered })
+    };
+    let (a_start, a_length) = stretch(a);
+    let (b_start, b_length) = stretch(b);
+    (b_start - a_start).rem_euclid(length) < a_length
+        || (a_start - b_start).rem_euclid(length) < b_length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::track::test_session;
+
+    const NO_FLAG: i8 = 0;
+    const YELLOW: i8 = 3;
+
+    #[test]
+    fn stretches_overlap_across_the_line() {
+        assert!(overlap((4900.0, 100.0), (50.0, 300.0), 5000.0));
+        assert!(overlap((50.0, 300.0), (4900.0, 100.0), 5000.0));
+        assert!(!overlap((4900.0, 100.0), (200.0, 4800.0), 5000.0));
+        // Distances past the length go around again
+        assert!(overlap((5100.0, 5200.0), (0.0, 150.0), 5000.0));
+        // Ending where it starts covers the whole lap
+        assert!(overlap((1000.0, 1000.0), (3000.0, 3100.0), 5000.0));
+    }
+
+    #[test]
+    fn the_last_zone_ends_at_the_first() {
+        let mut tracker = MarshalPostTracker::default();
+        tracker.set_posts(vec![(0.0, 500.0), (2000.0, 3000.0), (4500.0, 4900.0)]);
+        // The last zone from 4000 meters across the line to 250 meters
+        let zones = [(0.05, NO_FLAG), (0.5, NO_FLAG), (0.8, YELLOW)];
+        assert_eq!(
+            tracker.session(&test_session(5000, &zones)).unwrap(),
+            Some(FlagEvent::Indicator(Indicator::MarshalPosts(0b101)))
+        );
+    }
+
+    #[test]
+    fn nothing_without_posts_or_zones() {
+        let mut tracker = MarshalPostTracker::default();
+        let zones = [(0.0, YELLOW)];
+        assert_eq!(tracker.session(&test_session(5000, &zones)).unwrap(), None);
+        tracker.set_posts(vec![(0.0, 500.0)]);
+        assert_eq!(tracker.session(&test_session(5000, &[])).unwrap(), None);
+        assert_eq!(tracker.session(&test_session(0, &zones)).unwrap(), None);
+    }
+}
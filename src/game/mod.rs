@@ -0,0 +1,699 @@
+//! Support for the packet formats of the different F1 games.
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+use tracing::info;
+
+use crate::event::FlagEvent;
+use ambient::AmbientTracker;
+use board::PositionBoardTracker;
+use board::TimingTowerTracker;
+use collision::CrashTracker;
+use countdown::CountdownTracker;
+use damage::DamageTracker;
+use drs::CAR_TELEMETRY_PACKET;
+use drs::DrsTracker;
+use ers::ErsTracker;
+use gap::GapTracker;
+use gear::GearTracker;
+use lap_delta::LapDeltaTracker;
+use low_fuel::LowFuelTracker;
+use marshal::MarshalPostTracker;
+use motion::MOTION_PACKET;
+use motion::MotionTracker;
+use order::PacketOrder;
+use pit::PitTracker;
+use pit_limiter::PitLimiterTracker;
+use position::PositionTracker;
+use progress::ProgressTracker;
+use rain::RainTracker;
+use rev_lights::RevLightsTracker;
+use rpm::RpmTracker;
+use session::SessionTracker;
+use temperature::TemperatureThresholds;
+use temperature::TemperatureWarningTracker;
+use track::LAP_DATA_PACKET;
+use track::Track;
+use tyre::CAR_DAMAGE_PACKET;
+use tyre::TyreTracker;
+use tyre::TyreWarningTracker;
+use unserved_penalty::UnservedPenaltyTracker;
+
+mod ambient;
+mod board;
+mod collision;
+mod countdown;
+mod damage;
+mod drs;
+mod ers;
+mod f1_23;
+mod f1_24;
+mod gap;
+mod gear;
+mod lap_delta;
+mod low_fuel;
+mod marshal;
+mod motion;
+mod order;
+mod pit;
+mod pit_limiter;
+mod position;
+mod progress;
+mod rain;
+mod rev_lights;
+mod rpm;
+mod session;
+mod stats;
+mod temperature;
+mod track;
+mod tyre;
+mod unserved_penalty;
+
+pub use ambient::AmbientTemperature;
+pub use gap::GapTarget;
+pub use marshal::MAX_MARSHAL_POSTS;
+pub use motion::MotionSource;
+pub(crate) use stats::PacketCounter;
+pub use stats::PacketStatistics;
+
+/// The header shared by all games since F1 23.
+pub(crate) const HEADER_SIZE: usize = 29;
+const PACKET_VERSION_OFFSET: usize = 5;
+const PACKET_ID_OFFSET: usize = 6;
+const SESSION_TIME_OFFSET: usize = 15;
+const PLAYER_CAR_INDEX_OFFSET: usize = 27;
+const SECONDARY_PLAYER_CAR_INDEX_OFFSET: usize = 28;
+/// In the session packet, followed by the index of the spectated car
+const IS_SPECTATING_OFFSET: usize = 44;
+
+/// The game the telemetry comes from, the packet layouts change a little every year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameYear {
+    F1_23,
+    F1_24,
+    #[default]
+    F1_25,
+}
+
+impl FromStr for GameYear {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2023" | "23" => Ok(GameYear::F1_23),
+            "2024" | "24" => Ok(GameYear::F1_24),
+            "2025" | "25" => Ok(GameYear::F1_25),
+            _ => Err(format!(
+                "unsupported game year `{s}`, use 2023, 2024 or 2025"
+            )),
+        }
+    }
+}
+
+impl GameYear {
+    /// The game with the packet format (the `m_packetFormat` header field), if supported.
+    pub fn from_packet_format(format: u16) -> Option<Self> {
+        match format {
+            2023 => Some(GameYear::F1_23),
+            2024 => Some(GameYear::F1_24),
+            2025 => Some(GameYear::F1_25),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameYear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameYear::F1_23 => write!(f, "F1 23"),
+            GameYear::F1_24 => write!(f, "F1 24"),
+            GameYear::F1_25 => write!(f, "F1 25"),
+        }
+    }
+}
+
+/// Translates raw telemetry packets of a game into [`FlagEvent`]s.
+///
+/// Some games need to compare packets with earlier ones, so keep one parser per telemetry stream.
+#[derive(Debug, Clone)]
+pub struct PacketParser {
+    game_year: Option<GameYear>,
+    /// Follow the packet format in the header instead of sticking to `game_year`
+    detect: bool,
+    order: PacketOrder,
+    trackers: Trackers,
+    /// Car on screen while spectating, from the session packets
+    spectated_car: Option<u8>,
+    /// Follow the second player in split-screen instead of the first
+    second_player: bool,
+    /// Packets that failed to parse by packet id
+    failures: BTreeMap<u8, u64>,
+}
+
+/// What the parser follows from packet to packet, set up by the `with_` methods.
+#[derive(Debug, Clone, Default)]
+struct Trackers {
+    /// As the `with_` methods left them, what every session starts from
+    configured: TrackerSet,
+    /// Following the packets of the session
+    current: TrackerSet,
+}
+
+impl Trackers {
+    /// Changes the settings, before the first packet.
+    fn configure(&mut self, configure: impl FnOnce(&mut TrackerSet)) {
+        configure(&mut self.configured);
+        self.reset();
+    }
+
+    /// Forgets everything but the settings, for a new session or game.
+    fn reset(&mut self) {
+        self.current = self.configured.clone();
+    }
+}
+
+/// Everything of the packets that has to be compared with the packets before.
+#[derive(Debug, Clone, Default)]
+struct TrackerSet {
+    /// Last safety car status from the F1 23 session packets
+    safety_car_status: u8,
+    drs: DrsTracker,
+    rev_lights: RevLightsTracker,
+    rpm: RpmTracker,
+    ers: ErsTracker,
+    tyre: TyreTracker,
+    gear: GearTracker,
+    tyre_warning: TyreWarningTracker,
+    damage: DamageTracker,
+    crash: CrashTracker,
+    temperature_warning: TemperatureWarningTracker,
+    pit_limiter: PitLimiterTracker,
+    low_fuel: LowFuelTracker,
+    rain: RainTracker,
+    marshal_posts: MarshalPostTracker,
+    position_board: PositionBoardTracker,
+    timing_tower: TimingTowerTracker,
+    session: SessionTracker,
+    ambient: AmbientTracker,
+    motion: MotionTracker,
+    position: PositionTracker,
+    pit: PitTracker,
+    lap_delta: LapDeltaTracker,
+    gap: GapTracker,
+    progress: ProgressTracker,
+    countdown: CountdownTracker,
+    unserved_penalty: UnservedPenaltyTracker,
+    /// Only set when filtering the flags by the position on track
+    track: Option<Track>,
+}
+
+impl Default for PacketParser {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+impl PacketParser {
+    /// A parser for the packets of a single game.
+    pub fn new(game_year: GameYear) -> Self {
+        Self {
+            game_year: Some(game_year),
+            detect: false,
+            order: PacketOrder::default(),
+            trackers: Trackers::default(),
+            spectated_car: None,
+            second_player: false,
+            failures: BTreeMap::new(),
+        }
+    }
+
+    /// A parser that picks the game from the packet format of every packet.
+    pub fn detect() -> Self {
+        Self {
+            game_year: None,
+            detect: true,
+            ..Self::new(GameYear::default())
+        }
+    }
+
+    /// Only shows yellow when a yellow marshal zone is within `lookahead` meters ahead of the player
+    /// or the player is inside one, the car status yellow covers a lot more of the track.
+    pub fn with_marshal_zones(mut self, lookahead: f32) -> Self {
+        self.trackers.configure(|trackers| {
+            trackers
+                .track
+                .get_or_insert_default()
+                .set_yellow_lookahead(lookahead)
+        });
+        self
+    }
+
+    /// Only shows blue while the car lapping the player is at most `gap` meters behind, the game
+    /// shows it from a lot further and until the car is past.
+    pub fn with_blue_gap(mut self, gap: f32) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.track.get_or_insert_default().set_blue_gap(gap));
+        self
+    }
+
+    /// Lights the rev lights of gear `n` from the first to the second RPM of `gears[n - 1]`,
+    /// instead of following the rev lights of the game.
+    pub fn with_rev_light_gears(mut self, gears: Vec<[u16; 2]>) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.rev_lights.set_gears(gears));
+        self
+    }
+
+    /// Reports the engine speed from idle at 0 to the limiter at 255, past the redline from
+    /// `redline` percent.
+    pub fn with_rpm(mut self, redline: u8) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.rpm.set_redline(redline));
+        self
+    }
+
+    /// Warns once the fuel left drops below `laps`, as shown on the MFD.
+    pub fn with_low_fuel_laps(mut self, laps: f32) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.low_fuel.set_laps(laps));
+        self
+    }
+
+    /// Warns when the forecast gives rain within `minutes` while it is dry, either as the weather
+    /// or as a chance of at least `percentage`.
+    pub fn with_rain_forecast(mut self, minutes: u8, percentage: u8) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.rain.set_forecast(minutes, percentage));
+        self
+    }
+
+    /// Lights the marshal `posts` that cover a yellow marshal zone, each from and to a distance
+    /// around the lap in meters. Only the first [`MAX_MARSHAL_POSTS`] are used.
+    pub fn with_marshal_posts(mut self, mut posts: Vec<(f32, f32)>) -> Self {
+        posts.truncate(MAX_MARSHAL_POSTS);
+        self.trackers
+            .configure(|trackers| trackers.marshal_posts.set_posts(posts));
+        self
+    }
+
+    /// Reports the team of every car in the order of the race, for a light for each car.
+    pub fn with_position_board(mut self) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.position_board.enable());
+        self
+    }
+
+    /// Reports every car in the order of the race as in a battle while less than `battle` seconds
+    /// behind the car ahead and as close while less than `close` seconds behind.
+    pub fn with_timing_tower(mut self, battle: f32, close: f32) -> Self {
+        self.trackers.configure(|trackers| {
+            trackers
+                .timing_tower
+                .set_thresholds((battle * 1000.0) as u32, (close * 1000.0) as u32)
+        });
+        self
+    }
+
+    /// Reports the `temperature` of the session, for lights that show it while no flag is out.
+    pub fn with_ambient_temperature(mut self, temperature: AmbientTemperature) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.ambient.set_temperature(temperature));
+        self
+    }
+
+    /// Reports the `source` as a percentage of `full` km/h or G, in `steps` steps, for lights that
+    /// get brighter with it while no flag is out.
+    pub fn with_motion(mut self, source: MotionSource, full: f32, steps: u8) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.motion.set_motion(source, full, steps));
+        self
+    }
+
+    /// Reports how far the player is ahead or behind their best lap, at most `rate` times a second
+    /// of session time.
+    pub fn with_lap_delta(mut self, rate: f32) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.lap_delta.set_interval(1.0 / rate));
+        self
+    }
+
+    /// Reports the player's gap to the `target`, with the lights full at `full` seconds, at most
+    /// `rate` times a second of session time.
+    pub fn with_gap(mut self, target: GapTarget, full: f32, rate: f32) -> Self {
+        self.trackers.configure(|trackers| {
+            trackers
+                .gap
+                .set_target(target, (full * 1000.0) as u32, 1.0 / rate)
+        });
+        self
+    }
+
+    /// Warns when a tyre wears past the first and second percentage of `wear` or its surface gets
+    /// hotter than the degrees of `temperature`, for every corner on its own when `per_corner` and
+    /// for all at the worst corner otherwise.
+    pub fn with_tyre_warning(
+        mut self,
+        wear: [f32; 2],
+        temperature: [u8; 2],
+        per_corner: bool,
+    ) -> Self {
+        self.trackers.configure(|trackers| {
+            trackers
+                .tyre_warning
+                .set_thresholds(wear, temperature, per_corner)
+        });
+        self
+    }
+
+    /// Warns as the engine or the hottest brake gets hotter than the degrees of `engine` and
+    /// `brakes`, each where amber and where red starts, until it cooled down `hysteresis` degrees
+    /// below that.
+    pub fn with_temperature_warning(
+        mut self,
+        engine: [u16; 2],
+        brakes: [u16; 2],
+        hysteresis: u16,
+    ) -> Self {
+        self.trackers.configure(|trackers| {
+            trackers
+                .temperature_warning
+                .set_thresholds(TemperatureThresholds {
+                    engine,
+                    brakes,
+                    hysteresis,
+                })
+        });
+        self
+    }
+
+    /// Reports sudden damage when a tyre or the front wing gets at least `threshold` percent more
+    /// damaged from one car damage packet to the next, like a puncture or losing the wing.
+    pub fn with_damage_alerts(mut self, threshold: u8) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.damage.set_threshold(threshold));
+        self
+    }
+
+    /// Reports a crash when the player slows down harder than `g_force`, on top of the collisions
+    /// with other cars the game reports.
+    pub fn with_crash_g_force(mut self, g_force: f32) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.crash.set_g_force(g_force));
+        self
+    }
+
+    /// Reports how far into the session the player is, the laps done of a race and the time gone
+    /// by of the other sessions.
+    pub fn with_progress(mut self) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.progress.enable());
+        self
+    }
+
+    /// Counts the formation lap down in `steps`, by how much of the lap the player has left.
+    pub fn with_countdown(mut self, steps: u8) -> Self {
+        self.trackers
+            .configure(|trackers| trackers.countdown.set_steps(steps));
+        self
+    }
+
+    /// Follows the second player in split-screen, the car in the secondary player index of the
+    /// header.
+    pub fn with_second_player(mut self) -> Self {
+        self.second_player = true;
+        self
+    }
+
+    /// Number of packets dropped so far for coming late or twice.
+    pub fn late_packets(&self) -> u64 {
+        self.order.late()
+    }
+
+    /// The unique id of the session the packets are from, `None` before the first session.
+    pub fn session_uid(&self) -> Option<u64> {
+        Some(self.order.session_uid()).filter(|&session_uid| session_uid != 0)
+    }
+
+    /// Number of packets that failed to parse so far by packet id.
+    pub fn parse_failures(&self) -> &BTreeMap<u8, u64> {
+        &self.failures
+    }
+
+    /// The game the packets are parsed as, `None` until the first packet when detecting.
+    pub fn game_year(&self) -> Option<GameYear> {
+        self.game_year
+    }
+
+    /// The events the packet represents, most packets have one at most. Packets that come after a
+    /// newer one with the same id, or twice, have none.
+    ///
+    /// Errors name the packet id, size and header version of the packet.
+    pub fn parse(&mut self, data: &[u8]) -> io::Result<Vec<FlagEvent>> {
+        self.parse_packet(data).map_err(|e| {
+            if let Some(&packet_id) = data.get(PACKET_ID_OFFSET) {
+                *self.failures.entry(packet_id).or_default() += 1;
+            }
+            packet_error(data, &e)
+        })
+    }
+
+    fn parse_packet(&mut self, data: &[u8]) -> io::Result<Vec<FlagEvent>> {
+        if self.detect {
+            let format = packet_format(data)?;
+            let game_year = GameYear::from_packet_format(format).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported packet format {format}"),
+                )
+            })?;
+            if self.game_year != Some(game_year) {
+                // The parser of the first player already says so
+                if !self.second_player {
+                    info!("Detected {game_year} telemetry");
+                }
+                self.game_year = Some(game_year);
+                self.order.reset();
+                self.spectated_car = None;
+                self.trackers.reset();
+            }
+        }
+
+        if !self.order.is_new(data)? {
+            return Ok(Vec::new());
+        }
+
+        let game_year = self.game_year.unwrap_or_default();
+        let packet_id = packet_id(data)?;
+        if packet_id == f1_24::SESSION_PACKET {
+            let spectated_car = spectated_car(data)?;
+            if let Some(car) = spectated_car
+                && self.spectated_car != spectated_car
+                && !self.second_player
+            {
+                info!(car, "Following spectated car");
+            }
+            self.spectated_car = spectated_car;
+        }
+
+        // Everything reads the player from the header, so the spectated car or second player takes
+        // its place there
+        let car = match self.second_player {
+            true => {
+                let car = *data
+                    .get(SECONDARY_PLAYER_CAR_INDEX_OFFSET)
+                    .ok_or_else(too_short)?;
+                // 255 while nobody plays second, like in the menus or when playing alone
+                if usize::from(car) >= MAX_CARS_IN_SESSION {
+                    return Ok(Vec::new());
+                }
+                Some(car)
+            }
+            false => self.spectated_car,
+        };
+        let data = match car {
+            Some(car) => {
+                let mut data = data.to_vec();
+                data[PLAYER_CAR_INDEX_OFFSET] = car;
+                Cow::Owned(data)
+            }
+            None => Cow::Borrowed(data),
+        };
+        let data = &data[..];
+        let trackers = &mut self.trackers.current;
+        if packet_id == CAR_TELEMETRY_PACKET {
+            let drs = trackers.drs.car_telemetry(data)?;
+            let rev_lights = trackers.rev_lights.car_telemetry(data)?;
+            let rpm = trackers.rpm.car_telemetry(data)?;
+            let gear = trackers.gear.car_telemetry(data)?;
+            let tyre_warning = trackers.tyre_warning.car_telemetry(data)?;
+            let temperature_warning = trackers.temperature_warning.car_telemetry(data)?;
+            let motion = trackers.motion.car_telemetry(data)?;
+            return Ok(drs
+                .into_iter()
+                .chain(rev_lights)
+                .chain(rpm)
+                .chain(gear)
+                .chain(tyre_warning)
+                .chain(temperature_warning)
+                .chain(motion)
+                .collect());
+        }
+        if packet_id == MOTION_PACKET {
+            let motion = trackers.motion.motion(data)?;
+            let crash = trackers.crash.motion(data)?;
+            return Ok(motion.into_iter().chain(crash).collect());
+        }
+
+        let mut events: Vec<_> = self
+            .flag_event(data, packet_id, game_year)?
+            .into_iter()
+            .collect();
+        let trackers = &mut self.trackers.current;
+        if packet_id == f1_24::CAR_STATUS_PACKET {
+            trackers.rpm.car_status(data)?;
+            events.extend(trackers.ers.car_status(data)?);
+            events.extend(trackers.tyre.car_status(data)?);
+            events.extend(trackers.pit_limiter.car_status(data)?);
+            events.extend(trackers.low_fuel.car_status(data)?);
+        }
+        if packet_id == f1_24::SESSION_PACKET {
+            events.extend(trackers.session.session(data)?);
+            events.extend(trackers.rain.session(data)?);
+            events.extend(trackers.marshal_posts.session(data)?);
+            events.extend(trackers.ambient.session(data)?);
+            events.extend(trackers.countdown.session(data)?);
+            events.extend(trackers.progress.session(data)?);
+        }
+        if packet_id == CAR_DAMAGE_PACKET {
+            events.extend(trackers.tyre_warning.car_damage(data, game_year)?);
+            events.extend(trackers.damage.car_damage(data, game_year)?);
+        }
+        if packet_id == f1_24::PARTICIPANTS_PACKET {
+            trackers.position_board.participants(data, game_year)?;
+        }
+        if packet_id == LAP_DATA_PACKET {
+            events.extend(trackers.position_board.lap_data(data, game_year)?);
+            events.extend(trackers.timing_tower.lap_data(data, game_year)?);
+            events.extend(trackers.unserved_penalty.lap_data(data, game_year)?);
+            events.extend(trackers.position.lap_data(data, game_year)?);
+            events.extend(trackers.pit.lap_data(data, game_year)?);
+            events.extend(trackers.lap_delta.lap_data(data, game_year)?);
+            events.extend(trackers.gap.lap_data(data, game_year)?);
+            events.extend(trackers.progress.lap_data(data, game_year)?);
+            events.extend(trackers.countdown.lap_data(data, game_year)?);
+        }
+        Ok(events)
+    }
+
+    fn flag_event(
+        &mut self,
+        data: &[u8],
+        packet_id: u8,
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let event = match game_year {
+            GameYear::F1_23 => f1_23::parse(data, &mut self.trackers.current.safety_car_status)?,
+            GameYear::F1_24 => f1_24::parse(data)?,
+            GameYear::F1_25 => parse_f1_25(data)?,
+        };
+
+        match packet_id {
+            f1_24::CAR_STATUS_PACKET => self.trackers.current.drs.car_status(data)?,
+            _ if event == Some(FlagEvent::Reset) => self.trackers.reset(),
+            _ => {}
+        }
+
+        let Some(track) = &mut self.trackers.current.track else {
+            return Ok(event);
+        };
+        match (packet_id, event) {
+            (_, Some(event @ (FlagEvent::SetLocalFlag(_) | FlagEvent::ResetLocalFlag))) => {
+                Ok(Some(track.car_status(event)))
+            }
+            // An F1 23 session packet can carry a safety car change as well
+            (_, Some(event)) => Ok(Some(event)),
+            (f1_24::SESSION_PACKET, None) => track.session(data),
+            (LAP_DATA_PACKET, None) => track.lap_data(data, game_year),
+            (_, None) => Ok(None),
+        }
+    }
+}
+
+/// Reads the packet format (the year of the game) from the shared header.
+pub(crate) fn packet_format(data: &[u8]) -> io::Result<u16> {
+    match data.first_chunk() {
+        Some(&format) => Ok(u16::from_le_bytes(format)),
+        None => Err(too_short()),
+    }
+}
+
+/// The error with the packet it came from, as far as the header is there.
+fn packet_error(data: &[u8], error: &io::Error) -> io::Error {
+    let length = data.len();
+    let message = match (
+        packet_format(data),
+        data.get(PACKET_VERSION_OFFSET),
+        packet_id(data),
+    ) {
+        (Ok(format), Some(version), Ok(packet_id)) => {
+            format!(
+                "packet {packet_id} of {length} bytes, format {format} version {version}: {error}"
+            )
+        }
+        _ => format!("packet of {length} bytes: {error}"),
+    };
+    io::Error::new(error.kind(), message)
+}
+
+/// Reads the seconds since the start of the session from the shared header.
+pub(crate) fn session_time(data: &[u8]) -> io::Result<f32> {
+    match data.get(SESSION_TIME_OFFSET..SESSION_TIME_OFFSET + 4) {
+        Some(time) => Ok(f32::from_le_bytes(time.try_into().unwrap())),
+        None => Err(too_short()),
+    }
+}
+
+/// Reads the packet id from the shared header.
+pub(crate) fn packet_id(data: &[u8]) -> io::Result<u8> {
+    if data.len() < HEADER_SIZE {
+        return Err(too_short());
+    }
+    Ok(data[PACKET_ID_OFFSET])
+}
+
+/// The index of the car being watched from a session packet, `None` when not spectating.
+fn spectated_car(data: &[u8]) -> io::Result<Option<u8>> {
+    match data.get(IS_SPECTATING_OFFSET..IS_SPECTATING_OFFSET + 2) {
+        Some(&[1, car]) => Ok(Some(car)),
+        Some(_) => Ok(None),
+        None => Err(too_short()),
+    }
+}
+
+/// The data of the player's car in a packet with `car_size` bytes per car.
+pub(crate) fn player_car(data: &[u8], car_size: usize) -> io::Result<&[u8]> {
+    let player = usize::from(*data.get(PLAYER_CAR_INDEX_OFFSET).ok_or_else(too_short)?);
+    let car = HEADER_SIZE + player * car_size;
+    data.get(car..car + car_size).ok_or_else(too_short)
+}
+
+pub(crate) fn too_short() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "packet too short")
+}
+
+/// A packet with `cars` right after the header, the player in the first of them.
+#[cfg(test)]
+pub(crate) fn test_packet(cars: &[u8]) -> Vec<u8> {
+    [&[0; HEADER_SIZE][..], cars].concat()
+}
+
+/// Parses the packet with the F1 25 models.
+pub(crate) fn parse_f1_25(data: &[u8]) -> io::Result<Option<FlagEvent>> {
+    f1_game_library_models_25::deserialise_udp_packet_from_bytes(data)
+        .map(|packet| FlagEvent::from_packet(&packet))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
@@ -0,0 +1,133 @@
+//! How fast the player goes or how hard they accelerate and brake, from the car telemetry and the
+//! motion packets, laid out the same since F1 23.
+use std::io;
+
+use serde::Deserialize;
+
+use super::player_car;
+use crate::event::FlagEvent;
+
+pub(crate) const MOTION_PACKET: u8 = 0;
+const CAR_MOTION_SIZE: usize = 60;
+const G_FORCE_LONGITUDINAL_OFFSET: usize = 40;
+const CAR_TELEMETRY_SIZE: usize = 60;
+const SPEED_OFFSET: usize = 0;
+
+/// What the lights follow while no flag is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotionSource {
+    /// The speed in km/h
+    Speed,
+    /// The longitudinal G force, braking as much as accelerating
+    GForce,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MotionTracker {
+    /// What to follow, the speed or G force at full brightness and the number of steps to it, only
+    /// reports when set
+    settings: Option<(MotionSource, f32, u8)>,
+    /// Last percentage passed on
+    reported: Option<u8>,
+}
+
+impl MotionTracker {
+    pub(crate) fn set_motion(&mut self, source: MotionSource, full: f32, steps: u8) {
+        self.settings = Some((source, full, steps.max(1)));
+    }
+
+    /// Reads the speed from a car telemetry packet, the event when it took the lights to another
+    /// step.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some((MotionSource::Speed, full, steps)) = self.settings else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+        let speed = u16::from_le_bytes([car[SPEED_OFFSET], car[SPEED_OFFSET + 1]]);
+        Ok(self.report(f32::from(speed) / full, steps))
+    }
+
+    /// Reads the longitudinal G force from a motion packet, the event when it took the lights to
+    /// another step.
+    pub(crate) fn motion(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some((MotionSource::GForce, full, steps)) = self.settings else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_MOTION_SIZE)?;
+        let g_force = &car[G_FORCE_LONGITUDINAL_OFFSET..G_FORCE_LONGITUDINAL_OFFSET + 4];
+        let g_force = f32::from_le_bytes(g_force.try_into().unwrap());
+        Ok(self.report(g_force.abs() / full, steps))
+    }
+
+    fn report(&mut self, part: f32, steps: u8) -> Option<FlagEvent> {
+        let step = (part.clamp(0.0, 1.0) * f32::from(steps)).floor() as u8;
+        let percent = (u16::from(step) * 100 / u16::from(steps)) as u8;
+        if self.reported == Some(percent) {
+            return None;
+        }
+        self.reported = Some(percent);
+        Some(FlagEvent::Motion(percent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn car_telemetry(speed: u16) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[SPEED_OFFSET..SPEED_OFFSET + 2].copy_from_slice(&speed.to_le_bytes());
+        test_packet(&car)
+    }
+
+    fn motion(g_force: f32) -> Vec<u8> {
+        let mut car = [0; CAR_MOTION_SIZE];
+        car[G_FORCE_LONGITUDINAL_OFFSET..G_FORCE_LONGITUDINAL_OFFSET + 4]
+            .copy_from_slice(&g_force.to_le_bytes());
+        test_packet(&car)
+    }
+
+    #[test]
+    fn speed_goes_in_steps() {
+        let mut tracker = MotionTracker::default();
+        tracker.set_motion(MotionSource::Speed, 300.0, 4);
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(150)).unwrap(),
+            Some(FlagEvent::Motion(50))
+        );
+        assert_eq!(tracker.car_telemetry(&car_telemetry(220)).unwrap(), None);
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(350)).unwrap(),
+            Some(FlagEvent::Motion(100))
+        );
+        assert_eq!(tracker.motion(&motion(1.0)).unwrap(), None);
+    }
+
+    #[test]
+    fn braking_counts_as_much_as_accelerating() {
+        let mut tracker = MotionTracker::default();
+        tracker.set_motion(MotionSource::GForce, 4.0, 4);
+        assert_eq!(
+            tracker.motion(&motion(-3.0)).unwrap(),
+            Some(FlagEvent::Motion(75))
+        );
+        assert_eq!(tracker.motion(&motion(3.0)).unwrap(), None);
+        assert_eq!(tracker.car_telemetry(&car_telemetry(300)).unwrap(), None);
+    }
+
+    #[test]
+    fn there_is_always_a_step() {
+        let mut tracker = MotionTracker::default();
+        tracker.set_motion(MotionSource::Speed, 300.0, 0);
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(299)).unwrap(),
+            Some(FlagEvent::Motion(0))
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(300)).unwrap(),
+            Some(FlagEvent::Motion(100))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Dropping packets that arrive late or twice, UDP does not keep them in order.
+use std::io;
+
+use tracing::debug;
+
+use super::packet_id;
+
+const SESSION_UID_OFFSET: usize = 7;
+/// Unlike the frame identifier it does not go back after a flashback
+const OVERALL_FRAME_IDENTIFIER_OFFSET: usize = 23;
+/// A frame can have more than one event packet
+const EVENT_PACKET: u8 = 3;
+/// The packet ids of the games so far, with room to spare
+const PACKET_IDS: usize = 32;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PacketOrder {
+    session_uid: u64,
+    /// Overall frame identifier of the newest packet of every id
+    frames: [Option<u32>; PACKET_IDS],
+    /// The event packets of the newest frame with events
+    events: Vec<Vec<u8>>,
+    /// Packets dropped so far
+    late: u64,
+}
+
+impl PacketOrder {
+    /// Forgets the packets seen, for another game.
+    pub(crate) fn reset(&mut self) {
+        self.session_uid = 0;
+        self.frames = [None; PACKET_IDS];
+        self.events.clear();
+    }
+
+    /// The unique id of the session of the newest packets, 0 before any.
+    pub(crate) fn session_uid(&self) -> u64 {
+        self.session_uid
+    }
+
+    /// Number of packets dropped for coming late or twice.
+    pub(crate) fn late(&self) -> u64 {
+        self.late
+    }
+
+    /// Whether the packet is newer than the last one with its id, `false` for packets that came
+    /// late or twice.
+    pub(crate) fn is_new(&mut self, data: &[u8]) -> io::Result<bool> {
+        // Also checks the header is there
+        let id = packet_id(data)?;
+        let session_uid = u64::from_le_bytes(
+            data[SESSION_UID_OFFSET..SESSION_UID_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+        let frame = u32::from_le_bytes(
+            data[OVERALL_FRAME_IDENTIFIER_OFFSET..OVERALL_FRAME_IDENTIFIER_OFFSET + 4]
+                .try_into()
+                .unwrap(),
+        );
+        if session_uid != self.session_uid {
+            self.reset();
+            self.session_uid = session_uid;
+        }
+
+        let Some(last) = self.frames.get_mut(usize::from(id)) else {
+            return Ok(true);
+        };
+        let new = match *last {
+            Some(last) if frame < last => false,
+            Some(last) if frame == last && id == EVENT_PACKET => {
+                let new = !self.events.iter().any(|event| event.as_slice() == data);
+                if new {
+                    self.events.push(data.to_vec());
+                }
+                new
+            }
+            Some(last) if frame == last => false,
+            _ => {
+                *last = Some(frame);
+                if id == EVENT_PACKET {
+                    self.events.clear();
+                    self.events.push(data.to_vec());
+                }
+                true
+            }
+        };
+        if !new {
+            self.late += 1;
+            debug!(
+                packet_id = id,
+                frame, "Dropping packet that came late or twice"
+            );
+        }
+        Ok(new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::HEADER_SIZE;
+    use crate::game::PACKET_ID_OFFSET;
+
+    fn packet(id: u8, session_uid: u64, frame: u32) -> Vec<u8> {
+        let mut data = vec![0; HEADER_SIZE];
+        data[PACKET_ID_OFFSET] = id;
+        data[SESSION_UID_OFFSET..SESSION_UID_OFFSET + 8]
+            .copy_from_slice(&session_uid.to_le_bytes());
+        data[OVERALL_FRAME_IDENTIFIER_OFFSET..OVERALL_FRAME_IDENTIFIER_OFFSET + 4]
+            .copy_from_slice(&frame.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn late_and_repeated_packets_are_dropped_per_id() {
+        let mut order = PacketOrder::default();
+        assert!(order.is_new(&packet(6, 1, 10)).unwrap());
+        assert!(!order.is_new(&packet(6, 1, 10)).unwrap());
+        assert!(!order.is_new(&packet(6, 1, 9)).unwrap());
+        // Another id has frames of its own
+        assert!(order.is_new(&packet(7, 1, 9)).unwrap());
+        assert!(order.is_new(&packet(6, 1, 11)).unwrap());
+        assert_eq!(order.late(), 2);
+    }
+
+    #[test]
+    fn events_of_one_frame_are_only_dropped_when_the_same() {
+        let mut order = PacketOrder::default();
+        let first = packet(EVENT_PACKET, 1, 10);
+        let mut second = first.clone();
+        second.push(1);
+        assert!(order.is_new(&first).unwrap());
+        assert!(order.is_new(&second).unwrap());
+        assert!(!order.is_new(&first).unwrap());
+        assert!(!order.is_new(&packet(EVENT_PACKET, 1, 9)).unwrap());
+    }
+
+    #[test]
+    fn a_new_session_starts_the_frames_over() {
+        let mut order = PacketOrder::default();
+        assert!(order.is_new(&packet(6, 1, 5000)).unwrap());
+        assert!(order.is_new(&packet(6, 2, 1)).unwrap());
+        assert_eq!(order.session_uid(), 2);
+        assert_eq!(order.late(), 0);
+    }
+}
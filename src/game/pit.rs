@@ -0,0 +1,84 @@
+//! Whether the player is in the pit lane or stopped in the box, from the lap data.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::flag::PitStatus;
+
+/// After the lap number
+const PIT_STATUS_OFFSET: usize = 34;
+const F1_23_PIT_STATUS_OFFSET: usize = 32;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PitTracker {
+    /// Last status passed on
+    reported: Option<PitStatus>,
+}
+
+impl PitTracker {
+    /// Reads the pit status from a lap data packet, the event when it changed.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_PIT_STATUS_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (PIT_STATUS_OFFSET, LAP_DATA_SIZE),
+        };
+        let status = match player_car(data, size)?[offset] {
+            1 => PitStatus::PitLane,
+            2 => PitStatus::PitStop,
+            _ => PitStatus::None,
+        };
+        if self.reported == Some(status) {
+            return Ok(None);
+        }
+        self.reported = Some(status);
+        Ok(Some(FlagEvent::PitStatus(status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn lap_data(game_year: GameYear, status: u8) -> Vec<u8> {
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_PIT_STATUS_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (PIT_STATUS_OFFSET, LAP_DATA_SIZE),
+        };
+        let mut car = vec![0; size];
+        car[offset] = status;
+        test_packet(&car)
+    }
+
+    #[test]
+    fn pit_lane_then_stop_then_out() {
+        for game_year in [GameYear::F1_23, GameYear::F1_25] {
+            let mut tracker = PitTracker::default();
+            let mut status = |status| tracker.lap_data(&lap_data(game_year, status), game_year);
+            assert_eq!(
+                status(0).unwrap(),
+                Some(FlagEvent::PitStatus(PitStatus::None))
+            );
+            assert_eq!(
+                status(1).unwrap(),
+                Some(FlagEvent::PitStatus(PitStatus::PitLane))
+            );
+            assert_eq!(status(1).unwrap(), None);
+            assert_eq!(
+                status(2).unwrap(),
+                Some(FlagEvent::PitStatus(PitStatus::PitStop))
+            );
+            assert_eq!(
+                status(0).unwrap(),
+                Some(FlagEvent::PitStatus(PitStatus::None))
+            );
+        }
+    }
+}
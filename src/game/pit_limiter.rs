@@ -0,0 +1,54 @@
+//! The player's pit limiter from the car status, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+
+const CAR_STATUS_SIZE: usize = 55;
+const PIT_LIMITER_STATUS_OFFSET: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PitLimiterTracker {
+    /// Last state passed on
+    reported: Option<bool>,
+}
+
+impl PitLimiterTracker {
+    /// Reads the pit limiter from a car status packet, the event when it was turned on or off.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let car = player_car(data, CAR_STATUS_SIZE)?;
+        let on = car[PIT_LIMITER_STATUS_OFFSET] == 1;
+        if self.reported == Some(on) {
+            return Ok(None);
+        }
+        self.reported = Some(on);
+        Ok(Some(FlagEvent::PitLimiter(on)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn status(limiter: u8) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[PIT_LIMITER_STATUS_OFFSET] = limiter;
+        test_packet(&car)
+    }
+
+    #[test]
+    fn only_turning_it_on_or_off_is_passed_on() {
+        let mut tracker = PitLimiterTracker::default();
+        assert_eq!(
+            tracker.car_status(&status(0)).unwrap(),
+            Some(FlagEvent::PitLimiter(false))
+        );
+        assert_eq!(tracker.car_status(&status(0)).unwrap(), None);
+        assert_eq!(
+            tracker.car_status(&status(1)).unwrap(),
+            Some(FlagEvent::PitLimiter(true))
+        );
+        assert_eq!(tracker.car_status(&status(1)).unwrap(), None);
+    }
+}
@@ -0,0 +1,72 @@
+//! The player's race position from the lap data.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use crate::event::FlagEvent;
+
+/// After the distances and the safety car delta
+const CAR_POSITION_OFFSET: usize = 32;
+const F1_23_CAR_POSITION_OFFSET: usize = 30;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PositionTracker {
+    /// Last position seen, 0 before the first lap data
+    position: u8,
+}
+
+impl PositionTracker {
+    /// Reads the position from a lap data packet, the event when the player gained or lost places.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_CAR_POSITION_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (CAR_POSITION_OFFSET, LAP_DATA_SIZE),
+        };
+        let position = player_car(data, size)?[offset];
+        let previous = std::mem::replace(&mut self.position, position);
+        if previous == 0 || position == 0 || position == previous {
+            return Ok(None);
+        }
+        Ok(Some(FlagEvent::PositionChange {
+            gained: position < previous,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn lap_data(position: u8) -> Vec<u8> {
+        let mut car = [0; LAP_DATA_SIZE];
+        car[CAR_POSITION_OFFSET] = position;
+        test_packet(&car)
+    }
+
+    #[test]
+    fn only_changes_between_known_positions_count() {
+        let mut tracker = PositionTracker::default();
+        let game_year = GameYear::F1_25;
+        // The first position the game gives is where the player starts
+        assert_eq!(tracker.lap_data(&lap_data(5), game_year).unwrap(), None);
+        assert_eq!(
+            tracker.lap_data(&lap_data(4), game_year).unwrap(),
+            Some(FlagEvent::PositionChange { gained: true })
+        );
+        assert_eq!(tracker.lap_data(&lap_data(4), game_year).unwrap(), None);
+        assert_eq!(
+            tracker.lap_data(&lap_data(7), game_year).unwrap(),
+            Some(FlagEvent::PositionChange { gained: false })
+        );
+        // No position while the game is loading, and none coming back from it
+        assert_eq!(tracker.lap_data(&lap_data(0), game_year).unwrap(), None);
+        assert_eq!(tracker.lap_data(&lap_data(3), game_year).unwrap(), None);
+    }
+}
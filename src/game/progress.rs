@@ -0,0 +1,183 @@
+//! How far into the session the player is, by laps in a race and by time otherwise.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const TOTAL_LAPS_OFFSET: usize = 32;
+const SESSION_TYPE_OFFSET: usize = 35;
+const SESSION_TIME_LEFT_OFFSET: usize = 38;
+const SESSION_DURATION_OFFSET: usize = 40;
+const CURRENT_LAP_OFFSET: usize = 33;
+const F1_23_CURRENT_LAP_OFFSET: usize = 31;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProgressTracker {
+    /// Only reports when set
+    enabled: bool,
+    /// Laps of the race, `None` outside of races
+    total_laps: Option<u8>,
+    /// Last percentage passed on
+    reported: Option<u8>,
+}
+
+impl ProgressTracker {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Reads the type and length of the session from a session packet, the event when the time
+    /// gone by outside of a race changed the progress.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let u16_at = |offset: usize| {
+            data.get(offset..offset + 2)
+                .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or_else(too_short)
+        };
+        let total_laps = *data.get(TOTAL_LAPS_OFFSET).ok_or_else(too_short)?;
+        let race = matches!(
+            *data.get(SESSION_TYPE_OFFSET).ok_or_else(too_short)?,
+            15..=17
+        );
+        if race && total_laps > 0 {
+            self.total_laps = Some(total_laps);
+            return Ok(None);
+        }
+        self.total_laps = None;
+
+        let left = u32::from(u16_at(SESSION_TIME_LEFT_OFFSET)?);
+        let duration = u32::from(u16_at(SESSION_DURATION_OFFSET)?);
+        // Time trials go on for as long as they like
+        if duration == 0 {
+            return Ok(None);
+        }
+        Ok(self.report(duration.saturating_sub(left) * 100 / duration))
+    }
+
+    /// Reads the lap of the player from a lap data packet, the event when a race lap was done.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let Some(total_laps) = self.total_laps else {
+            return Ok(None);
+        };
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_CURRENT_LAP_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (CURRENT_LAP_OFFSET, LAP_DATA_SIZE),
+        };
+        let lap = player_car(data, size)?[offset];
+        // The lap the player is on is not done yet
+        let done = u32::from(lap.saturating_sub(1));
+        Ok(self.report(done * 100 / u32::from(total_laps)))
+    }
+
+    fn report(&mut self, percent: u32) -> Option<FlagEvent> {
+        let percent = percent.min(100) as u8;
+        if self.reported == Some(percent) {
+            return None;
+        }
+        self.reported = Some(percent);
+        Some(FlagEvent::Indicator(Indicator::Progress(percent)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    const QUALIFYING: u8 = 5;
+    const RACE: u8 = 15;
+    const TIME_TRIAL: u8 = 18;
+
+    fn session(session_type: u8, total_laps: u8, duration: u16, left: u16) -> Vec<u8> {
+        let mut data = vec![0; SESSION_DURATION_OFFSET + 2];
+        data[TOTAL_LAPS_OFFSET] = total_laps;
+        data[SESSION_TYPE_OFFSET] = session_type;
+        data[SESSION_TIME_LEFT_OFFSET..SESSION_TIME_LEFT_OFFSET + 2]
+            .copy_from_slice(&left.to_le_bytes());
+        data[SESSION_DURATION_OFFSET..SESSION_DURATION_OFFSET + 2]
+            .copy_from_slice(&duration.to_le_bytes());
+        data
+    }
+
+    fn lap_data(lap: u8) -> Vec<u8> {
+        let mut car = [0; LAP_DATA_SIZE];
+        car[CURRENT_LAP_OFFSET] = lap;
+        test_packet(&car)
+    }
+
+    fn progress(percent: u8) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Progress(percent)))
+    }
+
+    #[test]
+    fn race_laps_round_down_and_stop_at_the_end() {
+        let mut tracker = ProgressTracker::default();
+        tracker.enable();
+        let game_year = GameYear::F1_25;
+        assert_eq!(tracker.session(&session(RACE, 3, 0, 0)).unwrap(), None);
+        assert_eq!(
+            tracker.lap_data(&lap_data(2), game_year).unwrap(),
+            progress(33)
+        );
+        assert_eq!(
+            tracker.lap_data(&lap_data(3), game_year).unwrap(),
+            progress(66)
+        );
+        // The cool-down lap after the flag
+        assert_eq!(
+            tracker.lap_data(&lap_data(4), game_year).unwrap(),
+            progress(100)
+        );
+        assert_eq!(tracker.lap_data(&lap_data(5), game_year).unwrap(), None);
+    }
+
+    #[test]
+    fn time_counts_without_race_laps() {
+        let mut tracker = ProgressTracker::default();
+        tracker.enable();
+        let game_year = GameYear::F1_25;
+        // A timed race counts the time as well
+        assert_eq!(
+            tracker.session(&session(RACE, 0, 3600, 2700)).unwrap(),
+            progress(25)
+        );
+        assert_eq!(tracker.lap_data(&lap_data(2), game_year).unwrap(), None);
+        assert_eq!(
+            tracker
+                .session(&session(QUALIFYING, 0, 1080, 1200))
+                .unwrap(),
+            progress(0)
+        );
+        // Time trials have no end
+        assert_eq!(
+            tracker.session(&session(TIME_TRIAL, 0, 0, 0)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn nothing_until_enabled() {
+        let mut tracker = ProgressTracker::default();
+        assert_eq!(
+            tracker.session(&session(QUALIFYING, 0, 1080, 540)).unwrap(),
+            None
+        );
+        assert_eq!(tracker.session(&session(RACE, 3, 0, 0)).unwrap(), None);
+        assert_eq!(
+            tracker.lap_data(&lap_data(2), GameYear::F1_25).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Rain in the weather forecast of the session packet, laid out the same since F1 23.
+use std::io;
+
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const WEATHER_OFFSET: usize = 29;
+const SESSION_TYPE_OFFSET: usize = 35;
+/// After the marshal zones, the safety car status and whether it is an online game
+const NUM_FORECAST_SAMPLES_OFFSET: usize = 155;
+const FORECAST_SAMPLES_OFFSET: usize = 156;
+const FORECAST_SAMPLE_SIZE: usize = 8;
+const SAMPLE_TIME_OFFSET: usize = 1;
+const SAMPLE_WEATHER_OFFSET: usize = 2;
+const SAMPLE_RAIN_PERCENTAGE_OFFSET: usize = 7;
+/// Light rain, heavy rain and storm come after clear and the clouds
+const WEATHER_LIGHT_RAIN: u8 = 3;
+
+/// Warns when the forecast has rain coming soon while it is still dry.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RainTracker {
+    /// Minutes ahead and chance of rain that warn, only warns when set
+    forecast: Option<(u8, u8)>,
+    /// Last state passed on
+    reported: Option<bool>,
+}
+
+impl RainTracker {
+    pub(crate) fn set_forecast(&mut self, minutes: u8, percentage: u8) {
+        self.forecast = Some((minutes, percentage));
+    }
+
+    /// Reads the weather and forecast from a session packet, the event when the warning changed.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some((minutes, percentage)) = self.forecast else {
+            return Ok(None);
+        };
+        let count = usize::from(
+            *data
+                .get(NUM_FORECAST_SAMPLES_OFFSET)
+                .ok_or_else(too_short)?,
+        );
+        let samples = data.get(FORECAST_SAMPLES_OFFSET..).ok_or_else(too_short)?;
+        let session_type = data[SESSION_TYPE_OFFSET];
+
+        // The forecast covers the next sessions as well
+        let rain_coming = samples
+            .chunks_exact(FORECAST_SAMPLE_SIZE)
+            .take(count)
+            .filter(|sample| sample[0] == session_type && sample[SAMPLE_TIME_OFFSET] <= minutes)
+            .any(|sample| {
+                sample[SAMPLE_WEATHER_OFFSET] >= WEATHER_LIGHT_RAIN
+                    || sample[SAMPLE_RAIN_PERCENTAGE_OFFSET] >= percentage
+            });
+        let warn = rain_coming && data[WEATHER_OFFSET] < WEATHER_LIGHT_RAIN;
+
+        if self.reported == Some(warn) {
+            return Ok(None);
+        }
+        self.reported = Some(warn);
+        Ok(Some(FlagEvent::Indicator(Indicator::Rain(warn))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RACE: u8 = 15;
+    const OVERCAST: u8 = 2;
+
+    /// A session packet of a race in `weather`, with forecast samples of the session type, minutes
+    /// ahead, weather and rain percentage.
+    fn session(weather: u8, samples: &[(u8, u8, u8, u8)]) -> Vec<u8> {
+        let mut data = vec![0; FORECAST_SAMPLES_OFFSET + samples.len() * FORECAST_SAMPLE_SIZE];
+        data[WEATHER_OFFSET] = weather;
+        data[SESSION_TYPE_OFFSET] = RACE;
+        data[NUM_FORECAST_SAMPLES_OFFSET] = samples.len() as u8;
+        for (i, &(session_type, minutes, weather, percentage)) in samples.iter().enumerate() {
+            let sample = FORECAST_SAMPLES_OFFSET + i * FORECAST_SAMPLE_SIZE;
+            data[sample] = session_type;
+            data[sample + SAMPLE_TIME_OFFSET] = minutes;
+            data[sample + SAMPLE_WEATHER_OFFSET] = weather;
+            data[sample + SAMPLE_RAIN_PERCENTAGE_OFFSET] = percentage;
+        }
+        data
+    }
+
+    fn rain(coming: bool) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Rain(coming)))
+    }
+
+    #[test]
+    fn only_rain_within_the_window_warns() {
+        let mut tracker = RainTracker::default();
+        tracker.set_forecast(10, 60);
+        // Rain in 15 minutes is too far out
+        let packet = session(
+            0,
+            &[(RACE, 5, OVERCAST, 20), (RACE, 15, WEATHER_LIGHT_RAIN, 0)],
+        );
+        assert_eq!(tracker.session(&packet).unwrap(), rain(false));
+        // Right at the end of the window
+        let packet = session(
+            0,
+            &[(RACE, 5, OVERCAST, 20), (RACE, 10, WEATHER_LIGHT_RAIN, 0)],
+        );
+        assert_eq!(tracker.session(&packet).unwrap(), rain(true));
+        // A high enough chance keeps the warning, even with the weather staying dry
+        let packet = session(0, &[(RACE, 5, OVERCAST, 60)]);
+        assert_eq!(tracker.session(&packet).unwrap(), None);
+    }
+
+    #[test]
+    fn other_sessions_and_samples_past_the_count_do_not_warn() {
+        let mut tracker = RainTracker::default();
+        tracker.set_forecast(10, 60);
+        let mut packet = session(
+            0,
+            &[(RACE, 5, OVERCAST, 0), (RACE, 5, WEATHER_LIGHT_RAIN, 0)],
+        );
+        packet[NUM_FORECAST_SAMPLES_OFFSET] = 1;
+        assert_eq!(tracker.session(&packet).unwrap(), rain(false));
+        // The qualifying before the race
+        let packet = session(0, &[(5, 0, WEATHER_LIGHT_RAIN, 100)]);
+        assert_eq!(tracker.session(&packet).unwrap(), None);
+    }
+
+    #[test]
+    fn no_warning_once_it_rains() {
+        let mut tracker = RainTracker::default();
+        let packet = session(0, &[(RACE, 0, WEATHER_LIGHT_RAIN, 100)]);
+        assert_eq!(tracker.session(&packet).unwrap(), None);
+
+        tracker.set_forecast(5, 50);
+        assert_eq!(tracker.session(&packet).unwrap(), rain(true));
+        let packet = session(WEATHER_LIGHT_RAIN, &[(RACE, 0, WEATHER_LIGHT_RAIN, 100)]);
+        assert_eq!(tracker.session(&packet).unwrap(), rain(false));
+    }
+}
@@ -0,0 +1,112 @@
+//! The player's rev lights from the car telemetry, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const CAR_TELEMETRY_SIZE: usize = 60;
+const GEAR_OFFSET: usize = 15;
+const ENGINE_RPM_OFFSET: usize = 16;
+const REV_LIGHTS_PERCENT_OFFSET: usize = 19;
+
+/// Turns the engine speed into how far the rev lights are lit.
+///
+/// Gears with thresholds light up from the first to the second RPM, the others follow the rev
+/// lights percentage of the game.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RevLightsTracker {
+    /// RPM where the lights start and where they are full, starting at first gear
+    gears: Vec<[u16; 2]>,
+    /// Last percentage passed on
+    reported: Option<u8>,
+}
+
+impl RevLightsTracker {
+    pub(crate) fn set_gears(&mut self, gears: Vec<[u16; 2]>) {
+        self.gears = gears;
+    }
+
+    /// Reads the engine speed from a car telemetry packet, the event when the lights changed.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+
+        let gear = car[GEAR_OFFSET] as i8;
+        let rpm = u16::from_le_bytes([car[ENGINE_RPM_OFFSET], car[ENGINE_RPM_OFFSET + 1]]);
+        let thresholds = usize::try_from(gear)
+            .ok()
+            .and_then(|gear| gear.checked_sub(1))
+            .and_then(|gear| self.gears.get(gear));
+        let percent = match thresholds {
+            Some(&[start, full]) if full > start => {
+                let lit = f32::from(rpm.saturating_sub(start)) / f32::from(full - start);
+                (lit.min(1.0) * 100.0) as u8
+            }
+            Some(&[start, _]) => 100 * u8::from(rpm >= start),
+            None => car[REV_LIGHTS_PERCENT_OFFSET].min(100),
+        };
+
+        if self.reported == Some(percent) {
+            return Ok(None);
+        }
+        self.reported = Some(percent);
+        Ok(Some(FlagEvent::Indicator(Indicator::RevLights(percent))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn car_telemetry(gear: i8, rpm: u16, percent: u8) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[GEAR_OFFSET] = gear as u8;
+        car[ENGINE_RPM_OFFSET..ENGINE_RPM_OFFSET + 2].copy_from_slice(&rpm.to_le_bytes());
+        car[REV_LIGHTS_PERCENT_OFFSET] = percent;
+        test_packet(&car)
+    }
+
+    fn lights(percent: u8) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::RevLights(percent)))
+    }
+
+    #[test]
+    fn gears_with_thresholds_light_up_between_them() {
+        let mut tracker = RevLightsTracker::default();
+        tracker.set_gears(vec![[8_000, 11_000], [9_000, 9_000]]);
+        let lit = |tracker: &mut RevLightsTracker, gear, rpm| {
+            tracker.car_telemetry(&car_telemetry(gear, rpm, 0)).unwrap()
+        };
+        assert_eq!(lit(&mut tracker, 1, 7_000), lights(0));
+        assert_eq!(lit(&mut tracker, 1, 9_500), lights(50));
+        assert_eq!(lit(&mut tracker, 1, 12_000), lights(100));
+        // All or nothing when the lights are full where they start
+        assert_eq!(lit(&mut tracker, 2, 8_999), lights(0));
+        assert_eq!(lit(&mut tracker, 2, 9_000), lights(100));
+    }
+
+    #[test]
+    fn other_gears_follow_the_game() {
+        let mut tracker = RevLightsTracker::default();
+        tracker.set_gears(vec![[8_000, 11_000]]);
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry(3, 10_000, 40))
+                .unwrap(),
+            lights(40)
+        );
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry(-1, 10_000, 40))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry(0, 10_000, 150))
+                .unwrap(),
+            lights(100)
+        );
+    }
+}
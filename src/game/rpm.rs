@@ -0,0 +1,122 @@
+//! The engine speed of the player as a share of its range, from the car status and car
+//! telemetry, laid out the same since F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+
+const CAR_STATUS_SIZE: usize = 55;
+/// Followed by the idle RPM
+const MAX_RPM_OFFSET: usize = 17;
+const IDLE_RPM_OFFSET: usize = 19;
+const CAR_TELEMETRY_SIZE: usize = 60;
+const ENGINE_RPM_OFFSET: usize = 16;
+
+/// Turns the engine speed into a level from idle at 0 to the limiter at 255.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RpmTracker {
+    /// Percentage of the range from where the lights flash, only reports when set
+    redline: Option<u8>,
+    /// Idle and maximum RPM of the car, from the car status
+    range: Option<[u16; 2]>,
+    /// Last level passed on and whether it was past the redline
+    reported: Option<(u8, bool)>,
+}
+
+impl RpmTracker {
+    pub(crate) fn set_redline(&mut self, redline: u8) {
+        self.redline = Some(redline);
+    }
+
+    /// Reads the RPM range of the car from a car status packet.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.redline.is_none() {
+            return Ok(());
+        }
+        let car = player_car(data, CAR_STATUS_SIZE)?;
+        let max = u16::from_le_bytes([car[MAX_RPM_OFFSET], car[MAX_RPM_OFFSET + 1]]);
+        let idle = u16::from_le_bytes([car[IDLE_RPM_OFFSET], car[IDLE_RPM_OFFSET + 1]]);
+        self.range = (max > idle).then_some([idle, max]);
+        Ok(())
+    }
+
+    /// Reads the engine speed from a car telemetry packet, the event when the level changed. Only
+    /// reports once the range of the car is known.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let (Some(redline), Some([idle, max])) = (self.redline, self.range) else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+        let rpm = u16::from_le_bytes([car[ENGINE_RPM_OFFSET], car[ENGINE_RPM_OFFSET + 1]]);
+        let part = f32::from(rpm.saturating_sub(idle)) / f32::from(max - idle);
+        let level = (part.min(1.0) * 255.0) as u8;
+        let past_redline = u16::from(level) * 100 >= u16::from(redline) * 255;
+
+        if self.reported == Some((level, past_redline)) {
+            return Ok(None);
+        }
+        self.reported = Some((level, past_redline));
+        Ok(Some(FlagEvent::Indicator(Indicator::Rpm {
+            level,
+            redline: past_redline,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn car_status(idle: u16, max: u16) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[MAX_RPM_OFFSET..MAX_RPM_OFFSET + 2].copy_from_slice(&max.to_le_bytes());
+        car[IDLE_RPM_OFFSET..IDLE_RPM_OFFSET + 2].copy_from_slice(&idle.to_le_bytes());
+        test_packet(&car)
+    }
+
+    fn car_telemetry(rpm: u16) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[ENGINE_RPM_OFFSET..ENGINE_RPM_OFFSET + 2].copy_from_slice(&rpm.to_le_bytes());
+        test_packet(&car)
+    }
+
+    fn rpm(level: u8, redline: bool) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::Rpm { level, redline }))
+    }
+
+    #[test]
+    fn level_runs_from_idle_to_max() {
+        let mut tracker = RpmTracker::default();
+        tracker.set_redline(90);
+        tracker.car_status(&car_status(4_000, 12_000)).unwrap();
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(3_000)).unwrap(),
+            rpm(0, false)
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(8_000)).unwrap(),
+            rpm(127, false)
+        );
+        assert_eq!(tracker.car_telemetry(&car_telemetry(8_000)).unwrap(), None);
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(11_300)).unwrap(),
+            rpm(232, true)
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(13_000)).unwrap(),
+            rpm(255, true)
+        );
+    }
+
+    #[test]
+    fn nothing_without_a_range() {
+        let mut tracker = RpmTracker::default();
+        tracker.set_redline(90);
+        assert_eq!(tracker.car_telemetry(&car_telemetry(8_000)).unwrap(), None);
+        // A car status from before the car left the garage
+        tracker.car_status(&car_status(0, 0)).unwrap();
+        assert_eq!(tracker.car_telemetry(&car_telemetry(8_000)).unwrap(), None);
+    }
+}
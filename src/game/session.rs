@@ -0,0 +1,68 @@
+//! The type of session from the session packet, laid out the same since F1 23.
+use std::io;
+
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::flag::SessionKind;
+
+const SESSION_TYPE_OFFSET: usize = 35;
+
+/// Reports the type of session whenever it changes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionTracker {
+    /// Last type passed on
+    reported: Option<SessionKind>,
+}
+
+impl SessionTracker {
+    /// Reads the session type from a session packet, the event when it changed.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let kind = match *data.get(SESSION_TYPE_OFFSET).ok_or_else(too_short)? {
+            1..=4 => SessionKind::Practice,
+            // One-shot qualifying and the sprint shootouts as well
+            5..=14 => SessionKind::Qualifying,
+            15..=17 => SessionKind::Race,
+            18 => SessionKind::TimeTrial,
+            _ => return Ok(None),
+        };
+        if self.reported == Some(kind) {
+            return Ok(None);
+        }
+        self.reported = Some(kind);
+        Ok(Some(FlagEvent::Session(kind)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(session_type: u8) -> Vec<u8> {
+        let mut data = vec![0; SESSION_TYPE_OFFSET + 1];
+        data[SESSION_TYPE_OFFSET] = session_type;
+        data
+    }
+
+    #[test]
+    fn sessions_of_a_kind_are_one_change() {
+        let mut tracker = SessionTracker::default();
+        // Unknown, like in the menus
+        assert_eq!(tracker.session(&session(0)).unwrap(), None);
+        assert_eq!(
+            tracker.session(&session(5)).unwrap(),
+            Some(FlagEvent::Session(SessionKind::Qualifying))
+        );
+        // From Q1 to Q2
+        assert_eq!(tracker.session(&session(6)).unwrap(), None);
+        // A sprint shootout is qualifying as well
+        assert_eq!(tracker.session(&session(12)).unwrap(), None);
+        assert_eq!(
+            tracker.session(&session(17)).unwrap(),
+            Some(FlagEvent::Session(SessionKind::Race))
+        );
+        assert_eq!(
+            tracker.session(&session(18)).unwrap(),
+            Some(FlagEvent::Session(SessionKind::TimeTrial))
+        );
+    }
+}
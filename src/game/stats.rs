@@ -0,0 +1,149 @@
+//! How many packets come in and how many get lost on the way, for lag that is really the network.
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::HEADER_SIZE;
+use super::PACKET_ID_OFFSET;
+
+const OVERALL_FRAME_IDENTIFIER_OFFSET: usize = 23;
+/// Motion, lap data, car telemetry and car status come every tick, at the rate set in the game
+const TICK_PACKETS: [u8; 4] = [0, 2, 6, 7];
+const PACKET_IDS: usize = 32;
+/// How long the packets are counted for every time
+pub(crate) const WINDOW: Duration = Duration::from_secs(10);
+
+/// The packets of the last counting window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PacketStatistics {
+    /// Packets received per second by packet id
+    pub rates: BTreeMap<u8, f64>,
+    /// Share of the packets lost by packet id, for the packets the game sends every tick that came
+    /// in
+    pub losses: BTreeMap<u8, f64>,
+    /// Share of the packets sent every tick that got lost, `None` without any of them
+    pub loss: Option<f64>,
+    /// Packets dropped for coming late or twice
+    pub late: u64,
+    /// Packets that failed to parse by packet id, since the start
+    pub failures: BTreeMap<u8, u64>,
+}
+
+/// Counts the packets for [`PacketStatistics`], a tick counts as sent when any of its packets
+/// came in.
+#[derive(Debug)]
+pub(crate) struct PacketCounter {
+    started: Instant,
+    received: [u32; PACKET_IDS],
+    /// Overall frame identifiers of the ticks in the window
+    ticks: HashSet<u32>,
+    /// Packets dropped for coming late or twice before the window
+    late_before: u64,
+}
+
+impl Default for PacketCounter {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            received: [0; PACKET_IDS],
+            ticks: HashSet::new(),
+            late_before: 0,
+        }
+    }
+}
+
+impl PacketCounter {
+    /// Counts a packet.
+    pub(crate) fn packet(&mut self, data: &[u8]) {
+        if data.len() >= HEADER_SIZE
+            && let Some(received) = self.received.get_mut(usize::from(data[PACKET_ID_OFFSET]))
+        {
+            *received += 1;
+            if TICK_PACKETS.contains(&data[PACKET_ID_OFFSET]) {
+                self.ticks.insert(u32::from_le_bytes(
+                    data[OVERALL_FRAME_IDENTIFIER_OFFSET..OVERALL_FRAME_IDENTIFIER_OFFSET + 4]
+                        .try_into()
+                        .unwrap(),
+                ));
+            }
+        }
+    }
+
+    /// When the window is over and the [`statistics`](Self::statistics) are due.
+    pub(crate) fn window_end(&self) -> Instant {
+        self.started + WINDOW
+    }
+
+    /// The statistics of the window until `now`, which starts the next one. Empty when no packets
+    /// came in. `late` is the number of packets dropped for coming late or twice so far,
+    /// `failures` the packets that failed to parse so far.
+    pub(crate) fn statistics(
+        &mut self,
+        now: Instant,
+        late: u64,
+        failures: &BTreeMap<u8, u64>,
+    ) -> PacketStatistics {
+        let elapsed = now.saturating_duration_since(self.started);
+        let ticks = self.ticks.len() as f64;
+        // Packets turned off in the game are not lost
+        let sent: Vec<u8> = TICK_PACKETS
+            .into_iter()
+            .filter(|&id| self.received[usize::from(id)] > 0)
+            .collect();
+        let statistics = PacketStatistics {
+            rates: (0..)
+                .zip(self.received)
+                .filter(|&(_, received)| received > 0)
+                .map(|(id, received)| (id, f64::from(received) / elapsed.as_secs_f64()))
+                .collect(),
+            losses: sent
+                .iter()
+                .map(|&id| {
+                    let loss = 1.0 - f64::from(self.received[usize::from(id)]) / ticks;
+                    // Packets that came twice count as received
+                    (id, loss.max(0.0))
+                })
+                .collect(),
+            loss: (!sent.is_empty()).then(|| {
+                let received: f64 = sent
+                    .iter()
+                    .map(|&id| f64::from(self.received[usize::from(id)]).min(ticks))
+                    .sum();
+                1.0 - received / (ticks * sent.len() as f64)
+            }),
+            late: late - self.late_before,
+            failures: failures.clone(),
+        };
+        *self = Self {
+            started: now,
+            received: [0; PACKET_IDS],
+            ticks: HashSet::new(),
+            late_before: late,
+        };
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    #[test]
+    fn quiet_windows_have_no_rates() {
+        let mut counter = PacketCounter::default();
+        for _ in 0..20 {
+            counter.packet(&test_packet(&[]));
+        }
+        let end = counter.window_end();
+        let statistics = counter.statistics(end, 0, &BTreeMap::new());
+        assert_eq!(statistics.rates, BTreeMap::from([(0, 2.0)]));
+        assert_eq!(statistics.loss, Some(0.0));
+
+        assert_eq!(counter.window_end(), end + WINDOW);
+        let quiet = counter.statistics(counter.window_end(), 0, &BTreeMap::new());
+        assert!(quiet.rates.is_empty());
+        assert_eq!(quiet.loss, None);
+    }
+}
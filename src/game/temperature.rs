@@ -0,0 +1,168 @@
+//! Engine and brake temperatures of the player from the car telemetry, laid out the same since
+//! F1 23.
+use std::io;
+
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+use crate::indicator::WarningLevel;
+
+const CAR_TELEMETRY_SIZE: usize = 60;
+/// Four wheels of degrees Celsius as u16
+const BRAKES_TEMPERATURE_OFFSET: usize = 22;
+const ENGINE_TEMPERATURE_OFFSET: usize = 38;
+
+/// Degrees where the engine and the brakes turn amber and red, and how far below them they have
+/// to cool down to go back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TemperatureThresholds {
+    pub(crate) engine: [u16; 2],
+    pub(crate) brakes: [u16; 2],
+    pub(crate) hysteresis: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TemperatureWarningTracker {
+    /// Only reports when set
+    thresholds: Option<TemperatureThresholds>,
+    /// Last levels passed on, of the engine and the hottest brake
+    reported: Option<(WarningLevel, WarningLevel)>,
+}
+
+impl TemperatureWarningTracker {
+    pub(crate) fn set_thresholds(&mut self, thresholds: TemperatureThresholds) {
+        self.thresholds = Some(thresholds);
+    }
+
+    /// Reads the temperatures from a car telemetry packet, the event when a level changed.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let Some(thresholds) = self.thresholds else {
+            return Ok(None);
+        };
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+        let u16_at = |offset: usize| u16::from_le_bytes([car[offset], car[offset + 1]]);
+        let engine_temperature = u16_at(ENGINE_TEMPERATURE_OFFSET);
+        let brakes_temperature = (0..4)
+            .map(|brake| u16_at(BRAKES_TEMPERATURE_OFFSET + brake * 2))
+            .max()
+            .unwrap();
+
+        let (engine, brakes) = self
+            .reported
+            .unwrap_or((WarningLevel::None, WarningLevel::None));
+        let levels = (
+            level(
+                engine,
+                engine_temperature,
+                thresholds.engine,
+                thresholds.hysteresis,
+            ),
+            level(
+                brakes,
+                brakes_temperature,
+                thresholds.brakes,
+                thresholds.hysteresis,
+            ),
+        );
+        if self.reported == Some(levels) {
+            return Ok(None);
+        }
+        self.reported = Some(levels);
+        Ok(Some(FlagEvent::Indicator(Indicator::TemperatureWarning {
+            engine: levels.0,
+            brakes: levels.1,
+        })))
+    }
+}
+
+/// The level at `temperature`, staying at the `current` one until it cooled down `hysteresis`
+/// degrees below where it started.
+fn level(
+    current: WarningLevel,
+    temperature: u16,
+    [amber, red]: [u16; 2],
+    hysteresis: u16,
+) -> WarningLevel {
+    let above = |threshold: u16, level: WarningLevel| {
+        temperature >= threshold || (current >= level && temperature + hysteresis > threshold)
+    };
+    if above(red, WarningLevel::Red) {
+        WarningLevel::Red
+    } else if above(amber, WarningLevel::Amber) {
+        WarningLevel::Amber
+    } else {
+        WarningLevel::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn car_telemetry(engine: u16, brakes: [u16; 4]) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        for (brake, temperature) in brakes.into_iter().enumerate() {
+            let offset = BRAKES_TEMPERATURE_OFFSET + brake * 2;
+            car[offset..offset + 2].copy_from_slice(&temperature.to_le_bytes());
+        }
+        car[ENGINE_TEMPERATURE_OFFSET..ENGINE_TEMPERATURE_OFFSET + 2]
+            .copy_from_slice(&engine.to_le_bytes());
+        test_packet(&car)
+    }
+
+    fn warning(engine: WarningLevel, brakes: WarningLevel) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::TemperatureWarning {
+            engine,
+            brakes,
+        }))
+    }
+
+    fn tracker() -> TemperatureWarningTracker {
+        let mut tracker = TemperatureWarningTracker::default();
+        tracker.set_thresholds(TemperatureThresholds {
+            engine: [110, 120],
+            brakes: [900, 1_000],
+            hysteresis: 20,
+        });
+        tracker
+    }
+
+    #[test]
+    fn the_hottest_brake_counts() {
+        let mut tracker = tracker();
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry(100, [500, 950, 600, 700]))
+                .unwrap(),
+            warning(WarningLevel::None, WarningLevel::Amber)
+        );
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry(125, [1_000, 0, 0, 0]))
+                .unwrap(),
+            warning(WarningLevel::Red, WarningLevel::Red)
+        );
+    }
+
+    #[test]
+    fn levels_stay_until_cooled_down() {
+        let mut tracker = tracker();
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(121, [0; 4])).unwrap(),
+            warning(WarningLevel::Red, WarningLevel::None)
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(101, [0; 4])).unwrap(),
+            None
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(100, [0; 4])).unwrap(),
+            warning(WarningLevel::Amber, WarningLevel::None)
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry(90, [0; 4])).unwrap(),
+            warning(WarningLevel::None, WarningLevel::None)
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Yellow and blue flags from where the player is on track instead of only the car status.
+//!
+//! The session packet says which marshal zones are yellow and the lap data where every car is,
+//! the layout of both is the same since F1 23 apart from the size of the lap data.
+use std::io;
+
+use super::GameYear;
+use super::HEADER_SIZE;
+use super::too_short;
+use crate::event::FlagEvent;
+use crate::flag::LocalFlag;
+
+pub(crate) const LAP_DATA_PACKET: u8 = 2;
+
+const TRACK_LENGTH_OFFSET: usize = 33;
+const NUM_MARSHAL_ZONES_OFFSET: usize = 47;
+const MARSHAL_ZONES_OFFSET: usize = 48;
+const MARSHAL_ZONE_SIZE: usize = 5;
+const MAX_MARSHAL_ZONES: usize = 21;
+const ZONE_FLAG_YELLOW: i8 = 3;
+const PLAYER_CAR_INDEX_OFFSET: usize = 27;
+/// Distance around the lap, after the lap and sector times and the deltas
+const LAP_DISTANCE_OFFSET: usize = 20;
+const F1_23_LAP_DISTANCE_OFFSET: usize = 18;
+/// Distance in the whole session, right after the lap distance
+const TOTAL_DISTANCE_OFFSET: usize = 4;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+/// The length of the track in meters and the start of every marshal zone as a fraction of the lap
+/// with whether it is yellow, from a session packet.
+pub(crate) fn marshal_zones(data: &[u8]) -> io::Result<(f32, Vec<(f32, bool)>)> {
+    let zones_end = MARSHAL_ZONES_OFFSET + MAX_MARSHAL_ZONES * MARSHAL_ZONE_SIZE;
+    if data.len() < zones_end {
+        return Err(too_short());
+    }
+
+    let track_length = &data[TRACK_LENGTH_OFFSET..TRACK_LENGTH_OFFSET + 2];
+    let track_length = u16::from_le_bytes(track_length.try_into().unwrap()).into();
+    let count = usize::from(data[NUM_MARSHAL_ZONES_OFFSET]).min(MAX_MARSHAL_ZONES);
+    let zones = data[MARSHAL_ZONES_OFFSET..zones_end]
+        .chunks_exact(MARSHAL_ZONE_SIZE)
+        .take(count)
+        .map(|zone| {
+            let start = f32::from_le_bytes(zone[..4].try_into().unwrap());
+            (start, zone[4] as i8 == ZONE_FLAG_YELLOW)
+        })
+        .collect();
+    Ok((track_length, zones))
+}
+
+/// A session packet of a track of `length` meters with the marshal `zones`, each with its start as
+/// a fraction of the lap and its flag.
+#[cfg(test)]
+pub(crate) fn test_session(length: u16, zones: &[(f32, i8)]) -> Vec<u8> {
+    let mut data = vec![0; MARSHAL_ZONES_OFFSET + MAX_MARSHAL_ZONES * MARSHAL_ZONE_SIZE];
+    data[TRACK_LENGTH_OFFSET..TRACK_LENGTH_OFFSET + 2].copy_from_slice(&length.to_le_bytes());
+    data[NUM_MARSHAL_ZONES_OFFSET] = zones.len() as u8;
+    for (i, &(start, flag)) in zones.iter().enumerate() {
+        let zone = MARSHAL_ZONES_OFFSET + i * MARSHAL_ZONE_SIZE;
+        data[zone..zone + 4].copy_from_slice(&start.to_le_bytes());
+        data[zone + 4] = flag as u8;
+    }
+    data
+}
+
+/// Where a car is, in meters.
+#[derive(Debug, Clone, Copy)]
+struct CarPosition {
+    lap_distance: f32,
+    total_distance: f32,
+}
+
+/// Filters the yellow and blue flags of the car status by what is around the player.
+///
+/// The other local flags are passed on as they are. Until the game sent the marshal zones or the
+/// position of the cars, the car status flags are used as is.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Track {
+    /// Show yellow in yellow marshal zones and this many meters before
+    yellow_lookahead: Option<f32>,
+    /// Show blue when the car lapping the player is at most this many meters behind
+    blue_gap: Option<f32>,
+    track_length: f32,
+    /// Start of every zone as a fraction of the lap and whether it is yellow
+    zones: Vec<(f32, bool)>,
+    player: usize,
+    cars: Vec<CarPosition>,
+    /// Last local flag event from the car status
+    car_flag: Option<FlagEvent>,
+    /// Last event passed on
+    reported: Option<FlagEvent>,
+}
+
+impl Track {
+    pub(crate) fn set_yellow_lookahead(&mut self, lookahead: f32) {
+        self.yellow_lookahead = Some(lookahead);
+    }
+
+    pub(crate) fn set_blue_gap(&mut self, gap: f32) {
+        self.blue_gap = Some(gap);
+    }
+
+    /// Reads the marshal zones from a session packet, the event when that changes the flag.
+    pub(crate) fn session(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        (self.track_length, self.zones) = marshal_zones(data)?;
+        Ok(self.update())
+    }
+
+    /// Reads where the cars are from a lap data packet, the event when that changes the flag.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_LAP_DISTANCE_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (LAP_DISTANCE_OFFSET, LAP_DATA_SIZE),
+        };
+        self.player = usize::from(*data.get(PLAYER_CAR_INDEX_OFFSET).ok_or_else(too_short)?);
+        let cars = data.get(HEADER_SIZE..).ok_or_else(too_short)?;
+        self.cars = cars
+            .chunks_exact(size)
+            .map(|car| {
+                let distance =
+                    |offset: usize| f32::from_le_bytes(car[offset..offset + 4].try_into().unwrap());
+                CarPosition {
+                    lap_distance: distance(offset),
+                    total_distance: distance(offset + TOTAL_DISTANCE_OFFSET),
+                }
+            })
+            .collect();
+        if self.player >= self.cars.len() {
+            return Err(too_short());
+        }
+        Ok(self.update())
+    }
+
+    /// Combines a local flag event from the car status with the position on track.
+    pub(crate) fn car_status(&mut self, event: FlagEvent) -> FlagEvent {
+        self.car_flag = Some(event);
+        let event = self.event();
+        self.reported = Some(event);
+        event
+    }
+
+    fn update(&mut self) -> Option<FlagEvent> {
+        let event = self.event();
+        if self.reported == Some(event) {
+            return None;
+        }
+        self.reported = Some(event);
+        Some(event)
+    }
+
+    fn event(&self) -> FlagEvent {
+        match self.car_flag {
+            _ if self.yellow_near_player() == Some(true) => {
+                FlagEvent::SetLocalFlag(LocalFlag::Yellow)
+            }
+            Some(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+                if self.yellow_near_player() == Some(false) =>
+            {
+                FlagEvent::ResetLocalFlag
+            }
+            Some(FlagEvent::SetLocalFlag(LocalFlag::Blue))
+                if self.lapping_car_close() == Some(false) =>
+            {
+                FlagEvent::ResetLocalFlag
+            }
+            Some(event) => event,
+            None => FlagEvent::ResetLocalFlag,
+        }
+    }
+
+    /// Whether the player is in a yellow zone or less than the lookahead before one, `None` when
+    /// not filtering the yellow flag or the zones are unknown.
+    fn yellow_near_player(&self) -> Option<bool> {
+        let lookahead = self.yellow_lookahead?;
+        let length = self.track_length;
+        let distance = self.cars.get(self.player)?.lap_distance;
+        if self.zones.is_empty() || length <= 0.0 {
+            return None;
+        }
+
+        // The distance is negative before crossing the line for the first time
+        let distance = distance.rem_euclid(length);
+        Some(self.zones.iter().enumerate().any(|(i, &(start, yellow))| {
+            let start = start * length;
+            let end = self.zones.get(i + 1).map_or(self.zones[0].0, |zone| zone.0) * length;
+            let mut zone_length = (end - start).rem_euclid(length);
+            if zone_length == 0.0 {
+                // A single zone covers the whole lap
+                zone_length = length;
+            }
+            let ahead = (start - distance).rem_euclid(length);
+            let inside = (distance - start).rem_euclid(length) < zone_length;
+            yellow && (inside || ahead <= lookahead)
+        }))
+    }
+
+    /// Whether a car that is laps ahead is at most the gap behind the player, `None` when not
+    /// filtering the blue flag or the cars are unknown.
+    fn lapping_car_close(&self) -> Option<bool> {
+        let gap = self.blue_gap?;
+        let player = self.cars.get(self.player)?;
+        Some(self.cars.iter().any(|car| {
+            let mut behind = player.lap_distance - car.lap_distance;
+            if self.track_length > 0.0 {
+                behind = behind.rem_euclid(self.track_length);
+            }
+            car.total_distance > player.total_distance && (0.0..=gap).contains(&behind)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    const NO_FLAG: i8 = 0;
+
+    /// Lap data with the lap and total distance of every car, the player first.
+    fn lap_data(cars: &[(f32, f32)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for &(lap_distance, total_distance) in cars {
+            let mut car = [0; LAP_DATA_SIZE];
+            car[LAP_DISTANCE_OFFSET..LAP_DISTANCE_OFFSET + 4]
+                .copy_from_slice(&lap_distance.to_le_bytes());
+            let total = LAP_DISTANCE_OFFSET + TOTAL_DISTANCE_OFFSET;
+            car[total..total + 4].copy_from_slice(&total_distance.to_le_bytes());
+            data.extend_from_slice(&car);
+        }
+        test_packet(&data)
+    }
+
+    #[test]
+    fn lookahead_reaches_across_the_line() {
+        let mut track = Track::default();
+        track.set_yellow_lookahead(200.0);
+        let game_year = GameYear::F1_25;
+        let zones = [(0.0, ZONE_FLAG_YELLOW), (0.1, NO_FLAG)];
+        track.session(&test_session(5000, &zones)).unwrap();
+
+        // Still 300 meters before the zone
+        assert_eq!(
+            track
+                .lap_data(&lap_data(&[(4700.0, 4700.0)]), game_year)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            track
+                .lap_data(&lap_data(&[(4850.0, 4850.0)]), game_year)
+                .unwrap(),
+            Some(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+        );
+        // Before crossing the line for the first time
+        assert_eq!(
+            track
+                .lap_data(&lap_data(&[(-100.0, -100.0)]), game_year)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            track
+                .lap_data(&lap_data(&[(600.0, 5600.0)]), game_year)
+                .unwrap(),
+            Some(FlagEvent::ResetLocalFlag)
+        );
+    }
+
+    #[test]
+    fn blue_only_counts_cars_a_lap_ahead_close_behind() {
+        let mut track = Track::default();
+        track.set_blue_gap(100.0);
+        let game_year = GameYear::F1_25;
+        track.session(&test_session(5000, &[])).unwrap();
+        // A car on the same lap close behind and a lapping car far behind
+        let cars = [(1000.0, 6000.0), (950.0, 5950.0), (500.0, 10_500.0)];
+        track.lap_data(&lap_data(&cars), game_year).unwrap();
+        assert_eq!(
+            track.car_status(FlagEvent::SetLocalFlag(LocalFlag::Blue)),
+            FlagEvent::ResetLocalFlag
+        );
+        // The lapping car just behind across the line
+        let cars = [(20.0, 10_020.0), (4990.0, 9990.0), (4960.0, 14_960.0)];
+        assert_eq!(
+            track.lap_data(&lap_data(&cars), game_year).unwrap(),
+            Some(FlagEvent::SetLocalFlag(LocalFlag::Blue))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! The player's tyres: the compound from the car status, the wear from the car damage and the
+//! temperatures from the car telemetry.
+use std::io;
+
+use super::GameYear;
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::indicator::Indicator;
+use crate::indicator::Tyre;
+use crate::indicator::WarningLevel;
+
+const CAR_STATUS_SIZE: usize = 55;
+/// The compound as shown, the actual compound tells the C1 to C6 apart
+const VISUAL_TYRE_COMPOUND_OFFSET: usize = 26;
+pub(crate) const CAR_DAMAGE_PACKET: u8 = 10;
+/// F1 25 added the blisters after the tyre and brake damage
+const CAR_DAMAGE_SIZE: usize = 46;
+const F1_24_CAR_DAMAGE_SIZE: usize = 42;
+const TYRES_WEAR_OFFSET: usize = 0;
+const CAR_TELEMETRY_SIZE: usize = 60;
+const TYRES_SURFACE_TEMPERATURE_OFFSET: usize = 30;
+/// The game has the rear wheels first, the lights the front wheels
+const CORNERS: [usize; 4] = [2, 3, 0, 1];
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TyreTracker {
+    /// Last compound passed on
+    reported: Option<Tyre>,
+}
+
+impl TyreTracker {
+    /// Reads the compound from a car status packet, the event when it changed after a pit stop.
+    pub(crate) fn car_status(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        let car = player_car(data, CAR_STATUS_SIZE)?;
+        // The F2 and classic compounds have numbers of their own
+        let tyre = match car[VISUAL_TYRE_COMPOUND_OFFSET] {
+            11 | 12 | 16 => Tyre::Soft,
+            13 | 17 => Tyre::Medium,
+            9 | 14 | 18 => Tyre::Hard,
+            7 => Tyre::Inter,
+            8 | 10 | 15 => Tyre::Wet,
+            _ => Tyre::Unknown,
+        };
+
+        if self.reported == Some(tyre) {
+            return Ok(None);
+        }
+        self.reported = Some(tyre);
+        Ok(Some(FlagEvent::Indicator(Indicator::Tyre(tyre))))
+    }
+}
+
+/// Warns per corner when a tyre wears out or overheats.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TyreWarningTracker {
+    /// Wear in percent and surface temperature in degrees where the tyres turn amber and red, and
+    /// whether each corner gets its own level, only reports when set
+    thresholds: Option<([f32; 2], [u8; 2], bool)>,
+    /// Wear of each tyre in the order of the game
+    wear: [f32; 4],
+    temperature: [u8; 4],
+    /// Last levels passed on
+    reported: Option<[WarningLevel; 4]>,
+}
+
+impl TyreWarningTracker {
+    pub(crate) fn set_thresholds(
+        &mut self,
+        wear: [f32; 2],
+        temperature: [u8; 2],
+        per_corner: bool,
+    ) {
+        self.thresholds = Some((wear, temperature, per_corner));
+    }
+
+    /// Reads the wear of the tyres from a car damage packet, the event when a level changed.
+    pub(crate) fn car_damage(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        if self.thresholds.is_none() {
+            return Ok(None);
+        }
+        let size = match game_year {
+            GameYear::F1_25 => CAR_DAMAGE_SIZE,
+            GameYear::F1_23 | GameYear::F1_24 => F1_24_CAR_DAMAGE_SIZE,
+        };
+        let car = player_car(data, size)?;
+        for (tyre, wear) in self.wear.iter_mut().enumerate() {
+            let offset = TYRES_WEAR_OFFSET + tyre * 4;
+            *wear = f32::from_le_bytes(car[offset..offset + 4].try_into().unwrap());
+        }
+        Ok(self.report())
+    }
+
+    /// Reads the surface temperatures from a car telemetry packet, the event when a level changed.
+    pub(crate) fn car_telemetry(&mut self, data: &[u8]) -> io::Result<Option<FlagEvent>> {
+        if self.thresholds.is_none() {
+            return Ok(None);
+        }
+        let car = player_car(data, CAR_TELEMETRY_SIZE)?;
+        self.temperature.copy_from_slice(
+            &car[TYRES_SURFACE_TEMPERATURE_OFFSET..TYRES_SURFACE_TEMPERATURE_OFFSET + 4],
+        );
+        Ok(self.report())
+    }
+
+    fn report(&mut self) -> Option<FlagEvent> {
+        let ([amber_wear, red_wear], [amber_temperature, red_temperature], per_corner) =
+            self.thresholds?;
+        let mut levels = CORNERS.map(|tyre| {
+            let (wear, temperature) = (self.wear[tyre], self.temperature[tyre]);
+            if wear >= red_wear || temperature >= red_temperature {
+                WarningLevel::Red
+            } else if wear >= amber_wear || temperature >= amber_temperature {
+                WarningLevel::Amber
+            } else {
+                WarningLevel::None
+            }
+        });
+        if !per_corner {
+            levels = [levels.into_iter().max().unwrap(); 4];
+        }
+
+        if self.reported == Some(levels) {
+            return None;
+        }
+        self.reported = Some(levels);
+        Some(FlagEvent::Indicator(Indicator::TyreWarning(levels)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    fn car_status(compound: u8) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[VISUAL_TYRE_COMPOUND_OFFSET] = compound;
+        test_packet(&car)
+    }
+
+    fn car_damage(wear: [f32; 4]) -> Vec<u8> {
+        let mut car = [0; CAR_DAMAGE_SIZE];
+        for (tyre, wear) in wear.into_iter().enumerate() {
+            let offset = TYRES_WEAR_OFFSET + tyre * 4;
+            car[offset..offset + 4].copy_from_slice(&wear.to_le_bytes());
+        }
+        test_packet(&car)
+    }
+
+    fn car_telemetry(temperature: [u8; 4]) -> Vec<u8> {
+        let mut car = [0; CAR_TELEMETRY_SIZE];
+        car[TYRES_SURFACE_TEMPERATURE_OFFSET..TYRES_SURFACE_TEMPERATURE_OFFSET + 4]
+            .copy_from_slice(&temperature);
+        test_packet(&car)
+    }
+
+    fn warning(levels: [WarningLevel; 4]) -> Option<FlagEvent> {
+        Some(FlagEvent::Indicator(Indicator::TyreWarning(levels)))
+    }
+
+    #[test]
+    fn compounds_of_other_series_are_the_same_tyre() {
+        let mut tracker = TyreTracker::default();
+        let tyre = |tyre| Some(FlagEvent::Indicator(Indicator::Tyre(tyre)));
+        assert_eq!(
+            tracker.car_status(&car_status(16)).unwrap(),
+            tyre(Tyre::Soft)
+        );
+        assert_eq!(tracker.car_status(&car_status(11)).unwrap(), None);
+        assert_eq!(
+            tracker.car_status(&car_status(7)).unwrap(),
+            tyre(Tyre::Inter)
+        );
+        assert_eq!(
+            tracker.car_status(&car_status(0)).unwrap(),
+            tyre(Tyre::Unknown)
+        );
+    }
+
+    #[test]
+    fn corners_have_the_front_wheels_first() {
+        let mut tracker = TyreWarningTracker::default();
+        tracker.set_thresholds([50.0, 75.0], [100, 110], true);
+        // Rear left worn out
+        assert_eq!(
+            tracker
+                .car_damage(&car_damage([80.0, 0.0, 0.0, 0.0]), GameYear::F1_25)
+                .unwrap(),
+            warning([
+                WarningLevel::None,
+                WarningLevel::None,
+                WarningLevel::Red,
+                WarningLevel::None
+            ])
+        );
+        // Front right overheating as well
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry([0, 0, 0, 105]))
+                .unwrap(),
+            warning([
+                WarningLevel::None,
+                WarningLevel::Amber,
+                WarningLevel::Red,
+                WarningLevel::None
+            ])
+        );
+        assert_eq!(
+            tracker
+                .car_telemetry(&car_telemetry([0, 0, 0, 105]))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn the_worst_corner_counts_for_all_of_them() {
+        let mut tracker = TyreWarningTracker::default();
+        tracker.set_thresholds([50.0, 75.0], [100, 110], false);
+        assert_eq!(
+            tracker
+                .car_damage(&car_damage([0.0, 0.0, 60.0, 0.0]), GameYear::F1_24)
+                .unwrap(),
+            warning([WarningLevel::Amber; 4])
+        );
+    }
+
+    #[test]
+    fn nothing_without_thresholds() {
+        let mut tracker = TyreWarningTracker::default();
+        assert_eq!(
+            tracker
+                .car_damage(&car_damage([100.0; 4]), GameYear::F1_25)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            tracker.car_telemetry(&car_telemetry([120; 4])).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Drive-throughs and stop-gos the player still has to serve, from the lap data.
+use std::io;
+
+use super::GameYear;
+use super::PLAYER_CAR_INDEX_OFFSET;
+use super::player_car;
+use crate::event::FlagEvent;
+use crate::flag::PenaltyKind;
+
+/// After the lap distance and the other distances, positions and warnings
+const UNSERVED_DRIVE_THROUGHS_OFFSET: usize = 41;
+const F1_23_UNSERVED_DRIVE_THROUGHS_OFFSET: usize = 39;
+/// Right after the drive-throughs
+const UNSERVED_STOP_GOS_OFFSET: usize = 1;
+const LAP_DATA_SIZE: usize = 57;
+const F1_23_LAP_DATA_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UnservedPenaltyTracker {
+    /// Last penalty passed on
+    reported: Option<Option<PenaltyKind>>,
+}
+
+impl UnservedPenaltyTracker {
+    /// Reads the unserved penalties from a lap data packet, the event when they changed. A stop-go
+    /// goes before a drive-through.
+    pub(crate) fn lap_data(
+        &mut self,
+        data: &[u8],
+        game_year: GameYear,
+    ) -> io::Result<Option<FlagEvent>> {
+        let (offset, size) = match game_year {
+            GameYear::F1_23 => (F1_23_UNSERVED_DRIVE_THROUGHS_OFFSET, F1_23_LAP_DATA_SIZE),
+            GameYear::F1_24 | GameYear::F1_25 => (UNSERVED_DRIVE_THROUGHS_OFFSET, LAP_DATA_SIZE),
+        };
+        let car = player_car(data, size)?;
+        let kind = match (car[offset], car[offset + UNSERVED_STOP_GOS_OFFSET]) {
+            (_, 1..) => Some(PenaltyKind::StopGo),
+            (1.., 0) => Some(PenaltyKind::DriveThrough),
+            (0, 0) => None,
+        };
+        if self.reported == Some(kind) {
+            return Ok(None);
+        }
+        self.reported = Some(kind);
+        Ok(Some(FlagEvent::UnservedPenalty {
+            vehicle_index: usize::from(data[PLAYER_CAR_INDEX_OFFSET]),
+            kind,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_packet;
+
+    /// Lap data of two cars, the player second with the drive-throughs and stop-gos to serve.
+    fn lap_data(drive_throughs: u8, stop_gos: u8) -> Vec<u8> {
+        let mut cars = [0; 2 * LAP_DATA_SIZE];
+        let player = LAP_DATA_SIZE + UNSERVED_DRIVE_THROUGHS_OFFSET;
+        cars[player] = drive_throughs;
+        cars[player + UNSERVED_STOP_GOS_OFFSET] = stop_gos;
+        let mut data = test_packet(&cars);
+        data[PLAYER_CAR_INDEX_OFFSET] = 1;
+        data
+    }
+
+    fn unserved(kind: Option<PenaltyKind>) -> Option<FlagEvent> {
+        Some(FlagEvent::UnservedPenalty {
+            vehicle_index: 1,
+            kind,
+        })
+    }
+
+    #[test]
+    fn a_stop_go_goes_before_a_drive_through() {
+        let mut tracker = UnservedPenaltyTracker::default();
+        let game_year = GameYear::F1_25;
+        assert_eq!(
+            tracker.lap_data(&lap_data(0, 0), game_year).unwrap(),
+            unserved(None)
+        );
+        assert_eq!(
+            tracker.lap_data(&lap_data(1, 0), game_year).unwrap(),
+            unserved(Some(PenaltyKind::DriveThrough))
+        );
+        assert_eq!(
+            tracker.lap_data(&lap_data(2, 1), game_year).unwrap(),
+            unserved(Some(PenaltyKind::StopGo))
+        );
+        // Served the stop-go, the drive-throughs are left
+        assert_eq!(
+            tracker.lap_data(&lap_data(2, 0), game_year).unwrap(),
+            unserved(Some(PenaltyKind::DriveThrough))
+        );
+        assert_eq!(tracker.lap_data(&lap_data(1, 0), game_year).unwrap(), None);
+    }
+}
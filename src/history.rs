@@ -0,0 +1,140 @@
+//! A log of every flag shown and the game events behind them, for reviewing a race afterwards.
+//!
+//! Every line of the log is a JSON object with the Unix `time` in seconds and the unique id of the
+//! F1 `session` as a string, `null` without one. Game events have the `event`, the flags shown the
+//! `flag` name or `off`.
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::watch;
+
+use crate::event::FlagEvent;
+use crate::flag::Flag;
+use crate::manager::FlagManager;
+
+/// Appends the game events and what the lights show to a JSON lines file.
+#[derive(Debug)]
+pub struct HistoryLog {
+    file: File,
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+    /// What the log last said was shown
+    shown: Option<Flag>,
+}
+
+impl HistoryLog {
+    /// Opens the file at `path` to append to, creating it when needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+            session_uid: None,
+            shown: None,
+        })
+    }
+
+    /// Writes the unique id of the session to every line, as the F1 input or replay reports it.
+    pub fn with_session_uid(mut self, session_uid: watch::Receiver<Option<u64>>) -> Self {
+        self.session_uid = Some(session_uid);
+        self
+    }
+
+    /// Logs a game event, the indicators change too often to be of use.
+    pub fn event(&mut self, event: &FlagEvent) -> io::Result<()> {
+        let description = match event {
+            FlagEvent::Indicator(_) => return Ok(()),
+            FlagEvent::Participants { .. } => String::from("Participants"),
+            event => format!("{event:?}"),
+        };
+        self.write(json!({ "event": description }))
+    }
+
+    /// Logs what `manager` shows, when it changed since the last time.
+    pub fn update(&mut self, manager: &FlagManager) -> io::Result<()> {
+        let shown = manager.state().shown;
+        if shown == self.shown {
+            return Ok(());
+        }
+        self.shown = shown;
+        self.write(json!({ "flag": shown.map_or("off", Flag::name) }))
+    }
+
+    fn write(&mut self, mut line: Value) -> io::Result<()> {
+        line["time"] = json!(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64()
+        );
+        // As a string, JSON numbers lose the lower bits of a u64
+        line["session"] = json!(
+            self.session_uid
+                .as_ref()
+                .and_then(|session_uid| *session_uid.borrow())
+                .map(|session_uid| session_uid.to_string())
+        );
+        let mut line = line.to_string();
+        line.push('\n');
+        // One write per line, so lines stay whole when the program is killed
+        self.file.write_all(line.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flag::LocalFlag;
+    use crate::indicator::Drs;
+    use crate::indicator::Indicator;
+    use crate::output::ChannelOutput;
+
+    #[tokio::test]
+    async fn logs_events_and_changes_of_the_flag() {
+        let path =
+            std::env::temp_dir().join(format!("f1_light_sync_history_{}", std::process::id()));
+        // Appended to, so start without one left by an earlier run
+        let _ = std::fs::remove_file(&path);
+        let (session_uid, receiver) = watch::channel(Some(u64::MAX));
+        let mut history = HistoryLog::open(&path).unwrap().with_session_uid(receiver);
+        let (output, _messages) = ChannelOutput::new();
+        let mut manager = FlagManager::new(vec![Box::new(output)]);
+        let events = [
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+            FlagEvent::Indicator(Indicator::Drs(Drs::Available)),
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+        ];
+        for event in events {
+            history.event(&event).unwrap();
+            manager.handle(event).await;
+            history.update(&manager).unwrap();
+        }
+        session_uid.send_replace(None);
+        manager.handle(FlagEvent::Reset).await;
+        history.update(&manager).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let logged: Vec<_> = lines
+            .iter()
+            .map(|line| (&line["event"], &line["flag"], &line["session"]))
+            .collect();
+        let session = json!(u64::MAX.to_string());
+        assert_eq!(
+            logged,
+            [
+                (&json!("SetLocalFlag(Yellow)"), &Value::Null, &session),
+                (&Value::Null, &json!("yellow"), &session),
+                (&json!("SetLocalFlag(Yellow)"), &Value::Null, &session),
+                (&Value::Null, &json!("off"), &Value::Null),
+            ]
+        );
+    }
+}
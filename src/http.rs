@@ -0,0 +1,466 @@
+//! Built-in web dashboard and control API for a [`FlagManager`], for a race director's laptop.
+//!
+//! `GET /` serves the page, which follows `GET /events`: server-sent events with the status as
+//! JSON whenever it changes. `GET /overlay` is a flag banner on a transparent page for a stream.
+//! `GET /state` returns the status once. Unless control is turned off `POST /flag` forces a flag
+//! over the game for a while, `DELETE /flag` goes back to the game and `POST /pit_reminder` tells
+//! the player to pit this lap.
+//!
+//! Pages on other origins, like the Stream Deck plugin, can only use the API once their origin is
+//! allowed.
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tracing::debug;
+use tracing::warn;
+
+use crate::color::Rgb;
+use crate::event::FlagEvent;
+use crate::flag::Flag;
+use crate::game::PacketStatistics;
+use crate::manager::FlagManager;
+
+const DASHBOARD: &str = include_str!("dashboard.html");
+const OVERLAY: &str = include_str!("overlay.html");
+/// Events listed on the dashboard, the oldest go first
+const EVENT_HISTORY: usize = 20;
+/// Requests with a head larger than this are refused
+const MAX_REQUEST_SIZE: usize = 8192;
+/// A comment sent to idle dashboards, so closed connections are noticed
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Commands from the API waiting for the manager
+const COMMAND_QUEUE: usize = 16;
+
+/// What the API or the keys of the [`StatusView`](crate::tui::StatusView) ask of the manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Show the flag over the game, `None` for the lights off, for the time given or until cleared
+    Force {
+        flag: Option<Flag>,
+        ttl: Option<Duration>,
+    },
+    /// Go back to the flags of the game
+    ClearForced,
+    PitReminder(bool),
+}
+
+impl ControlCommand {
+    /// Carries the command out on `manager`.
+    pub async fn apply(self, manager: &mut FlagManager) {
+        match self {
+            ControlCommand::Force { flag, ttl } => manager.force(flag, ttl).await,
+            ControlCommand::ClearForced => manager.clear_forced().await,
+            ControlCommand::PitReminder(on) => manager.set_pit_reminder(on).await,
+        }
+    }
+}
+
+/// Body of `POST /flag`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ForceRequest {
+    /// Name of the flag like in the config, `off` for the lights off
+    flag: String,
+    /// Seconds the flag is shown, until cleared when left out
+    ttl: Option<f64>,
+}
+
+/// Body of `POST /pit_reminder`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PitReminderRequest {
+    on: bool,
+}
+
+/// Serves the dashboard in the background and keeps the status it shows.
+#[derive(Debug)]
+pub struct HttpServer {
+    status: watch::Sender<String>,
+    commands: mpsc::Receiver<ControlCommand>,
+    output_names: Vec<String>,
+    /// Seconds since the Unix epoch and a description of the last events
+    events: VecDeque<(f64, String)>,
+    /// Whole seconds since the Unix epoch of the last event, indicators included
+    last_event: Option<u64>,
+    packets: Option<watch::Receiver<PacketStatistics>>,
+}
+
+impl HttpServer {
+    /// Starts listening on `address`, the flag outputs are listed with `output_names`. The API only
+    /// takes commands when `control` is set, and pages on `allow_origin` may use it as well.
+    pub async fn bind(
+        address: &str,
+        output_names: Vec<String>,
+        control: bool,
+        allow_origin: Option<&str>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        let headers: Arc<str> = match allow_origin {
+            Some(origin) => format!(
+                "Access-Control-Allow-Origin: {origin}\r\n\
+                 Access-Control-Allow-Methods: GET, POST, DELETE\r\n\
+                 Access-Control-Allow-Headers: Content-Type\r\n"
+            )
+            .into(),
+            None => "".into(),
+        };
+        let (status, _) = watch::channel(String::new());
+        let (sender, commands) = mpsc::channel(COMMAND_QUEUE);
+
+        let clients = status.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, address)) => {
+                        let status = clients.subscribe();
+                        let commands = control.then(|| sender.clone());
+                        let headers = headers.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = serve(stream, status, commands, &headers).await {
+                                debug!(%address, error = %e, "Dashboard connection ended");
+                            }
+                        });
+                    }
+                    Err(e) => warn!(error = %e, "Failed to accept dashboard client"),
+                }
+            }
+        });
+
+        Ok(Self {
+            status,
+            commands,
+            output_names,
+            events: VecDeque::with_capacity(EVENT_HISTORY),
+            last_event: None,
+            packets: None,
+        })
+    }
+
+    /// Lists the flag outputs with `output_names` from now on, for a manager with other outputs.
+    pub fn set_output_names(&mut self, output_names: Vec<String>) {
+        self.output_names = output_names;
+    }
+
+    /// Also shows the packet rates and losses of the F1 telemetry.
+    pub fn with_packet_statistics(mut self, packets: watch::Receiver<PacketStatistics>) -> Self {
+        self.packets = Some(packets);
+        self
+    }
+
+    /// The next command from the API, waits forever when control is turned off.
+    pub async fn command(&mut self) -> ControlCommand {
+        match self.commands.recv().await {
+            Some(command) => command,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Adds an event to the ones listed, indicators only count for when telemetry last came in.
+    pub fn event(&mut self, event: &FlagEvent) {
+        let now = unix_time();
+        self.last_event = Some(now as u64);
+        let description = match event {
+            FlagEvent::Indicator(_) => return,
+            // The arrays of every car say little in a list
+            FlagEvent::Participants { .. } => String::from("Participants"),
+            event => format!("{event:?}"),
+        };
+        if self.events.len() == EVENT_HISTORY {
+            self.events.pop_front();
+        }
+        self.events.push_back((now, description));
+    }
+
+    /// Publishes the state of `manager` to the dashboards, when something changed.
+    pub fn update(&mut self, manager: &FlagManager) {
+        let state = manager.state();
+        let drivers: Vec<Value> = manager
+            .driver_numbers()
+            .iter()
+            .zip(manager.driver_abbreviations())
+            .enumerate()
+            .filter(|(_, (number, _))| **number != 0)
+            .map(|(index, (number, driver))| {
+                json!({
+                    "index": index,
+                    "number": number,
+                    "driver": driver.map(|driver| driver.to_string()),
+                })
+            })
+            .collect();
+        let outputs: Vec<Value> = self
+            .output_names
+            .iter()
+            .zip(manager.output_errors())
+            .map(|(name, error)| json!({ "name": name, "error": error }))
+            .collect();
+        let forced = manager.forced().map(|(flag, until)| {
+            json!({
+                "flag": flag.map_or("off", Flag::name),
+                "until": until.map(|until| {
+                    unix_time() + until.saturating_duration_since(Instant::now()).as_secs_f64()
+                }),
+            })
+        });
+        let packets = self.packets.as_ref().map(|packets| {
+            let packets = packets.borrow();
+            json!({
+                "rates": packets.rates,
+                "losses": packets.losses,
+                "loss": packets.loss,
+                "late": packets.late,
+                "failures": packets.failures,
+            })
+        });
+        let Rgb([r, g, b]) = Rgb::default_for(state.shown);
+        let status = json!({
+            "global": state.global_flag.map(|flag| flag.name()),
+            "local": state.local_flag.map(|flag| flag.name()),
+            "shown": state.shown.map(Flag::name),
+            "color": format!("#{r:02x}{g:02x}{b:02x}"),
+            "race_finished": state.race_finished,
+            "forced": forced,
+            "events": self
+                .events
+                .iter()
+                .map(|(time, event)| json!({ "time": time, "event": event }))
+                .collect::<Vec<_>>(),
+            "drivers": drivers,
+            "outputs": outputs,
+            "last_event": self.last_event,
+            "packets": packets,
+        })
+        .to_string();
+        self.status.send_if_modified(|published| {
+            let changed = *published != status;
+            *published = status;
+            changed
+        });
+    }
+}
+
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// What the client asked for.
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+async fn read_request(stream: &mut TcpStream) -> io::Result<Option<Request>> {
+    let mut data = Vec::with_capacity(1024);
+    let mut chunk = [0; 1024];
+    loop {
+        let length = stream.read(&mut chunk).await?;
+        if length == 0 {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk[..length]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut request = httparse::Request::new(&mut headers);
+        match request.parse(&data) {
+            Ok(httparse::Status::Complete(head)) => {
+                let length = request
+                    .headers
+                    .iter()
+                    .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+                    .map(|header| {
+                        std::str::from_utf8(header.value)
+                            .ok()
+                            .and_then(|length| length.trim().parse::<usize>().ok())
+                            .ok_or_else(|| {
+                                io::Error::new(io::ErrorKind::InvalidData, "invalid content length")
+                            })
+                    })
+                    .transpose()?
+                    .unwrap_or_default();
+                let method = request.method.unwrap_or_default().to_string();
+                let path = request.path.unwrap_or_default().to_string();
+                if head + length > MAX_REQUEST_SIZE {
+                    return Err(too_large());
+                }
+                let mut body = data.split_off(head);
+                while body.len() < length {
+                    let read = stream.read(&mut chunk).await?;
+                    if read == 0 {
+                        return Ok(None);
+                    }
+                    body.extend_from_slice(&chunk[..read]);
+                }
+                body.truncate(length);
+                return Ok(Some(Request { method, path, body }));
+            }
+            Ok(httparse::Status::Partial) if data.len() < MAX_REQUEST_SIZE => {}
+            Ok(httparse::Status::Partial) => return Err(too_large()),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "request too large")
+}
+
+/// Answers a single request, `commands` is `None` when control is turned off.
+/// Answers one request, every response gets the extra `headers`.
+async fn serve(
+    mut stream: TcpStream,
+    status: watch::Receiver<String>,
+    commands: Option<mpsc::Sender<ControlCommand>>,
+    headers: &str,
+) -> io::Result<()> {
+    let Some(request) = read_request(&mut stream).await? else {
+        return Ok(());
+    };
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => {
+            return respond(
+                &mut stream,
+                headers,
+                "200 OK",
+                "text/html; charset=utf-8",
+                DASHBOARD,
+            )
+            .await;
+        }
+        ("GET", "/overlay") => {
+            let content_type = "text/html; charset=utf-8";
+            return respond(&mut stream, headers, "200 OK", content_type, OVERLAY).await;
+        }
+        ("GET", "/events") => return send_events(stream, status, headers).await,
+        ("GET", "/state") => {
+            let state = status.borrow().clone();
+            return respond(&mut stream, headers, "200 OK", "application/json", &state).await;
+        }
+        ("POST", "/flag") => serde_json::from_slice(&request.body)
+            .map_err(|e| e.to_string())
+            .and_then(|request: ForceRequest| {
+                let flag = Flag::from_name(&request.flag)?;
+                let ttl = request
+                    .ttl
+                    .map(|ttl| Duration::try_from_secs_f64(ttl).map_err(|e| e.to_string()))
+                    .transpose()?;
+                Ok(ControlCommand::Force { flag, ttl })
+            }),
+        ("DELETE", "/flag") => Ok(ControlCommand::ClearForced),
+        ("POST", "/pit_reminder") => serde_json::from_slice(&request.body)
+            .map(|request: PitReminderRequest| ControlCommand::PitReminder(request.on))
+            .map_err(|e| e.to_string()),
+        // The preflight of pages on other origins
+        ("OPTIONS", "/" | "/overlay" | "/events" | "/state" | "/flag" | "/pit_reminder") => {
+            return respond(&mut stream, headers, "204 No Content", "text/plain", "").await;
+        }
+        (_, "/" | "/overlay" | "/events" | "/state" | "/flag" | "/pit_reminder") => {
+            return respond(
+                &mut stream,
+                headers,
+                "405 Method Not Allowed",
+                "text/plain",
+                "method not allowed",
+            )
+            .await;
+        }
+        _ => {
+            return respond(
+                &mut stream,
+                headers,
+                "404 Not Found",
+                "text/plain",
+                "not found",
+            )
+            .await;
+        }
+    };
+
+    match (command, commands) {
+        (Err(e), _) => respond(&mut stream, headers, "400 Bad Request", "text/plain", &e).await,
+        (Ok(_), None) => {
+            respond(
+                &mut stream,
+                headers,
+                "403 Forbidden",
+                "text/plain",
+                "control is turned off",
+            )
+            .await
+        }
+        (Ok(command), Some(commands)) => {
+            if commands.send(command).await.is_err() {
+                return respond(
+                    &mut stream,
+                    headers,
+                    "503 Service Unavailable",
+                    "text/plain",
+                    "not taking commands",
+                )
+                .await;
+            }
+            respond(&mut stream, headers, "204 No Content", "text/plain", "").await
+        }
+    }
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    headers: &str,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n{headers}\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Sends the status as server-sent events until the client goes away.
+async fn send_events(
+    mut stream: TcpStream,
+    mut status: watch::Receiver<String>,
+    headers: &str,
+) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+         Connection: keep-alive\r\n{headers}\r\n"
+    );
+    stream.write_all(head.as_bytes()).await?;
+    loop {
+        let event = format!("data: {}\n\n", status.borrow_and_update().as_str());
+        stream.write_all(event.as_bytes()).await?;
+        loop {
+            tokio::select! {
+                changed = status.changed() => match changed {
+                    Ok(()) => break,
+                    Err(_) => return Ok(()),
+                },
+                () = tokio::time::sleep(KEEPALIVE_INTERVAL) => {
+                    stream.write_all(b": keepalive\n\n").await?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,725 @@
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+
+use crate::color::Rgb;
+
+/// Place on the position board without a car.
+pub const NO_CAR: u8 = 255;
+
+/// State of the player's DRS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drs {
+    Unavailable,
+    /// Allowed in the next DRS zone
+    Available,
+    Open,
+}
+
+impl Drs {
+    /// Lowercase name, as published over MQTT.
+    pub fn name(self) -> &'static str {
+        match self {
+            Drs::Unavailable => "unavailable",
+            Drs::Available => "available",
+            Drs::Open => "open",
+        }
+    }
+}
+
+/// ERS deploy mode of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErsMode {
+    None,
+    Medium,
+    Hotlap,
+    Overtake,
+}
+
+impl ErsMode {
+    /// Lowercase name, as published over MQTT.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErsMode::None => "none",
+            ErsMode::Medium => "medium",
+            ErsMode::Hotlap => "hotlap",
+            ErsMode::Overtake => "overtake",
+        }
+    }
+}
+
+/// Tyre compound the player is on, as shown in the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tyre {
+    Unknown,
+    Soft,
+    Medium,
+    Hard,
+    Inter,
+    Wet,
+}
+
+impl Tyre {
+    /// Lowercase name, as published over MQTT.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tyre::Unknown => "unknown",
+            Tyre::Soft => "soft",
+            Tyre::Medium => "medium",
+            Tyre::Hard => "hard",
+            Tyre::Inter => "inter",
+            Tyre::Wet => "wet",
+        }
+    }
+}
+
+/// How urgent a warning is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarningLevel {
+    None,
+    Amber,
+    Red,
+}
+
+impl WarningLevel {
+    /// Lowercase name, as published over MQTT.
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningLevel::None => "none",
+            WarningLevel::Amber => "amber",
+            WarningLevel::Red => "red",
+        }
+    }
+
+    fn color(self) -> Rgb {
+        match self {
+            WarningLevel::None => Rgb::OFF,
+            WarningLevel::Amber => Rgb([255, 140, 0]),
+            WarningLevel::Red => Rgb([255, 0, 0]),
+        }
+    }
+}
+
+/// Gap of a car to the car ahead on the timing tower.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    /// Too far behind to matter, and the leader
+    Clear,
+    Close,
+    /// Close enough to fight for the place
+    Battle,
+}
+
+impl Interval {
+    /// Lowercase name, as published over MQTT.
+    pub fn name(self) -> &'static str {
+        match self {
+            Interval::Clear => "clear",
+            Interval::Close => "close",
+            Interval::Battle => "battle",
+        }
+    }
+
+    fn color(self) -> Rgb {
+        match self {
+            Interval::Clear => Rgb::OFF,
+            Interval::Close => Rgb([255, 200, 0]),
+            Interval::Battle => Rgb([0, 255, 0]),
+        }
+    }
+}
+
+/// Which [`Indicator`] a set of outputs shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorKind {
+    Drs,
+    RevLights,
+    Rpm,
+    Ers,
+    Tyre,
+    Gear,
+    TyreWarning,
+    TemperatureWarning,
+    LowFuel,
+    Rain,
+    LapDelta,
+    Gap,
+    Progress,
+    MarshalPosts,
+    PositionBoard,
+    TimingTower,
+}
+
+impl IndicatorKind {
+    /// Lowercase name, as filled into templates.
+    pub fn name(self) -> &'static str {
+        match self {
+            IndicatorKind::Drs => "drs",
+            IndicatorKind::RevLights => "rev_lights",
+            IndicatorKind::Rpm => "rpm",
+            IndicatorKind::Ers => "ers",
+            IndicatorKind::Tyre => "tyre",
+            IndicatorKind::Gear => "gear",
+            IndicatorKind::TyreWarning => "tyre_warning",
+            IndicatorKind::TemperatureWarning => "temperature_warning",
+            IndicatorKind::LowFuel => "low_fuel",
+            IndicatorKind::Rain => "rain",
+            IndicatorKind::LapDelta => "lap_delta",
+            IndicatorKind::Gap => "gap",
+            IndicatorKind::Progress => "progress",
+            IndicatorKind::MarshalPosts => "marshal_posts",
+            IndicatorKind::PositionBoard => "position_board",
+            IndicatorKind::TimingTower => "timing_tower",
+        }
+    }
+}
+
+/// Car information shown on outputs of their own, separate from the flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Drs(Drs),
+    /// Percentage of the rev lights that is lit
+    RevLights(u8),
+    /// Engine speed from idle at 0 to the limiter at 255, and whether it is past the redline
+    Rpm {
+        level: u8,
+        redline: bool,
+    },
+    /// Deploy mode and percentage of the battery that is charged
+    Ers {
+        mode: ErsMode,
+        charge: u8,
+    },
+    Tyre(Tyre),
+    /// Gear the player is in, `-1` for reverse and `0` for neutral
+    Gear(i8),
+    /// Wear and temperature of the front left, front right, rear left and rear right tyre
+    TyreWarning([WarningLevel; 4]),
+    /// Temperature of the engine and of the hottest brake
+    TemperatureWarning {
+        engine: WarningLevel,
+        brakes: WarningLevel,
+    },
+    /// Whether the fuel left is below the warning threshold
+    LowFuel(bool),
+    /// Whether rain is coming soon
+    Rain(bool),
+    /// Milliseconds behind the best lap of the player, negative when ahead and `None` without a
+    /// best lap, and whether the lap is on for the fastest lap of the session
+    LapDelta {
+        delta: Option<i32>,
+        purple: bool,
+    },
+    /// Milliseconds the player is behind the leader or the car ahead, `None` without one, and the
+    /// gap that fills the lights
+    Gap {
+        gap: Option<u32>,
+        full: u32,
+    },
+    /// Percentage of the race laps done, or of the time of the other sessions
+    Progress(u8),
+    /// The marshal posts lit yellow, one bit for each post from the lowest
+    MarshalPosts(u32),
+    /// The F1 team id of every car from the leader back, [`NO_CAR`] for the places left
+    PositionBoard([u8; MAX_CARS_IN_SESSION]),
+    /// The gap of every car from the leader back to the car ahead, `None` for the places left
+    TimingTower([Option<Interval>; MAX_CARS_IN_SESSION]),
+}
+
+impl Indicator {
+    pub fn kind(self) -> IndicatorKind {
+        match self {
+            Indicator::Drs(_) => IndicatorKind::Drs,
+            Indicator::RevLights(_) => IndicatorKind::RevLights,
+            Indicator::Rpm { .. } => IndicatorKind::Rpm,
+            Indicator::Ers { .. } => IndicatorKind::Ers,
+            Indicator::Tyre(_) => IndicatorKind::Tyre,
+            Indicator::Gear(_) => IndicatorKind::Gear,
+            Indicator::TyreWarning(_) => IndicatorKind::TyreWarning,
+            Indicator::TemperatureWarning { .. } => IndicatorKind::TemperatureWarning,
+            Indicator::LowFuel(_) => IndicatorKind::LowFuel,
+            Indicator::Rain(_) => IndicatorKind::Rain,
+            Indicator::LapDelta { .. } => IndicatorKind::LapDelta,
+            Indicator::Gap { .. } => IndicatorKind::Gap,
+            Indicator::Progress(_) => IndicatorKind::Progress,
+            Indicator::MarshalPosts(_) => IndicatorKind::MarshalPosts,
+            Indicator::PositionBoard(_) => IndicatorKind::PositionBoard,
+            Indicator::TimingTower(_) => IndicatorKind::TimingTower,
+        }
+    }
+
+    /// The indicator with nothing to show, used when a session ends.
+    pub fn off(kind: IndicatorKind) -> Self {
+        match kind {
+            IndicatorKind::Drs => Indicator::Drs(Drs::Unavailable),
+            IndicatorKind::RevLights => Indicator::RevLights(0),
+            IndicatorKind::Rpm => Indicator::Rpm {
+                level: 0,
+                redline: false,
+            },
+            IndicatorKind::Ers => Indicator::Ers {
+                mode: ErsMode::None,
+                charge: 0,
+            },
+            IndicatorKind::Tyre => Indicator::Tyre(Tyre::Unknown),
+            IndicatorKind::Gear => Indicator::Gear(0),
+            IndicatorKind::TyreWarning => Indicator::TyreWarning([WarningLevel::None; 4]),
+            IndicatorKind::TemperatureWarning => Indicator::TemperatureWarning {
+                engine: WarningLevel::None,
+                brakes: WarningLevel::None,
+            },
+            IndicatorKind::LowFuel => Indicator::LowFuel(false),
+            IndicatorKind::Rain => Indicator::Rain(false),
+            IndicatorKind::LapDelta => Indicator::LapDelta {
+                delta: None,
+                purple: false,
+            },
+            IndicatorKind::Gap => Indicator::Gap { gap: None, full: 0 },
+            IndicatorKind::Progress => Indicator::Progress(0),
+            IndicatorKind::MarshalPosts => Indicator::MarshalPosts(0),
+            IndicatorKind::PositionBoard => Indicator::PositionBoard([NO_CAR; MAX_CARS_IN_SESSION]),
+            IndicatorKind::TimingTower => Indicator::TimingTower([None; MAX_CARS_IN_SESSION]),
+        }
+    }
+
+    /// The string sent to the light controller, like the flag codes but from 50 to 99, which are
+    /// kept for the indicators while the flags have 0 to 49.
+    pub fn to_enum_str(self) -> String {
+        match self {
+            Indicator::Drs(drs) => format!("50,{}", drs as u8),
+            Indicator::RevLights(percent) => format!("51,{percent}"),
+            Indicator::Ers { mode, charge } => format!("52,{},{charge}", mode as u8),
+            Indicator::Tyre(tyre) => format!("61,{}", tyre as u8),
+            Indicator::TyreWarning([front_left, front_right, rear_left, rear_right]) => format!(
+                "62,{},{},{},{}",
+                front_left as u8, front_right as u8, rear_left as u8, rear_right as u8
+            ),
+            Indicator::LowFuel(low) => format!("53,{}", u8::from(low)),
+            Indicator::Rain(coming) => format!("54,{}", u8::from(coming)),
+            Indicator::LapDelta {
+                delta: Some(delta),
+                purple,
+            } => format!("55,{delta},{}", u8::from(purple)),
+            Indicator::LapDelta { delta: None, .. } => "55,c".to_string(),
+            Indicator::Gap { gap: Some(gap), .. } => format!("59,{gap}"),
+            Indicator::Gap { gap: None, .. } => "59,c".to_string(),
+            Indicator::Progress(percent) => format!("60,{percent}"),
+            Indicator::TemperatureWarning { engine, brakes } => {
+                format!("63,{},{}", engine as u8, brakes as u8)
+            }
+            Indicator::Gear(gear) => format!("64,{gear}"),
+            Indicator::Rpm { level, redline } => format!("65,{level},{}", u8::from(redline)),
+            Indicator::MarshalPosts(lit) => format!("56,{lit}"),
+            Indicator::PositionBoard(teams) => {
+                let mut message = String::from("57");
+                for team in teams.into_iter().filter(|&team| team != NO_CAR) {
+                    message.push_str(&format!(",{team}"));
+                }
+                message
+            }
+            Indicator::TimingTower(intervals) => {
+                let mut message = String::from("58");
+                for interval in intervals.into_iter().flatten() {
+                    message.push_str(&format!(",{}", interval as u8));
+                }
+                message
+            }
+        }
+    }
+
+    /// The color the lights get for outputs that only show a color.
+    ///
+    /// Rev lights get the color of the last lit light, ERS the color of the deploy mode. The RPM
+    /// goes from green through yellow to red towards the limiter and is red past the redline. The
+    /// lap delta is green when ahead of the best lap, red when behind and purple when on for the
+    /// fastest lap of the session. The gap goes from green through yellow to red as it grows to the
+    /// gap that fills the lights. The gear steps from green in first through yellow to red in
+    /// eighth, with a dim white for neutral and pink for reverse. The timing tower is green while
+    /// any car is in a battle and yellow while any car is close.
+    pub fn color(self) -> Rgb {
+        match self {
+            Indicator::Drs(Drs::Unavailable) => Rgb::OFF,
+            Indicator::Drs(Drs::Available) => Rgb([255, 255, 255]),
+            Indicator::Drs(Drs::Open) => Rgb([0, 255, 0]),
+            Indicator::RevLights(0) => Rgb::OFF,
+            Indicator::RevLights(percent) => rev_light_color(usize::from(percent - 1), 100),
+            Indicator::Rpm { level: 0, .. } => Rgb::OFF,
+            Indicator::Rpm { redline: true, .. } => Rgb([255, 0, 0]),
+            Indicator::Rpm { level, .. } => gap_color(level.into(), 255),
+            Indicator::Ers { charge: 0, .. } => Rgb::OFF,
+            Indicator::Ers { mode, .. } => Rgb(match mode {
+                ErsMode::None => [60, 60, 60],
+                ErsMode::Medium => [255, 200, 0],
+                ErsMode::Hotlap => [0, 160, 255],
+                ErsMode::Overtake => [255, 0, 200],
+            }),
+            Indicator::Tyre(tyre) => match tyre {
+                Tyre::Unknown => Rgb::OFF,
+                Tyre::Soft => Rgb([255, 0, 0]),
+                Tyre::Medium => Rgb([255, 200, 0]),
+                Tyre::Hard => Rgb([255, 255, 255]),
+                Tyre::Inter => Rgb([0, 255, 0]),
+                Tyre::Wet => Rgb([0, 80, 255]),
+            },
+            Indicator::Gear(-1) => Rgb([255, 0, 200]),
+            Indicator::Gear(0) => Rgb([60, 60, 60]),
+            Indicator::Gear(gear) => gap_color(gear.unsigned_abs().saturating_sub(1).into(), 7),
+            Indicator::TyreWarning(levels) => levels.into_iter().max().unwrap().color(),
+            Indicator::TemperatureWarning { engine, brakes } => engine.max(brakes).color(),
+            Indicator::LowFuel(false) | Indicator::Rain(false) => Rgb::OFF,
+            Indicator::LowFuel(true) => Rgb([255, 80, 0]),
+            Indicator::Rain(true) => Rgb([0, 80, 255]),
+            Indicator::LapDelta { delta: None, .. } => Rgb::OFF,
+            Indicator::LapDelta { purple: true, .. } => Rgb([160, 0, 255]),
+            Indicator::LapDelta {
+                delta: Some(delta), ..
+            } if delta <= 0 => Rgb([0, 255, 0]),
+            Indicator::LapDelta { .. } => Rgb([255, 0, 0]),
+            Indicator::Gap { gap: None, .. } => Rgb::OFF,
+            Indicator::Gap {
+                gap: Some(gap),
+                full,
+            } => gap_color(gap, full),
+            Indicator::Progress(0) => Rgb::OFF,
+            Indicator::Progress(_) => Rgb([255, 255, 255]),
+            Indicator::MarshalPosts(0) => Rgb::OFF,
+            Indicator::MarshalPosts(_) => Rgb([255, 200, 0]),
+            Indicator::PositionBoard(teams) => team_color(teams[0]),
+            Indicator::TimingTower(intervals) => intervals
+                .into_iter()
+                .flatten()
+                .max_by_key(|&interval| interval as u8)
+                .map_or(Rgb::OFF, Interval::color),
+        }
+    }
+
+    /// Whether outputs that can flash should flash the color, only done for the warnings and the
+    /// RPM past the redline.
+    pub fn flashing(self) -> bool {
+        matches!(
+            self,
+            Indicator::LowFuel(true) | Indicator::Rain(true) | Indicator::Rpm { redline: true, .. }
+        )
+    }
+
+    /// The color of each of `count` lights, for outputs that have more than one.
+    ///
+    /// Rev lights fill up from the first light, a third green, a third red and a third blue. The
+    /// RPM fills up from green to red, all red past the redline. ERS fills up as far as the battery
+    /// is charged in the color of the deploy mode. The position board has a light in the color of
+    /// the team for every car, the leader first, and the timing tower a light for every car that is
+    /// green in a battle and yellow when close. The gap fills up as far as it is to the gap that
+    /// fills the lights, the progress as far into the session as the player is. The tyre warning
+    /// splits the lights in four, from the front left to the rear right tyre.
+    pub fn lights(self, count: usize) -> Vec<Rgb> {
+        match self {
+            Indicator::RevLights(percent) => (0..count)
+                .map(|light| {
+                    if usize::from(percent) * count > light * 100 {
+                        rev_light_color(light, count)
+                    } else {
+                        Rgb::OFF
+                    }
+                })
+                .collect(),
+            Indicator::Ers { charge, .. } | Indicator::Progress(charge) => (0..count)
+                .map(|light| {
+                    if usize::from(charge) * count > light * 100 {
+                        self.color()
+                    } else {
+                        Rgb::OFF
+                    }
+                })
+                .collect(),
+            Indicator::Rpm {
+                level,
+                redline: false,
+            } => (0..count)
+                .map(|light| {
+                    if usize::from(level) * count > light * 255 {
+                        gap_color(light as u32, count.saturating_sub(1) as u32)
+                    } else {
+                        Rgb::OFF
+                    }
+                })
+                .collect(),
+            Indicator::PositionBoard(teams) => (0..count)
+                .map(|light| teams.get(light).map_or(Rgb::OFF, |&team| team_color(team)))
+                .collect(),
+            Indicator::Gap {
+                gap: Some(gap),
+                full,
+            } => (0..count)
+                .map(|light| {
+                    if u64::from(gap) * count as u64 > light as u64 * u64::from(full) {
+                        self.color()
+                    } else {
+                        Rgb::OFF
+                    }
+                })
+                .collect(),
+            Indicator::TyreWarning(levels) => (0..count)
+                .map(|light| levels[light * 4 / count].color())
+                .collect(),
+            Indicator::TimingTower(intervals) => (0..count)
+                .map(|light| {
+                    intervals
+                        .get(light)
+                        .copied()
+                        .flatten()
+                        .map_or(Rgb::OFF, Interval::color)
+                })
+                .collect(),
+            Indicator::Drs(_)
+            | Indicator::Tyre(_)
+            | Indicator::Gear(_)
+            | Indicator::Rpm { redline: true, .. }
+            | Indicator::TemperatureWarning { .. }
+            | Indicator::LowFuel(_)
+            | Indicator::Rain(_)
+            | Indicator::LapDelta { .. }
+            | Indicator::Gap { gap: None, .. }
+            | Indicator::MarshalPosts(_) => {
+                vec![self.color(); count]
+            }
+        }
+    }
+}
+
+/// The color of an F1 team by its id in the telemetry, white for the teams of other series and
+/// my team.
+fn team_color(team: u8) -> Rgb {
+    Rgb(match team {
+        NO_CAR => return Rgb::OFF,
+        0 => [0, 210, 190],   // Mercedes
+        1 => [220, 0, 0],     // Ferrari
+        2 => [30, 65, 255],   // Red Bull
+        3 => [100, 196, 255], // Williams
+        4 => [0, 111, 98],    // Aston Martin
+        5 => [255, 100, 200], // Alpine
+        6 => [102, 146, 255], // RB
+        7 => [182, 186, 189], // Haas
+        8 => [255, 128, 0],   // McLaren
+        9 => [82, 226, 82],   // Sauber
+        _ => [255, 255, 255],
+    })
+}
+
+/// Green at no gap, yellow halfway to `full` and red from there on.
+fn gap_color(gap: u32, full: u32) -> Rgb {
+    let part = match full {
+        0 => 1.0,
+        full => (gap as f32 / full as f32).min(1.0),
+    };
+    Rgb([
+        (255.0 * (part * 2.0).min(1.0)) as u8,
+        (255.0 * ((1.0 - part) * 2.0).min(1.0)) as u8,
+        0,
+    ])
+}
+
+fn rev_light_color(light: usize, count: usize) -> Rgb {
+    match light * 3 / count {
+        0 => Rgb([0, 255, 0]),
+        1 => Rgb([255, 0, 0]),
+        _ => Rgb([0, 0, 255]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flag::Flag;
+    use crate::flag::GlobalFlag;
+    use crate::flag::LocalFlag;
+    use crate::flag::PenaltyKind;
+
+    const KINDS: [IndicatorKind; 16] = [
+        IndicatorKind::Drs,
+        IndicatorKind::RevLights,
+        IndicatorKind::Ers,
+        IndicatorKind::LowFuel,
+        IndicatorKind::Rain,
+        IndicatorKind::LapDelta,
+        IndicatorKind::MarshalPosts,
+        IndicatorKind::PositionBoard,
+        IndicatorKind::TimingTower,
+        IndicatorKind::Gap,
+        IndicatorKind::Progress,
+        IndicatorKind::Tyre,
+        IndicatorKind::TyreWarning,
+        IndicatorKind::TemperatureWarning,
+        IndicatorKind::Gear,
+        IndicatorKind::Rpm,
+    ];
+
+    /// The number a code starts with, which tells the controller what it is.
+    fn id(code: &str) -> u8 {
+        code.split(',').next().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn flags_and_indicators_keep_to_their_codes() {
+        let flags = [
+            Flag::Local(LocalFlag::Green),
+            Flag::Local(LocalFlag::Yellow),
+            Flag::Local(LocalFlag::DoubleYellow),
+            Flag::Local(LocalFlag::Blue),
+            Flag::Global(GlobalFlag::Vsc),
+            Flag::Global(GlobalFlag::Sc),
+            Flag::Global(GlobalFlag::Red),
+            Flag::Penalty {
+                number: 0,
+                kind: PenaltyKind::Time,
+                driver: None,
+            },
+            Flag::Penalty {
+                number: 0,
+                kind: PenaltyKind::DriveThrough,
+                driver: None,
+            },
+            Flag::Penalty {
+                number: 0,
+                kind: PenaltyKind::StopGo,
+                driver: None,
+            },
+            Flag::Finish,
+            Flag::PitLimiter,
+            Flag::FastestLap,
+            Flag::Global(GlobalFlag::VscEnding),
+            Flag::Global(GlobalFlag::ScEnding),
+            Flag::Global(GlobalFlag::FormationLap),
+            Flag::Black,
+            Flag::BlackAndWhite,
+            Flag::StartLights(5),
+            Flag::Ambient(20),
+            Flag::PositionGained,
+            Flag::PositionLost,
+            Flag::Countdown(3),
+            Flag::PitLane,
+            Flag::PitStop,
+            Flag::PitThisLap,
+            Flag::Damage,
+            Flag::Motion(50),
+            Flag::Collision { red: true },
+        ];
+        for flag in flags {
+            let code = flag.to_enum_str();
+            assert!(id(&code) < 50, "{flag:?} has the indicator code {code}");
+        }
+
+        let codes = KINDS.map(|kind| Indicator::off(kind).to_enum_str());
+        for (kind, code) in KINDS.iter().zip(&codes) {
+            assert!(
+                (50..100).contains(&id(code)),
+                "{kind:?} has the code {code}"
+            );
+            let same = codes.iter().filter(|other| id(other) == id(code)).count();
+            assert_eq!(same, 1, "{kind:?} shares the code {code}");
+        }
+    }
+
+    #[test]
+    fn position_board_skips_the_places_left() {
+        let mut teams = [NO_CAR; MAX_CARS_IN_SESSION];
+        teams[..3].copy_from_slice(&[0, 8, 1]);
+        let board = Indicator::PositionBoard(teams);
+        assert_eq!(board.to_enum_str(), "57,0,8,1");
+        assert_eq!(
+            board.lights(4),
+            [
+                Rgb([0, 210, 190]),
+                Rgb([255, 128, 0]),
+                Rgb([220, 0, 0]),
+                Rgb::OFF
+            ]
+        );
+    }
+
+    #[test]
+    fn timing_tower_is_green_with_any_battle() {
+        let mut intervals = [None; MAX_CARS_IN_SESSION];
+        intervals[..4].copy_from_slice(&[
+            Some(Interval::Clear),
+            Some(Interval::Battle),
+            Some(Interval::Close),
+            Some(Interval::Clear),
+        ]);
+        let tower = Indicator::TimingTower(intervals);
+        assert_eq!(tower.to_enum_str(), "58,0,2,1,0");
+        assert_eq!(
+            tower.lights(5),
+            [
+                Rgb::OFF,
+                Rgb([0, 255, 0]),
+                Rgb([255, 200, 0]),
+                Rgb::OFF,
+                Rgb::OFF
+            ]
+        );
+        assert_eq!(tower.color(), Rgb([0, 255, 0]));
+    }
+
+    #[test]
+    fn gap_fills_the_lights_towards_the_full_gap() {
+        let halfway = Indicator::Gap {
+            gap: Some(5_000),
+            full: 10_000,
+        };
+        assert_eq!(halfway.to_enum_str(), "59,5000");
+        assert_eq!(halfway.color(), Rgb([255, 255, 0]));
+        assert_eq!(
+            halfway.lights(4),
+            [Rgb([255, 255, 0]), Rgb([255, 255, 0]), Rgb::OFF, Rgb::OFF]
+        );
+
+        let past = Indicator::Gap {
+            gap: Some(12_000),
+            full: 10_000,
+        };
+        assert_eq!(past.color(), Rgb([255, 0, 0]));
+        assert_eq!(past.lights(2), [Rgb([255, 0, 0]); 2]);
+
+        let none = Indicator::Gap {
+            gap: None,
+            full: 3_000,
+        };
+        assert_eq!(none.to_enum_str(), "59,c");
+        assert_eq!(none.lights(3), [Rgb::OFF; 3]);
+    }
+
+    #[test]
+    fn tyre_sends_the_compound_in_its_color() {
+        let tyre = Indicator::Tyre(Tyre::Inter);
+        assert_eq!(tyre.to_enum_str(), "61,4");
+        assert_eq!(tyre.color(), Rgb([0, 255, 0]));
+    }
+
+    #[test]
+    fn tyre_warning_sends_the_level_of_every_corner() {
+        let warning = Indicator::TyreWarning([
+            WarningLevel::None,
+            WarningLevel::Red,
+            WarningLevel::Amber,
+            WarningLevel::None,
+        ]);
+        assert_eq!(warning.to_enum_str(), "62,0,2,1,0");
+        assert_eq!(warning.color(), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn gear_goes_from_green_to_red() {
+        assert_eq!(Indicator::Gear(-1).to_enum_str(), "64,-1");
+        assert_eq!(Indicator::Gear(1).color(), Rgb([0, 255, 0]));
+        assert_eq!(Indicator::Gear(8).color(), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn rpm_flashes_red_past_the_redline() {
+        let indicator = Indicator::Rpm {
+            level: 255,
+            redline: true,
+        };
+        assert_eq!(indicator.to_enum_str(), "65,255,1");
+        assert!(indicator.flashing());
+        assert_eq!(indicator.lights(3), [Rgb([255, 0, 0]); 3]);
+    }
+}
@@ -0,0 +1,79 @@
+//! Assetto Corsa Competizione through its shared memory.
+use std::io;
+
+use async_trait::async_trait;
+
+use super::FlagSnapshot;
+use super::InputSource;
+use super::shared_memory::Poller;
+use super::shared_memory::SharedMemory;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+const GRAPHICS_PAGE: &str = "Local\\acpmf_graphics";
+
+// Offsets in `SPageFileGraphic` of the ACC shared memory documentation
+const STATUS_OFFSET: usize = 4;
+const FLAG_OFFSET: usize = 1224;
+const GLOBAL_YELLOW_OFFSET: usize = 1500;
+const GLOBAL_CHEQUERED_OFFSET: usize = 1524;
+const GLOBAL_RED_OFFSET: usize = 1528;
+
+const STATUS_OFF: i32 = 0;
+const FLAG_CHECKERED: i32 = 5;
+
+/// Reads the flags from a running ACC.
+///
+/// A yellow over the whole track (full course yellow) is shown as a VSC and the white flag for a
+/// slow car ahead as a yellow.
+#[derive(Debug)]
+pub struct AccInput {
+    poller: Poller,
+}
+
+impl Default for AccInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AccInput {
+    /// The game does not have to be running yet, it is picked up once it starts.
+    pub fn new() -> Self {
+        Self {
+            poller: Poller::new("ACC", GRAPHICS_PAGE),
+        }
+    }
+}
+
+fn snapshot(memory: &SharedMemory) -> io::Result<FlagSnapshot> {
+    let flag = memory.read_i32(FLAG_OFFSET)?;
+    let global_flag = if memory.read_i32(GLOBAL_RED_OFFSET)? != 0 {
+        Some(GlobalFlag::Red)
+    } else if memory.read_i32(GLOBAL_YELLOW_OFFSET)? != 0 {
+        Some(GlobalFlag::Vsc)
+    } else {
+        None
+    };
+
+    Ok(FlagSnapshot {
+        in_session: memory.read_i32(STATUS_OFFSET)? != STATUS_OFF,
+        local_flag: match flag {
+            1 => Some(LocalFlag::Blue),
+            2 | 4 => Some(LocalFlag::Yellow),
+            7 => Some(LocalFlag::Green),
+            // No flag, and the black, penalty and orange flags the lights have no color for
+            _ => None,
+        },
+        global_flag,
+        finished: flag == FLAG_CHECKERED || memory.read_i32(GLOBAL_CHEQUERED_OFFSET)? != 0,
+    })
+}
+
+#[async_trait]
+impl InputSource for AccInput {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        self.poller.next_event(snapshot).await.map(Some)
+    }
+}
@@ -0,0 +1,128 @@
+//! Automobilista 2 and Project CARS 2 through their UDP telemetry (protocol version 2).
+use std::collections::VecDeque;
+use std::io;
+
+use async_trait::async_trait;
+use tokio::net::ToSocketAddrs;
+use tokio::net::UdpSocket;
+
+use super::FlagSnapshot;
+use super::InputSource;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+/// The port the games send to, they broadcast on the local network.
+pub const AMS2_PORT: u16 = 5606;
+
+// Offsets in `PacketBase`
+const PACKET_TYPE_OFFSET: usize = 10;
+const TIMINGS_PACKET: u8 = 3;
+const GAME_STATE_PACKET: u8 = 4;
+
+// Offsets in `sTimingsData`
+const TIMINGS_SIZE: usize = 1063;
+const PARTICIPANTS_OFFSET: usize = 33;
+const PARTICIPANT_SIZE: usize = 32;
+const MAX_PARTICIPANTS: usize = 32;
+const LOCAL_PARTICIPANT_INDEX_OFFSET: usize = 1057;
+
+// Offsets in `sParticipantInfo`
+const HIGHEST_FLAG_OFFSET: usize = 16;
+const RACE_STATE_OFFSET: usize = 20;
+
+// Offsets in `sGameStateData`
+const GAME_STATE_OFFSET: usize = 14;
+
+// Flag colours, the lower nibble of `sHighestFlag`
+const FLAG_GREEN: u8 = 1;
+const FLAG_BLUE: u8 = 2;
+const FLAG_RED: u8 = 5;
+const FLAG_YELLOW: u8 = 6;
+const FLAG_DOUBLE_YELLOW: u8 = 7;
+const FLAG_CHEQUERED: u8 = 11;
+
+const RACE_STATE_FINISHED: u8 = 3;
+
+// Game states, the lower nibble of `mGameState`
+const GAME_EXITED: u8 = 0;
+const GAME_FRONT_END: u8 = 1;
+
+/// Listens for the UDP telemetry of Automobilista 2 or Project CARS 2.
+///
+/// Set the UDP protocol version to "Project CARS 2" in the game's options.
+#[derive(Debug)]
+pub struct Ams2Input {
+    socket: UdpSocket,
+    current: FlagSnapshot,
+    reported: FlagSnapshot,
+    pending: VecDeque<FlagEvent>,
+}
+
+impl Ams2Input {
+    pub async fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(address).await?,
+            current: FlagSnapshot::default(),
+            reported: FlagSnapshot::default(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Updates the current flags with a packet, packets without flags are ignored.
+    fn apply(&mut self, packet: &[u8]) {
+        match packet.get(PACKET_TYPE_OFFSET) {
+            Some(&TIMINGS_PACKET) if packet.len() >= TIMINGS_SIZE => self.apply_timings(packet),
+            Some(&GAME_STATE_PACKET) if packet.len() > GAME_STATE_OFFSET => {
+                let state = packet[GAME_STATE_OFFSET] & 0x0f;
+                self.current.in_session = state != GAME_EXITED && state != GAME_FRONT_END;
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_timings(&mut self, packet: &[u8]) {
+        let index = u16::from_le_bytes([
+            packet[LOCAL_PARTICIPANT_INDEX_OFFSET],
+            packet[LOCAL_PARTICIPANT_INDEX_OFFSET + 1],
+        ]);
+        let index = usize::from(index);
+        if index >= MAX_PARTICIPANTS {
+            // Spectating or not in a session
+            return;
+        }
+
+        let participant = PARTICIPANTS_OFFSET + index * PARTICIPANT_SIZE;
+        let flag = packet[participant + HIGHEST_FLAG_OFFSET] & 0x0f;
+        let race_state = packet[participant + RACE_STATE_OFFSET] & 0x07;
+        self.current = FlagSnapshot {
+            in_session: true,
+            local_flag: match flag {
+                FLAG_GREEN => Some(LocalFlag::Green),
+                FLAG_BLUE => Some(LocalFlag::Blue),
+                FLAG_YELLOW => Some(LocalFlag::Yellow),
+                FLAG_DOUBLE_YELLOW => Some(LocalFlag::DoubleYellow),
+                _ => None,
+            },
+            global_flag: (flag == FLAG_RED).then_some(GlobalFlag::Red),
+            finished: flag == FLAG_CHEQUERED || race_state == RACE_STATE_FINISHED,
+        };
+    }
+}
+
+#[async_trait]
+impl InputSource for Ams2Input {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        let mut buf = [0; 2048];
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let len = self.socket.recv(&mut buf).await?;
+            self.apply(&buf[..len]);
+            self.pending.extend(self.reported.changes(&self.current));
+            self.reported = self.current;
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! The F1 games through their UDP telemetry.
+use std::collections::VecDeque;
+use std::io;
+use std::net::Ipv4Addr;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use tokio::net::ToSocketAddrs;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tracing::debug;
+use tracing::warn;
+
+use super::InputSource;
+use crate::capture::CaptureWriter;
+use crate::dump::PacketDump;
+use crate::event::FlagEvent;
+use crate::game::PacketCounter;
+use crate::game::PacketParser;
+use crate::game::PacketStatistics;
+
+/// The port the games send to by default.
+pub const F1_PORT: u16 = 20888;
+
+/// Share of lost packets above which a warning is logged.
+pub const LOSS_WARNING: f64 = 0.05;
+
+/// Listens for F1 23, 24 or 25 telemetry, optionally forwarding and recording every packet.
+#[derive(Debug)]
+pub struct F1Input {
+    socket: UdpSocket,
+    parser: PacketParser,
+    forward: Vec<String>,
+    capture: Option<CaptureWriter>,
+    dump: Option<PacketDump>,
+    buf: Box<[u8; 2048]>,
+    /// Events of the last packet that were not returned yet
+    pending: VecDeque<FlagEvent>,
+    /// Parser and events of the second player in split-screen
+    second_player: Option<(PacketParser, mpsc::UnboundedSender<FlagEvent>)>,
+    counter: PacketCounter,
+    statistics: watch::Sender<PacketStatistics>,
+    session_uid: watch::Sender<Option<u64>>,
+}
+
+/// The events of the second player in split-screen, from the packets the [`F1Input`] receives.
+///
+/// Only gets events while the [`F1Input`] is read, and ends when it is dropped.
+#[derive(Debug)]
+pub struct SecondPlayerInput {
+    events: mpsc::UnboundedReceiver<FlagEvent>,
+}
+
+impl F1Input {
+    pub async fn bind(address: impl ToSocketAddrs, parser: PacketParser) -> io::Result<Self> {
+        Self::new(UdpSocket::bind(address).await?, parser)
+    }
+
+    /// Listens on a socket that is already bound, like one from systemd. Needs a Tokio runtime.
+    pub fn from_std(socket: std::net::UdpSocket, parser: PacketParser) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        Self::new(UdpSocket::from_std(socket)?, parser)
+    }
+
+    fn new(socket: UdpSocket, parser: PacketParser) -> io::Result<Self> {
+        // So the packets can be forwarded to a broadcast address
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            parser,
+            forward: Vec::new(),
+            capture: None,
+            dump: None,
+            buf: Box::new([0; 2048]),
+            pending: VecDeque::new(),
+            second_player: None,
+            counter: PacketCounter::default(),
+            statistics: watch::Sender::new(PacketStatistics::default()),
+            session_uid: watch::Sender::new(None),
+        })
+    }
+
+    /// The packet rates and losses, updated every ten seconds. Empty while no packets come in.
+    pub fn statistics(&self) -> watch::Receiver<PacketStatistics> {
+        self.statistics.subscribe()
+    }
+
+    /// The unique id of the session the packets are from, `None` until the first packet.
+    pub fn session_uid(&self) -> watch::Receiver<Option<u64>> {
+        self.session_uid.subscribe()
+    }
+
+    /// Also receives the packets sent to the multicast `group`, the socket should be bound to
+    /// `0.0.0.0`.
+    pub fn with_multicast(self, group: Ipv4Addr) -> io::Result<Self> {
+        self.socket
+            .join_multicast_v4(group, Ipv4Addr::UNSPECIFIED)?;
+        Ok(self)
+    }
+
+    /// Sends every received packet on to `destination` unchanged, for other telemetry tools. Can
+    /// be given more than once.
+    pub fn with_forward(mut self, destination: String) -> Self {
+        self.forward.push(destination);
+        self
+    }
+
+    /// Writes every received packet to the capture.
+    pub fn with_capture(mut self, capture: CaptureWriter) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Writes the first packet of every packet id that fails to parse to the dump.
+    pub fn with_dump(mut self, dump: PacketDump) -> Self {
+        self.dump = Some(dump);
+        self
+    }
+
+    /// Parses every packet for the second player in split-screen as well, `parser` should be
+    /// set up [for the second player](PacketParser::with_second_player).
+    pub fn split_screen(mut self, parser: PacketParser) -> (Self, SecondPlayerInput) {
+        let (sender, events) = mpsc::unbounded_channel();
+        self.second_player = Some((parser, sender));
+        (self, SecondPlayerInput { events })
+    }
+
+    /// Sends the statistics of the window that is over, with a warning when many packets got lost.
+    fn publish_statistics(&mut self) {
+        let statistics = self.counter.statistics(
+            Instant::now(),
+            self.parser.late_packets(),
+            self.parser.parse_failures(),
+        );
+        if let Some(loss) = statistics.loss
+            && loss > LOSS_WARNING
+        {
+            warn!(
+                late = statistics.late,
+                "Losing {:.0}% of the telemetry packets, check the network",
+                loss * 100.0
+            );
+        }
+        self.statistics.send_replace(statistics);
+    }
+}
+
+#[async_trait]
+impl InputSource for F1Input {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let received = tokio::select! {
+                biased;
+                // Also while the game is quiet, the last rates would stay up otherwise
+                () = tokio::time::sleep_until(self.counter.window_end().into()) => None,
+                received = self.socket.recv_from(&mut self.buf[..]) => Some(received?),
+            };
+            let Some((len, _)) = received else {
+                self.publish_statistics();
+                continue;
+            };
+            let packet = &self.buf[..len];
+
+            for destination in &self.forward {
+                // Another tool not listening should not stop the lights
+                if let Err(e) = self.socket.send_to(packet, destination).await {
+                    debug!(%destination, error = %e, "Failed to forward packet");
+                }
+            }
+
+            if let Some(capture) = &mut self.capture
+                && let Err(e) = capture.write(packet)
+            {
+                warn!(error = %e, "Failed to record packet");
+            }
+
+            match self.parser.parse(packet) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => {
+                    warn!(error = %e, "Failed to parse packet");
+                    if let Some(dump) = &mut self.dump
+                        && let Err(e) = dump.write(packet, &e)
+                    {
+                        warn!(error = %e, "Failed to dump packet");
+                    }
+                }
+            }
+            let session_uid = self.parser.session_uid();
+            self.session_uid
+                .send_if_modified(|current| std::mem::replace(current, session_uid) != session_uid);
+
+            self.counter.packet(packet);
+
+            if let Some((parser, sender)) = &mut self.second_player
+                && let Ok(events) = parser.parse(packet)
+            {
+                for event in events {
+                    // Nobody is showing the second player's flags anymore
+                    let _ = sender.send(event);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl InputSource for SecondPlayerInput {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        Ok(self.events.recv().await)
+    }
+}
@@ -0,0 +1,145 @@
+//! iRacing through the memory mapped file of its SDK.
+use std::io;
+
+use async_trait::async_trait;
+
+use super::FlagSnapshot;
+use super::InputSource;
+use super::shared_memory::Poller;
+use super::shared_memory::SharedMemory;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+const MEMORY_MAPPED_FILE: &str = "Local\\IRSDKMemMapFileName";
+
+// Offsets in `irsdk_header`
+const STATUS_OFFSET: usize = 4;
+const NUM_VARS_OFFSET: usize = 24;
+const VAR_HEADER_OFFSET_OFFSET: usize = 28;
+const NUM_BUF_OFFSET: usize = 32;
+const VAR_BUF_OFFSET: usize = 48;
+const VAR_BUF_SIZE: usize = 16;
+const MAX_BUFS: usize = 4;
+
+// Offsets in `irsdk_varHeader`
+const VAR_HEADER_SIZE: usize = 144;
+const VAR_OFFSET_OFFSET: usize = 4;
+const VAR_NAME_OFFSET: usize = 16;
+
+const STATUS_CONNECTED: i32 = 1;
+
+// Bits of the `SessionFlags` variable
+const CHECKERED: u32 = 0x0001;
+const GREEN: u32 = 0x0004;
+const YELLOW: u32 = 0x0008;
+const RED: u32 = 0x0010;
+const BLUE: u32 = 0x0020;
+const YELLOW_WAVING: u32 = 0x0100;
+const ONE_LAP_TO_GREEN: u32 = 0x0200;
+const CAUTION: u32 = 0x4000;
+const CAUTION_WAVING: u32 = 0x8000;
+
+/// Reads the session flags from a running iRacing.
+///
+/// A full course caution is shown as a safety car.
+#[derive(Debug)]
+pub struct IracingInput {
+    poller: Poller,
+    /// Offset of `SessionFlags` in the variable buffers, looked up once connected
+    session_flags_offset: Option<usize>,
+}
+
+impl Default for IracingInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IracingInput {
+    /// The sim does not have to be running yet, it is picked up once it starts.
+    pub fn new() -> Self {
+        Self {
+            poller: Poller::new("iRacing", MEMORY_MAPPED_FILE),
+            session_flags_offset: None,
+        }
+    }
+}
+
+/// Finds where the variable is stored in the variable buffers.
+fn variable_offset(memory: &SharedMemory, name: &str) -> io::Result<usize> {
+    let headers = memory.read_i32(VAR_HEADER_OFFSET_OFFSET)? as usize;
+    for i in 0..memory.read_i32(NUM_VARS_OFFSET)?.max(0) as usize {
+        let header = headers + i * VAR_HEADER_SIZE;
+        let var_name: [u8; 32] = memory.read(header + VAR_NAME_OFFSET)?;
+        if var_name.split(|&b| b == 0).next() == Some(name.as_bytes()) {
+            return Ok(memory.read_i32(header + VAR_OFFSET_OFFSET)? as usize);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("iRacing has no {name} variable"),
+    ))
+}
+
+/// Reads the flags from the buffer the sim wrote last.
+fn snapshot(
+    memory: &SharedMemory,
+    session_flags_offset: &mut Option<usize>,
+) -> io::Result<FlagSnapshot> {
+    if memory.read_i32(STATUS_OFFSET)? & STATUS_CONNECTED == 0 {
+        // The variables can move between sessions
+        *session_flags_offset = None;
+        return Ok(FlagSnapshot::default());
+    }
+
+    let offset = match *session_flags_offset {
+        Some(offset) => offset,
+        None => *session_flags_offset.insert(variable_offset(memory, "SessionFlags")?),
+    };
+
+    let buffers = (memory.read_i32(NUM_BUF_OFFSET)?.max(1) as usize).min(MAX_BUFS);
+    let mut latest = (i32::MIN, 0);
+    for i in 0..buffers {
+        let buffer = VAR_BUF_OFFSET + i * VAR_BUF_SIZE;
+        let tick_count = memory.read_i32(buffer)?;
+        if tick_count > latest.0 {
+            latest = (tick_count, memory.read_i32(buffer + 4)? as usize);
+        }
+    }
+    let flags = memory.read_i32(latest.1 + offset)? as u32;
+
+    Ok(FlagSnapshot {
+        in_session: true,
+        local_flag: if flags & BLUE != 0 {
+            Some(LocalFlag::Blue)
+        } else if flags & (YELLOW | YELLOW_WAVING) != 0 {
+            Some(LocalFlag::Yellow)
+        } else if flags & GREEN != 0 {
+            Some(LocalFlag::Green)
+        } else {
+            None
+        },
+        global_flag: if flags & RED != 0 {
+            Some(GlobalFlag::Red)
+        } else if flags & (CAUTION | CAUTION_WAVING) != 0 && flags & ONE_LAP_TO_GREEN != 0 {
+            Some(GlobalFlag::ScEnding)
+        } else if flags & (CAUTION | CAUTION_WAVING) != 0 {
+            Some(GlobalFlag::Sc)
+        } else {
+            None
+        },
+        finished: flags & CHECKERED != 0,
+    })
+}
+
+#[async_trait]
+impl InputSource for IracingInput {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        let session_flags_offset = &mut self.session_flags_offset;
+        self.poller
+            .next_event(|memory| snapshot(memory, session_flags_offset))
+            .await
+            .map(Some)
+    }
+}
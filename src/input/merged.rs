@@ -0,0 +1,137 @@
+//! Several sources read at once.
+use std::collections::VecDeque;
+use std::io;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::InputSource;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+/// The events of several sources as one stream, in the order they happen.
+///
+/// Every source runs in its own task. The flags are kept per source and only the worst of them is
+/// passed on, so one source clearing its yellow does not clear the yellow of another. A source
+/// starting or ending a session resets everything, after which the flags of the others are passed
+/// on again. The stream ends once all sources ended, an error of any of them is passed on.
+#[derive(Debug)]
+pub struct MergedInput {
+    events: mpsc::Receiver<(usize, io::Result<FlagEvent>)>,
+    /// The global and local flag of every source
+    flags: Vec<(Option<GlobalFlag>, Option<LocalFlag>)>,
+    /// The worst flags passed on last
+    merged: (Option<GlobalFlag>, Option<LocalFlag>),
+    pending: VecDeque<FlagEvent>,
+}
+
+impl MergedInput {
+    pub fn new(sources: Vec<Box<dyn InputSource>>) -> Self {
+        let (sender, events) = mpsc::channel(16);
+        let flags = vec![(None, None); sources.len()];
+        for (index, mut source) in sources.into_iter().enumerate() {
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                loop {
+                    let event = match source.next_event().await {
+                        Ok(Some(event)) => Ok(event),
+                        Ok(None) => return,
+                        Err(e) => Err(e),
+                    };
+                    let failed = event.is_err();
+                    if sender.send((index, event)).await.is_err() || failed {
+                        return;
+                    }
+                }
+            });
+        }
+        Self {
+            events,
+            flags,
+            merged: (None, None),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queues the events that bring the merged flags to the worst flags of the sources.
+    fn merge(&mut self) {
+        let global = self
+            .flags
+            .iter()
+            .filter_map(|(global, _)| *global)
+            .max_by_key(|&flag| global_severity(flag));
+        let local = self
+            .flags
+            .iter()
+            .filter_map(|(_, local)| *local)
+            .max_by_key(|&flag| local_severity(flag));
+        if global != self.merged.0 {
+            self.pending.push_back(match global {
+                Some(flag) => FlagEvent::SetGlobalFlag(flag),
+                None => FlagEvent::ResetGlobalFlag,
+            });
+        }
+        if local != self.merged.1 {
+            self.pending.push_back(match local {
+                Some(flag) => FlagEvent::SetLocalFlag(flag),
+                None => FlagEvent::ResetLocalFlag,
+            });
+        }
+        self.merged = (global, local);
+    }
+}
+
+/// How bad a global flag is, the worst of the sources is shown.
+fn global_severity(flag: GlobalFlag) -> u8 {
+    match flag {
+        GlobalFlag::FormationLap => 0,
+        GlobalFlag::VscEnding => 1,
+        GlobalFlag::ScEnding => 2,
+        GlobalFlag::Vsc => 3,
+        GlobalFlag::Sc => 4,
+        GlobalFlag::Red => 5,
+    }
+}
+
+/// How bad a local flag is, the worst of the sources is shown.
+fn local_severity(flag: LocalFlag) -> u8 {
+    match flag {
+        LocalFlag::Green => 0,
+        LocalFlag::Blue => 1,
+        LocalFlag::Yellow => 2,
+        LocalFlag::DoubleYellow => 3,
+    }
+}
+
+#[async_trait]
+impl InputSource for MergedInput {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let Some((index, event)) = self.events.recv().await else {
+                return Ok(None);
+            };
+            let flags = &mut self.flags[index];
+            match event? {
+                FlagEvent::SetGlobalFlag(flag) => flags.0 = Some(flag),
+                FlagEvent::ResetGlobalFlag => flags.0 = None,
+                FlagEvent::SetLocalFlag(flag) => flags.1 = Some(flag),
+                FlagEvent::ResetLocalFlag => flags.1 = None,
+                FlagEvent::Reset => {
+                    *flags = (None, None);
+                    self.merged = (None, None);
+                    self.pending.push_back(FlagEvent::Reset);
+                }
+                event => {
+                    self.pending.push_back(event);
+                    continue;
+                }
+            }
+            self.merge();
+        }
+    }
+}
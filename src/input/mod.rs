@@ -0,0 +1,84 @@
+//! The games the flags can come from.
+use std::fmt::Debug;
+use std::io;
+
+use async_trait::async_trait;
+
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+#[cfg(windows)]
+pub mod acc;
+pub mod ams2;
+pub mod f1;
+#[cfg(windows)]
+pub mod iracing;
+mod merged;
+pub mod replay;
+#[cfg(windows)]
+pub mod rfactor2;
+#[cfg(windows)]
+mod shared_memory;
+
+#[cfg(windows)]
+pub use acc::AccInput;
+pub use ams2::Ams2Input;
+pub use f1::F1Input;
+pub use f1::SecondPlayerInput;
+#[cfg(windows)]
+pub use iracing::IracingInput;
+pub use merged::MergedInput;
+pub use replay::ReplayCommand;
+pub use replay::ReplayInput;
+#[cfg(windows)]
+pub use rfactor2::RFactor2Input;
+
+/// A game that reports the flags as [`FlagEvent`]s.
+///
+/// Implement this to take the flags from a game the crate does not support out of the box.
+#[async_trait]
+pub trait InputSource: Debug + Send {
+    /// Waits for the next event from the game, `None` when the source ended.
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>>;
+}
+
+/// The flags a game shows at one moment, for games that are polled instead of sending events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct FlagSnapshot {
+    /// False in the menus, leaving a session clears everything
+    pub(crate) in_session: bool,
+    pub(crate) local_flag: Option<LocalFlag>,
+    pub(crate) global_flag: Option<GlobalFlag>,
+    pub(crate) finished: bool,
+}
+
+impl FlagSnapshot {
+    /// The events that lead from this snapshot to `next`.
+    pub(crate) fn changes(&self, next: &FlagSnapshot) -> Vec<FlagEvent> {
+        let mut events = Vec::new();
+        if !next.in_session {
+            if self.in_session {
+                events.push(FlagEvent::Reset);
+            }
+            return events;
+        }
+
+        if next.local_flag != self.local_flag {
+            events.push(match next.local_flag {
+                Some(flag) => FlagEvent::SetLocalFlag(flag),
+                None => FlagEvent::ResetLocalFlag,
+            });
+        }
+        if next.global_flag != self.global_flag {
+            events.push(match next.global_flag {
+                Some(flag) => FlagEvent::SetGlobalFlag(flag),
+                None => FlagEvent::ResetGlobalFlag,
+            });
+        }
+        if next.finished && !self.finished {
+            events.push(FlagEvent::Finish);
+        }
+        events
+    }
+}
@@ -0,0 +1,253 @@
+//! Captures made with [`F1Input::with_capture`](super::F1Input::with_capture) played back.
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::info;
+use tracing::warn;
+
+use super::InputSource;
+use crate::capture::CaptureReader;
+use crate::capture::CapturedPacket;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::game::PacketParser;
+use crate::game::session_time;
+
+/// Controls a replay while it plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayCommand {
+    /// Pause, or go on when paused
+    TogglePause,
+    /// Play on right away up to the next packet with events other than the indicators, and pause
+    /// there
+    Step,
+}
+
+/// Plays the packets of a capture with their original timing, scaled by `speed`.
+#[derive(Debug)]
+pub struct ReplayInput {
+    capture: CaptureReader,
+    parser: PacketParser,
+    speed: f64,
+    /// When the packet with the timestamp is played, set by the first packet asked for
+    clock: Option<(Instant, Duration)>,
+    /// Events of the last packet that were not returned yet
+    pending: VecDeque<FlagEvent>,
+    /// The packet read but not due yet, kept when waiting for it is cut short
+    next: Option<CapturedPacket>,
+    session_uid: watch::Sender<Option<u64>>,
+    /// Skipping the packets before a session time
+    seek: Option<Seek>,
+    commands: Option<mpsc::UnboundedReceiver<ReplayCommand>>,
+    paused: bool,
+    stepping: bool,
+}
+
+/// What the packets skipped over left behind, the rest of their events only mattered then.
+#[derive(Debug)]
+struct Seek {
+    start_at: f32,
+    participants: Option<FlagEvent>,
+    session: Option<FlagEvent>,
+    pit_limiter: Option<FlagEvent>,
+    local_flag: Option<LocalFlag>,
+    global_flag: Option<GlobalFlag>,
+    finished: bool,
+}
+
+impl Seek {
+    fn skip(&mut self, event: FlagEvent) {
+        match event {
+            FlagEvent::Participants { .. } => self.participants = Some(event),
+            FlagEvent::Session(_) => self.session = Some(event),
+            FlagEvent::PitLimiter(_) => self.pit_limiter = Some(event),
+            FlagEvent::SetLocalFlag(flag) => self.local_flag = Some(flag),
+            FlagEvent::ResetLocalFlag => self.local_flag = None,
+            FlagEvent::SetGlobalFlag(flag) => self.global_flag = Some(flag),
+            FlagEvent::ResetGlobalFlag => self.global_flag = None,
+            FlagEvent::Finish => self.finished = true,
+            FlagEvent::Reset => {
+                self.pit_limiter = None;
+                self.local_flag = None;
+                self.global_flag = None;
+                self.finished = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// The events that bring the outputs to where the replay starts, the global flag first so a
+    /// local flag under it does not flash.
+    fn events(self) -> impl Iterator<Item = FlagEvent> {
+        [
+            self.participants,
+            self.session,
+            self.pit_limiter,
+            self.global_flag.map(FlagEvent::SetGlobalFlag),
+            self.local_flag.map(FlagEvent::SetLocalFlag),
+            self.finished.then_some(FlagEvent::Finish),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl ReplayInput {
+    pub fn new(capture: CaptureReader, parser: PacketParser, speed: f64) -> io::Result<Self> {
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the replay speed has to be above 0",
+            ));
+        }
+        Ok(Self {
+            capture,
+            parser,
+            speed,
+            clock: None,
+            pending: VecDeque::new(),
+            next: None,
+            session_uid: watch::Sender::new(None),
+            seek: None,
+            commands: None,
+            paused: false,
+            stepping: false,
+        })
+    }
+
+    /// Starts playing at the first packet at or after `session_time` seconds. The packets before
+    /// are only read for the drivers, the session and the flags out at that time.
+    pub fn with_start_at(mut self, session_time: f32) -> Self {
+        self.seek = Some(Seek {
+            start_at: session_time,
+            participants: None,
+            session: None,
+            pit_limiter: None,
+            local_flag: None,
+            global_flag: None,
+            finished: false,
+        });
+        self
+    }
+
+    /// Pauses and steps through the replay by the `commands`.
+    pub fn with_commands(mut self, commands: mpsc::UnboundedReceiver<ReplayCommand>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    /// The unique id of the session the packets are from, `None` until the first packet.
+    pub fn session_uid(&self) -> watch::Receiver<Option<u64>> {
+        self.session_uid.subscribe()
+    }
+
+    /// Waits until the packet with `timestamp` is due, or for the commands while paused.
+    async fn wait(&mut self, timestamp: Duration, session_time: f32) {
+        loop {
+            if self.paused {
+                match next_command(&mut self.commands).await {
+                    Some(ReplayCommand::TogglePause) => info!(session_time, "Resumed the replay"),
+                    Some(ReplayCommand::Step) => self.stepping = true,
+                    // Nothing can resume it anymore
+                    None => {}
+                }
+                self.paused = false;
+                self.clock = Some((Instant::now(), timestamp));
+            }
+            if self.stepping {
+                return;
+            }
+
+            let (started, offset) = *self.clock.get_or_insert((Instant::now(), Duration::ZERO));
+            let due = started + timestamp.saturating_sub(offset).div_f64(self.speed);
+            tokio::select! {
+                () = tokio::time::sleep_until(due) => return,
+                command = next_command(&mut self.commands) => match command {
+                    Some(ReplayCommand::TogglePause) => {
+                        info!(session_time, "Paused the replay");
+                        self.paused = true;
+                    }
+                    Some(ReplayCommand::Step) => self.stepping = true,
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+/// The next command, never comes without any.
+async fn next_command(
+    commands: &mut Option<mpsc::UnboundedReceiver<ReplayCommand>>,
+) -> Option<ReplayCommand> {
+    match commands {
+        Some(receiver) => match receiver.recv().await {
+            Some(command) => Some(command),
+            None => {
+                *commands = None;
+                None
+            }
+        },
+        None => std::future::pending().await,
+    }
+}
+
+#[async_trait]
+impl InputSource for ReplayInput {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let packet = match self.next.take() {
+                Some(packet) => packet,
+                None => match self.capture.read()? {
+                    Some(packet) => packet,
+                    None => return Ok(None),
+                },
+            };
+            let time = session_time(&packet.data).ok();
+            if let Some(seek) = &mut self.seek {
+                if time.is_none_or(|time| time < seek.start_at) {
+                    for event in self.parser.parse(&packet.data).unwrap_or_default() {
+                        seek.skip(event);
+                    }
+                    continue;
+                }
+                info!(session_time = time, "Starting the replay");
+                self.pending.extend(self.seek.take().unwrap().events());
+                // The skipped packets do not count for the timing
+                self.clock = Some((Instant::now(), packet.timestamp));
+            }
+
+            let timestamp = packet.timestamp;
+            self.next = Some(packet);
+            self.wait(timestamp, time.unwrap_or_default()).await;
+            let packet = self.next.take().expect("the packet waited for is kept");
+            match self.parser.parse(&packet.data) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => warn!(error = %e, "Failed to parse packet"),
+            }
+            // The indicators and the motion change all the time
+            if self.stepping
+                && self
+                    .pending
+                    .iter()
+                    .any(|event| !matches!(event, FlagEvent::Indicator(_) | FlagEvent::Motion(_)))
+            {
+                info!(session_time = time, "Paused the replay");
+                self.stepping = false;
+                self.paused = true;
+            }
+            let session_uid = self.parser.session_uid();
+            self.session_uid
+                .send_if_modified(|current| std::mem::replace(current, session_uid) != session_uid);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! rFactor 2 and Le Mans Ultimate through the rF2 Shared Memory Map Plugin.
+use std::io;
+
+use async_trait::async_trait;
+
+use super::FlagSnapshot;
+use super::InputSource;
+use super::shared_memory::Poller;
+use super::shared_memory::SharedMemory;
+use crate::event::FlagEvent;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+const SCORING_BUFFER: &str = "$rFactor2SMMP_Scoring$";
+
+// Offsets in `rF2Scoring` of the plugin's `rF2State.h`, the scoring info follows the version
+// block and the bytes updated hint
+const VERSION_BEGIN_OFFSET: usize = 0;
+const VERSION_END_OFFSET: usize = 4;
+const SCORING_INFO_OFFSET: usize = 12;
+const NUM_VEHICLES_OFFSET: usize = SCORING_INFO_OFFSET + 104;
+const GAME_PHASE_OFFSET: usize = SCORING_INFO_OFFSET + 108;
+const VEHICLES_OFFSET: usize = SCORING_INFO_OFFSET + 548;
+const MAX_VEHICLES: usize = 128;
+
+// Offsets in `rF2VehicleScoring`
+const VEHICLE_SIZE: usize = 584;
+const IS_PLAYER_OFFSET: usize = 196;
+const FLAG_OFFSET: usize = 504;
+const UNDER_YELLOW_OFFSET: usize = 505;
+
+// Values of `mGamePhase`
+const FULL_COURSE_YELLOW: u8 = 6;
+const SESSION_STOPPED: u8 = 7;
+const SESSION_OVER: u8 = 8;
+
+const FLAG_BLUE: u8 = 6;
+
+const MAX_READ_ATTEMPTS: usize = 10;
+
+/// Reads the flags from rFactor 2 or Le Mans Ultimate with the shared memory plugin enabled.
+///
+/// A full course yellow or safety car is shown as SC and a stopped session as red.
+#[derive(Debug)]
+pub struct RFactor2Input {
+    poller: Poller,
+}
+
+impl Default for RFactor2Input {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RFactor2Input {
+    /// The sim does not have to be running yet, it is picked up once it starts.
+    pub fn new() -> Self {
+        Self {
+            poller: Poller::new("rFactor 2", SCORING_BUFFER),
+        }
+    }
+}
+
+/// Reads the flags, retrying a few times when the plugin was writing the buffer at the same time.
+fn snapshot(memory: &SharedMemory) -> io::Result<FlagSnapshot> {
+    let mut snapshot = FlagSnapshot::default();
+    for _ in 0..MAX_READ_ATTEMPTS {
+        let version = memory.read_i32(VERSION_BEGIN_OFFSET)?;
+        snapshot = read_scoring(memory)?;
+        if memory.read_i32(VERSION_BEGIN_OFFSET)? == version
+            && memory.read_i32(VERSION_END_OFFSET)? == version
+        {
+            break;
+        }
+    }
+    Ok(snapshot)
+}
+
+fn read_scoring(memory: &SharedMemory) -> io::Result<FlagSnapshot> {
+    let vehicles = (memory.read_i32(NUM_VEHICLES_OFFSET)?.max(0) as usize).min(MAX_VEHICLES);
+    let [phase] = memory.read(GAME_PHASE_OFFSET)?;
+
+    let mut local_flag = None;
+    for i in 0..vehicles {
+        let vehicle = VEHICLES_OFFSET + i * VEHICLE_SIZE;
+        let [is_player] = memory.read(vehicle + IS_PLAYER_OFFSET)?;
+        if is_player == 0 {
+            continue;
+        }
+
+        let [flag] = memory.read(vehicle + FLAG_OFFSET)?;
+        let [under_yellow] = memory.read(vehicle + UNDER_YELLOW_OFFSET)?;
+        local_flag = if flag == FLAG_BLUE {
+            Some(LocalFlag::Blue)
+        } else if under_yellow != 0 {
+            Some(LocalFlag::Yellow)
+        } else {
+            None
+        };
+        break;
+    }
+
+    Ok(FlagSnapshot {
+        in_session: vehicles > 0,
+        local_flag,
+        global_flag: match phase {
+            FULL_COURSE_YELLOW => Some(GlobalFlag::Sc),
+            SESSION_STOPPED => Some(GlobalFlag::Red),
+            _ => None,
+        },
+        finished: phase == SESSION_OVER,
+    })
+}
+
+#[async_trait]
+impl InputSource for RFactor2Input {
+    async fn next_event(&mut self) -> io::Result<Option<FlagEvent>> {
+        self.poller.next_event(snapshot).await.map(Some)
+    }
+}
@@ -0,0 +1,163 @@
+//! Read-only access to the shared memory games publish their telemetry in.
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::time::Duration;
+
+use tracing::info;
+use tracing::warn;
+use windows_sys::Win32::Foundation::CloseHandle;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::Memory::FILE_MAP_READ;
+use windows_sys::Win32::System::Memory::MEMORY_BASIC_INFORMATION;
+use windows_sys::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS;
+use windows_sys::Win32::System::Memory::MapViewOfFile;
+use windows_sys::Win32::System::Memory::OpenFileMappingW;
+use windows_sys::Win32::System::Memory::UnmapViewOfFile;
+use windows_sys::Win32::System::Memory::VirtualQuery;
+
+use super::FlagSnapshot;
+use crate::event::FlagEvent;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How often to look for the game when it is not running
+const OPEN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A named file mapping created by a game, mapped for reading.
+pub(crate) struct SharedMemory {
+    handle: HANDLE,
+    view: MEMORY_MAPPED_VIEW_ADDRESS,
+    size: usize,
+}
+
+impl fmt::Debug for SharedMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+// The view is only ever read, the game on the other side writes it regardless
+unsafe impl Send for SharedMemory {}
+
+impl SharedMemory {
+    /// Opens the mapping, which only exists while the game is running.
+    pub(crate) fn open(name: &str) -> io::Result<Self> {
+        let name: Vec<u16> = OsStr::new(name).encode_wide().chain([0]).collect();
+        // SAFETY: the name is a null terminated wide string that outlives the call
+        let handle = unsafe { OpenFileMappingW(FILE_MAP_READ, 0, name.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: the handle is a valid file mapping, a size of zero maps all of it
+        let view = unsafe { MapViewOfFile(handle, FILE_MAP_READ, 0, 0, 0) };
+        if view.Value.is_null() {
+            let error = io::Error::last_os_error();
+            // SAFETY: the handle is valid and not used after this
+            unsafe { CloseHandle(handle) };
+            return Err(error);
+        }
+
+        // SAFETY: the struct is plain data, all zeroes is a valid value
+        let mut info: MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: the view is mapped and `info` is large enough for the answer
+        unsafe { VirtualQuery(view.Value, &mut info, size_of::<MEMORY_BASIC_INFORMATION>()) };
+        Ok(Self {
+            handle,
+            view,
+            size: info.RegionSize,
+        })
+    }
+
+    /// Copies `N` bytes starting at `offset`, the game may be writing them at the same time.
+    pub(crate) fn read<const N: usize>(&self, offset: usize) -> io::Result<[u8; N]> {
+        if offset + N > self.size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "read past the end of the shared memory",
+            ));
+        }
+
+        let mut bytes = [0; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            // SAFETY: the offset is within the mapped region
+            *byte = unsafe { self.view.Value.cast::<u8>().add(offset + i).read_volatile() };
+        }
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_i32(&self, offset: usize) -> io::Result<i32> {
+        self.read(offset).map(i32::from_le_bytes)
+    }
+}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        // SAFETY: both were created in `open` and are not used after this
+        unsafe {
+            UnmapViewOfFile(self.view);
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Polls the shared memory of a game and turns changes in its flags into events.
+#[derive(Debug)]
+pub(crate) struct Poller {
+    game: &'static str,
+    mapping: &'static str,
+    memory: Option<SharedMemory>,
+    last: FlagSnapshot,
+    pending: VecDeque<FlagEvent>,
+}
+
+impl Poller {
+    /// The game does not have to be running yet, it is picked up once it starts.
+    pub(crate) fn new(game: &'static str, mapping: &'static str) -> Self {
+        Self {
+            game,
+            mapping,
+            memory: None,
+            last: FlagSnapshot::default(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Waits for the next change, reading the flags with `read`.
+    pub(crate) async fn next_event(
+        &mut self,
+        mut read: impl FnMut(&SharedMemory) -> io::Result<FlagSnapshot>,
+    ) -> io::Result<FlagEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            let Some(memory) = &self.memory else {
+                match SharedMemory::open(self.mapping) {
+                    Ok(memory) => {
+                        info!(game = %self.game, "Connected");
+                        self.memory = Some(memory);
+                    }
+                    Err(_) => tokio::time::sleep(OPEN_INTERVAL).await,
+                }
+                continue;
+            };
+
+            let snapshot = read(memory).unwrap_or_else(|e| {
+                warn!(game = %self.game, error = %e, "Lost connection");
+                self.memory = None;
+                FlagSnapshot::default()
+            });
+            self.pending.extend(self.last.changes(&snapshot));
+            self.last = snapshot;
+            if self.pending.is_empty() {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Flag logic for syncing lights with the flags shown in the F1 games.
+//!
+//! Telemetry packets are translated into [`FlagEvent`]s which are applied to a [`FlagManager`].
+//! The manager decides which [`Flag`] should be shown and sends it to every [`output::Output`].
+//!
+//! ```no_run
+//! use f1_light_sync::FlagEvent;
+//! use f1_light_sync::FlagManager;
+//! use f1_light_sync::output::UdpOutput;
+//! use tokio::net::UdpSocket;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let input_socket = UdpSocket::bind("127.0.0.1:20888").await?;
+//! let output = UdpOutput::connect("192.168.1.10:1234").await?;
+//! let mut manager = FlagManager::new(vec![Box::new(output)]);
+//!
+//! let mut buf = [0; 2048];
+//! loop {
+//!     let (len, _) = input_socket.recv_from(&mut buf).await?;
+//!     if let Ok(packet) = f1_game_library_models_25::deserialise_udp_packet_from_bytes(&buf[..len])
+//!         && let Some(event) = FlagEvent::from_packet(&packet)
+//!     {
+//!         manager.handle(event).await;
+//!     }
+//! }
+//! # }
+//! ```
+
+pub mod capture;
+mod color;
+mod config;
+pub mod daemon;
+pub mod discovery;
+pub mod dump;
+mod event;
+mod flag;
+mod game;
+pub mod history;
+pub mod http;
+mod indicator;
+pub mod input;
+pub mod logging;
+mod manager;
+pub mod output;
+#[cfg(windows)]
+pub mod service;
+pub mod shutdown;
+#[cfg(target_os = "linux")]
+pub mod systemd;
+pub mod tui;
+
+pub use color::Rgb;
+pub use config::AmbientConfig;
+pub use config::BlackAndWhiteConfig;
+pub use config::CollisionConfig;
+pub use config::Config;
+pub use config::CountdownConfig;
+pub use config::DamageConfig;
+pub use config::FastestLapConfig;
+pub use config::FinishAnimationConfig;
+pub use config::FlagTable;
+pub use config::GapConfig;
+pub use config::GreenFlashConfig;
+pub use config::HttpConfig;
+pub use config::KeepaliveConfig;
+pub use config::LapDeltaConfig;
+pub use config::LowFuelConfig;
+pub use config::MarshalPostConfig;
+pub use config::OutputConfig;
+pub use config::PenaltyConfig;
+pub use config::PitConfig;
+pub use config::PositionConfig;
+pub use config::ProfileTables;
+pub use config::RainConfig;
+pub use config::RateLimitConfig;
+pub use config::RevLightsConfig;
+pub use config::RpmConfig;
+pub use config::SecondPlayerConfig;
+pub use config::TemperatureWarningConfig;
+pub use config::TimingTowerConfig;
+pub use config::TyreWarningConfig;
+pub use config::ZoneConfig;
+pub use event::FlagEvent;
+pub use flag::Abbreviation;
+pub use flag::Flag;
+pub use flag::GlobalFlag;
+pub use flag::LocalFlag;
+pub use flag::PenaltyKind;
+pub use flag::PitStatus;
+pub use flag::SessionKind;
+pub use game::AmbientTemperature;
+pub use game::GameYear;
+pub use game::GapTarget;
+pub use game::MAX_MARSHAL_POSTS;
+pub use game::MotionSource;
+pub use game::PacketParser;
+pub use game::PacketStatistics;
+pub use indicator::Drs;
+pub use indicator::ErsMode;
+pub use indicator::Indicator;
+pub use indicator::IndicatorKind;
+pub use indicator::Interval;
+pub use indicator::Tyre;
+pub use indicator::WarningLevel;
+pub use manager::BLACK_AND_WHITE_SHOW_TIME;
+pub use manager::DEFAULT_PRIORITY;
+pub use manager::Display;
+pub use manager::FlagMachine;
+pub use manager::FlagManager;
+pub use manager::FlagState;
+pub use manager::GREEN_FLASH_TIME;
+pub use manager::MAX_RETRY_DELAY;
+pub use manager::PENALTY_SHOW_TIME;
+pub use manager::Priority;
+pub use manager::RETRY_DELAY;
+pub use manager::Trigger;
@@ -0,0 +1,280 @@
+//! Log output for the [`tracing`] events and spans of the crate, as text or JSON lines on stdout
+//! or in a file.
+//!
+//! The events of other crates are only shown from warnings up.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+
+use serde_json::Map;
+use serde_json::Value;
+use tracing::Event;
+use tracing::Level;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span::Attributes;
+use tracing::span::Id;
+use tracing::span::Record;
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// `INFO session{number=1}: Showing flag flag="sc"`
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields and spans as objects
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format `{s}`, use text or json")),
+        }
+    }
+}
+
+/// Sends the log events of `level` and up to stdout in `format`, for the rest of the program. Given
+/// a `file` they are appended to it instead.
+pub fn init(level: Level, format: LogFormat, file: Option<&Path>) -> io::Result<()> {
+    let file = match file {
+        Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+        None => None,
+    };
+    let logger = Logger {
+        level,
+        format,
+        file: file.map(Mutex::new),
+        next_id: AtomicU64::new(1),
+        spans: Mutex::new(HashMap::new()),
+    };
+    tracing::subscriber::set_global_default(logger).map_err(io::Error::other)
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Span {
+    name: &'static str,
+    fields: Map<String, Value>,
+    /// Handles to the span that are still open
+    references: usize,
+}
+
+struct Logger {
+    level: Level,
+    format: LogFormat,
+    /// Where the lines go instead of stdout
+    file: Option<Mutex<File>>,
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, Span>>,
+}
+
+impl Logger {
+    fn write(&self, line: &str) {
+        let _ = match &self.file {
+            Some(file) => writeln!(file.lock().unwrap(), "{line}"),
+            None => writeln!(io::stdout().lock(), "{line}"),
+        };
+    }
+
+    fn text(
+        &self,
+        event: &Event,
+        fields: Map<String, Value>,
+        spans: &[(&str, Map<String, Value>)],
+    ) {
+        let mut line = format!("{:>5} ", event.metadata().level().as_str());
+        for (name, fields) in spans {
+            line.push_str(name);
+            if !fields.is_empty() {
+                line.push('{');
+                line.push_str(&join(fields));
+                line.push('}');
+            }
+            line.push(':');
+        }
+        if !spans.is_empty() {
+            line.push(' ');
+        }
+        let mut fields = fields;
+        if let Some(Value::String(message)) = fields.remove("message") {
+            line.push_str(&message);
+        }
+        if !fields.is_empty() {
+            let _ = write!(line, " {}", join(&fields));
+        }
+        self.write(&line);
+    }
+
+    fn json(
+        &self,
+        event: &Event,
+        fields: Map<String, Value>,
+        spans: &[(&str, Map<String, Value>)],
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let mut line = Map::new();
+        line.insert("timestamp".into(), timestamp.into());
+        line.insert("level".into(), event.metadata().level().as_str().into());
+        line.insert("target".into(), event.metadata().target().into());
+        if !spans.is_empty() {
+            let spans = spans
+                .iter()
+                .map(|(name, fields)| {
+                    let mut span = fields.clone();
+                    span.insert("name".into(), (*name).into());
+                    Value::Object(span)
+                })
+                .collect();
+            line.insert("spans".into(), Value::Array(spans));
+        }
+        line.extend(fields);
+        self.write(&Value::Object(line).to_string());
+    }
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let level = match metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            true => self.level,
+            false => self.level.min(Level::WARN),
+        };
+        *metadata.level() <= level
+    }
+
+    fn new_span(&self, span: &Attributes) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        let span = Span {
+            name: span.metadata().name(),
+            fields: fields.0,
+            references: 1,
+        };
+        self.spans.lock().unwrap().insert(id, span);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.fields.extend(fields.0);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let spans: Vec<_> = {
+            let spans = self.spans.lock().unwrap();
+            ENTERED.with_borrow(|entered| {
+                entered
+                    .iter()
+                    .filter_map(|id| spans.get(id))
+                    .map(|span| (span.name, span.fields.clone()))
+                    .collect()
+            })
+        };
+        match self.format {
+            LogFormat::Text => self.text(event, fields.0, &spans),
+            LogFormat::Json => self.json(event, fields.0, &spans),
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with_borrow_mut(|entered| entered.push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with_borrow_mut(|entered| {
+            if let Some(i) = entered.iter().rposition(|&id| id == span.into_u64()) {
+                entered.remove(i);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.references += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let Some(open) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        open.references -= 1;
+        if open.references > 0 {
+            return false;
+        }
+        spans.remove(&span.into_u64());
+        true
+    }
+}
+
+/// `key=value` pairs, strings in quotes.
+fn join(fields: &Map<String, Value>) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Default)]
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{value:?}").into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+}
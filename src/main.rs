@@ -1,305 +1,1491 @@
 use std::io;
+use std::io::BufRead;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 use std::time::Instant;
 
+use clap::Args;
 use clap::Parser;
-use f1_game_library_models_25::telemetry_data::EventType;
-use f1_game_library_models_25::telemetry_data::F1Data;
-use f1_game_library_models_25::telemetry_data::VehicleFiaFlags;
-use tokio::net::UdpSocket;
-
-const PENALTY_SHOW_TIME: Duration = Duration::from_secs(2);
+use clap::Subcommand;
+use clap::ValueEnum;
+use f1_light_sync::Config;
+use f1_light_sync::Flag;
+use f1_light_sync::FlagEvent;
+use f1_light_sync::FlagMachine;
+use f1_light_sync::FlagManager;
+use f1_light_sync::FlagState;
+use f1_light_sync::GameYear;
+use f1_light_sync::IndicatorKind;
+use f1_light_sync::MAX_MARSHAL_POSTS;
+use f1_light_sync::MotionSource;
+use f1_light_sync::OutputConfig;
+use f1_light_sync::PacketParser;
+use f1_light_sync::PacketStatistics;
+use f1_light_sync::RateLimitConfig;
+use f1_light_sync::SessionKind;
+use f1_light_sync::capture::CaptureReader;
+use f1_light_sync::capture::CaptureWriter;
+#[cfg(unix)]
+use f1_light_sync::daemon;
+use f1_light_sync::daemon::PidFile;
+use f1_light_sync::discovery;
+use f1_light_sync::discovery::Device;
+use f1_light_sync::dump::PacketDump;
+use f1_light_sync::history::HistoryLog;
+use f1_light_sync::http::ControlCommand;
+use f1_light_sync::http::HttpServer;
+#[cfg(windows)]
+use f1_light_sync::input::AccInput;
+use f1_light_sync::input::Ams2Input;
+use f1_light_sync::input::F1Input;
+use f1_light_sync::input::InputSource;
+#[cfg(windows)]
+use f1_light_sync::input::IracingInput;
+use f1_light_sync::input::MergedInput;
+#[cfg(windows)]
+use f1_light_sync::input::RFactor2Input;
+use f1_light_sync::input::ReplayCommand;
+use f1_light_sync::input::ReplayInput;
+use f1_light_sync::input::SecondPlayerInput;
+use f1_light_sync::input::ams2::AMS2_PORT;
+use f1_light_sync::input::f1::F1_PORT;
+use f1_light_sync::logging;
+use f1_light_sync::logging::LogFormat;
+use f1_light_sync::output;
+use f1_light_sync::output::DryRunOutput;
+use f1_light_sync::output::MarshalPostOutput;
+use f1_light_sync::output::Output;
+use f1_light_sync::output::ThrottledOutput;
+#[cfg(windows)]
+use f1_light_sync::service;
+#[cfg(windows)]
+use f1_light_sync::service::SERVICE_NAME;
+use f1_light_sync::shutdown::Shutdown;
+#[cfg(target_os = "linux")]
+use f1_light_sync::systemd;
+use f1_light_sync::tui::StatusView;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tracing::Instrument;
+use tracing::Level;
+use tracing::info;
+use tracing::info_span;
+use tracing::warn;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// Destination IP (with port) for the light packets
-    destination: String,
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+
+    /// Least important log messages shown: error, warn, info, debug or trace
+    #[arg(long, global = true, default_value_t = Level::INFO)]
+    log_level: Level,
+
+    /// Log as text or as JSON lines
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
+
+    /// Append the log to this file instead of printing it
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Write the process id to this file, removed again on exit
+    #[arg(long, global = true, value_name = "PATH")]
+    pid_file: Option<PathBuf>,
+
+    /// Run in the background, detached from the terminal. Log with --log-file
+    #[cfg(unix)]
+    #[arg(long, global = true)]
+    daemon: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Look for WLED and ESPHome devices on the network and print the config for one of them
+    Discover(DiscoverArgs),
+    /// Play a capture made with --record back to the outputs
+    Replay(ReplayArgs),
+    /// Cycle through the flags on the outputs, no game needed
+    Simulate(SimulateArgs),
+    /// Install, remove or run the Windows service
+    #[cfg(windows)]
+    #[command(subcommand)]
+    Service(ServiceCommand),
+}
+
+#[cfg(windows)]
+#[derive(Subcommand, Debug)]
+enum ServiceCommand {
+    /// Install as a service that starts with the PC and start it, running with the options given
+    /// after `install`
+    Install(RunArgs),
+    /// Stop and remove the service
+    Uninstall,
+    /// Run as the service, which Windows does
+    #[command(hide = true)]
+    Run {
+        /// Directory the service was installed from, relative paths are taken from there
+        #[arg(long)]
+        directory: PathBuf,
+
+        #[command(flatten)]
+        run: RunArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    outputs: OutputArgs,
+
+    /// Games to take the flags from, the other options about packets only apply to F1
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "f1")]
+    input: Vec<Input>,
 
     /// Port to listen on for packets
-    #[arg(short, long, default_value_t = 20888)]
+    #[arg(short, long, default_value_t = F1_PORT.into())]
     source_port: u32,
 
-    /// Port to forward the UDP packet to
-    #[arg(short, long)]
-    forward: Option<String>,
-}
+    /// Address to listen on for packets instead of 127.0.0.1, `0.0.0.0:20888` for a game on a
+    /// console or another PC. Several listen to more than one game at once, the worst flag of them
+    /// is shown
+    #[arg(
+        long,
+        value_name = "ADDR:PORT",
+        value_delimiter = ',',
+        conflicts_with = "source_port"
+    )]
+    listen: Vec<SocketAddr>,
 
-#[tokio::main]
-async fn main() -> io::Result<()> {
-    let args = Args::parse();
+    /// Multicast group to join for packets, listens on all addresses unless `--listen` is given
+    #[arg(long, value_name = "GROUP")]
+    multicast: Option<Ipv4Addr>,
 
-    let source_port = args.source_port;
-    let destination = &args.destination;
+    /// Addresses to forward every UDP packet to unchanged, for other telemetry tools
+    #[arg(short, long, value_delimiter = ',')]
+    forward: Vec<String>,
 
-    let input_socket = UdpSocket::bind(format!("127.0.0.1:{}", source_port)).await?;
-    let output_socket = UdpSocket::bind("0.0.0.0:0").await?;
-    output_socket.connect(destination).await?;
+    /// Write every received packet to this capture file
+    #[arg(short, long)]
+    record: Option<PathBuf>,
 
-    let mut buf = [0; 2048];
+    /// Hex dump the first packet of every packet id that fails to parse to this file, for bug
+    /// reports
+    #[arg(long, value_name = "FILE")]
+    dump_failures: Option<PathBuf>,
 
-    let mut manager = FlagManager::new(output_socket);
-    println!(
-        "Listening to 127.0.0.1:{} and outputting on {}{}",
-        source_port,
-        destination,
-        args.forward
-            .as_ref()
-            .map(|s| format!(" and forwarding to {s}"))
-            .unwrap_or_default()
-    );
+    #[command(flatten)]
+    parser: ParserArgs,
+}
 
-    loop {
-        let (len, _) = input_socket.recv_from(&mut buf).await?;
+/// How the F1 packets are read.
+#[derive(Args, Debug)]
+struct ParserArgs {
+    /// Game the telemetry comes from, 2023, 2024 or 2025. Detected from the packets when left out
+    #[arg(long, value_name = "YEAR")]
+    game_year: Option<GameYear>,
 
-        // Forward the message if forwarding has an address
-        if let Some(addr) = &args.forward {
-            input_socket.send_to(&buf[..len], addr).await?;
-        }
+    /// Only show yellow for yellow marshal zones the player is in or at most this many meters
+    /// ahead, instead of the yellow the game gives for a large part of the track
+    #[arg(long, value_name = "METERS")]
+    yellow_lookahead: Option<f32>,
 
-        // Try parsing the message
-        let Ok(packet) = f1_game_library_models_25::deserialise_udp_packet_from_bytes(&buf[..len])
-        else {
-            println!("Failed to parse packet");
-            continue;
-        };
+    /// Only show blue while the car lapping the player is at most this many meters behind
+    #[arg(long, value_name = "METERS")]
+    blue_gap: Option<f32>,
+}
 
-        match packet {
-            F1Data::ParticipantData(data) => {
-                manager.driver_numbers = data.participants.map(|v| v.race_number)
-            }
-            F1Data::EventData(data) => match data.r#type {
-                EventType::SafetyCar(safetycar) => {
-                    match (safetycar.safety_car_type, safetycar.event_type) {
-                        (0, _) | (_, 2) | (_, 3) => manager.reset_global_flag().await,
-                        (1, x) | (3, x) if x == 0 || x == 1 => {
-                            manager.set_global_flag(GlobalFlag::Sc).await
-                        }
-                        (2, 0) | (2, 1) => manager.set_global_flag(GlobalFlag::Vsc).await,
-                        _ => unreachable!("all numbers should be in the range ([0,3], [0,3])"),
-                    }
-                }
-                EventType::Penalty(penalty) => manager.set_penalty(penalty.vehicle_index).await,
-                EventType::ChequeredFlag(_) => manager.finish().await,
-                EventType::RedFlag(_) => manager.set_global_flag(GlobalFlag::Red).await,
-                EventType::SessionStart(_) | EventType::SessionEnd(_) => manager.reset().await,
-                _ => (),
-            },
-            F1Data::ClassificationData(_) => manager.reset().await,
-            F1Data::CarStatusData(data) => {
-                let driver_index = data.header.player_car_index;
-                match data
-                    .car_status_data
-                    .get(driver_index)
-                    .expect("driver index should be within maximum cars in session")
-                    .vehicle_fia_flags
-                {
-                    VehicleFiaFlags::InvalidUnknown => println!("Unknown local flag received"),
-                    VehicleFiaFlags::None => manager.reset_local_flag().await,
-                    VehicleFiaFlags::Green => manager.set_local_flag(LocalFlag::Green).await,
-                    VehicleFiaFlags::Blue => manager.set_local_flag(LocalFlag::Blue).await,
-                    VehicleFiaFlags::Yellow => manager.set_local_flag(LocalFlag::Yellow).await,
-                    VehicleFiaFlags::Red => manager.set_global_flag(GlobalFlag::Red).await,
-                }
+impl ParserArgs {
+    fn parser(&self, config: &Config) -> PacketParser {
+        let mut parser = self
+            .game_year
+            .map_or_else(PacketParser::detect, PacketParser::new);
+        if let Some(lookahead) = self.yellow_lookahead {
+            parser = parser.with_marshal_zones(lookahead);
+        }
+        if let Some(gap) = self.blue_gap {
+            parser = parser.with_blue_gap(gap);
+        }
+        if let Some(rev_lights) = &config.rev_lights {
+            parser = parser.with_rev_light_gears(rev_lights.gears.clone());
+        }
+        if let Some(rpm) = &config.rpm {
+            parser = parser.with_rpm(rpm.redline);
+        }
+        if let Some(low_fuel) = &config.low_fuel {
+            parser = parser.with_low_fuel_laps(low_fuel.laps);
+        }
+        if let Some(tyre_warning) = &config.tyre_warning {
+            parser = parser.with_tyre_warning(
+                tyre_warning.wear,
+                tyre_warning.temperature,
+                tyre_warning.per_corner,
+            );
+        }
+        if let Some(temperature_warning) = &config.temperature_warning {
+            parser = parser.with_temperature_warning(
+                temperature_warning.engine,
+                temperature_warning.brakes,
+                temperature_warning.hysteresis,
+            );
+        }
+        if let Some(rain) = &config.rain {
+            parser = parser.with_rain_forecast(rain.minutes, rain.percentage);
+        }
+        if let Some(lap_delta) = &config.lap_delta {
+            parser = parser.with_lap_delta(lap_delta.rate);
+        }
+        if let Some(gap) = &config.gap {
+            parser = parser.with_gap(gap.to, gap.full, gap.rate);
+        }
+        if !config.progress.is_empty() {
+            parser = parser.with_progress();
+        }
+        if let Some(damage) = &config.damage {
+            parser = parser.with_damage_alerts(damage.threshold);
+        }
+        if let Some(collision) = &config.collision {
+            parser = parser.with_crash_g_force(collision.g_force);
+        }
+        if let Some(countdown) = &config.countdown {
+            parser = parser.with_countdown(countdown.steps);
+        }
+        if !config.position_board.is_empty() {
+            parser = parser.with_position_board();
+        }
+        if let Some(timing_tower) = &config.timing_tower {
+            parser = parser.with_timing_tower(timing_tower.battle, timing_tower.close);
+        }
+        if !config.marshal_post.is_empty() {
+            let posts = config
+                .marshal_post
+                .iter()
+                .map(|post| (post.from, post.to))
+                .collect();
+            parser = parser.with_marshal_posts(posts);
+        }
+        if let Some(ambient) = &config.ambient {
+            parser = parser.with_ambient_temperature(ambient.temperature);
+            if let Some(source) = ambient.motion {
+                let full = match source {
+                    MotionSource::Speed => ambient.top_speed,
+                    MotionSource::GForce => ambient.max_g_force,
+                };
+                parser = parser.with_motion(source, full, ambient.steps);
             }
-            _ => (),
         }
+        parser
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum GlobalFlag {
-    Vsc,
-    Sc,
-    Red,
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    /// F1 23, 24 or 25 telemetry over UDP
+    F1,
+    /// Automobilista 2 or Project CARS 2 telemetry over UDP
+    #[value(alias = "pcars2")]
+    Ams2,
+    /// Assetto Corsa Competizione shared memory
+    #[cfg(windows)]
+    Acc,
+    /// iRacing SDK memory mapped file
+    #[cfg(windows)]
+    Iracing,
+    /// rFactor 2 or Le Mans Ultimate with the rF2 Shared Memory Map Plugin
+    #[cfg(windows)]
+    #[value(name = "rf2", alias = "lmu")]
+    RFactor2,
+}
+
+#[derive(Args, Debug)]
+struct ReplayArgs {
+    /// Capture file to play
+    file: PathBuf,
+
+    /// Playback speed, 2 or 2x plays the capture twice as fast
+    #[arg(long, default_value_t = 1.0, value_parser = speed)]
+    speed: f64,
+
+    /// Session time to start playing at, in seconds or like `12:30` or `1:02:03`
+    #[arg(long, value_name = "TIME")]
+    start_at: Option<SessionTime>,
+
+    #[command(flatten)]
+    parser: ParserArgs,
+
+    #[command(flatten)]
+    outputs: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+struct SimulateArgs {
+    /// Flags to show in order, each optionally with its own number of seconds like `sc:10`.
+    /// Possible flags are the ones of the config, like green, yellow, sc, red, penalty, finish and off
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "green,yellow,sc,vsc,red,finish"
+    )]
+    sequence: Vec<SimulationStep>,
+
+    /// Seconds every flag is shown for when the sequence does not say otherwise
+    #[arg(long, default_value_t = 3.0)]
+    step: f64,
+
+    /// Stop after going through the sequence once instead of repeating it
+    #[arg(long)]
+    once: bool,
+
+    #[command(flatten)]
+    outputs: OutputArgs,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum LocalFlag {
-    Green,
-    Yellow,
-    Blue,
+/// A playback speed like `4` or `0.5x`.
+fn speed(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    s.strip_suffix(['x', 'X'])
+        .unwrap_or(s)
+        .parse()
+        .map_err(|_| format!("invalid speed `{s}`"))
 }
 
+/// Seconds since the start of a session.
 #[derive(Debug, Clone, Copy)]
-enum Flag {
-    Global(GlobalFlag),
-    Local(LocalFlag),
-    Penalty(usize),
-    Finish,
-}
-
-impl Flag {
-    fn to_enum_str(self) -> String {
-        match self {
-            Flag::Global(global_flag) => String::from(match global_flag {
-                GlobalFlag::Vsc => "5",
-                GlobalFlag::Sc => "4",
-                GlobalFlag::Red => "12",
-            }),
-            Flag::Local(local_flag) => String::from(match local_flag {
-                LocalFlag::Green => "1",
-                LocalFlag::Yellow => "2",
-                LocalFlag::Blue => "8",
-            }),
-            Flag::Penalty(index) => format!("11,{index}"),
-            Flag::Finish => String::from("16"),
+struct SessionTime(f32);
+
+impl FromStr for SessionTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid session time `{s}`, give seconds or like `12:30`");
+        let mut seconds = 0.0;
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        if parts.len() > 3 {
+            return Err(invalid());
         }
+        for part in parts {
+            let part: f32 = part.parse().map_err(|_| invalid())?;
+            if !part.is_finite() || part < 0.0 {
+                return Err(invalid());
+            }
+            seconds = seconds * 60.0 + part;
+        }
+        Ok(Self(seconds))
     }
 }
 
-impl From<LocalFlag> for Flag {
-    fn from(value: LocalFlag) -> Self {
-        Self::Local(value)
-    }
+/// A flag of the simulated sequence.
+#[derive(Debug, Clone)]
+struct SimulationStep {
+    flag: Option<Flag>,
+    duration: Option<Duration>,
 }
 
-impl From<GlobalFlag> for Flag {
-    fn from(value: GlobalFlag) -> Self {
-        Self::Global(value)
+impl FromStr for SimulationStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, seconds) = match s.split_once(':') {
+            Some((name, seconds)) => (name, Some(seconds)),
+            None => (s, None),
+        };
+        let flag = Flag::from_name(name.trim())?;
+        let duration = seconds
+            .map(|seconds| {
+                seconds
+                    .trim()
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or_else(|| format!("invalid number of seconds `{seconds}`"))
+            })
+            .transpose()?;
+        Ok(Self { flag, duration })
     }
 }
 
-#[derive(Debug)]
-struct FlagManager {
-    global_flag: Option<GlobalFlag>,
-    local_flag: Option<LocalFlag>,
-    race_finished: bool,
-    showing_penalty_since: Option<Instant>,
-    driver_numbers: [u8; f1_game_library_models_25::constants::MAX_CARS_IN_SESSION],
-    output_socket: UdpSocket,
+#[derive(Args, Debug)]
+struct OutputArgs {
+    /// Destination IPs (with port) for the light packets, added to the outputs in the config
+    destination: Vec<String>,
+
+    /// Path to a TOML config file
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Look for devices on the network at startup and add the picked one to the outputs
+    #[arg(long)]
+    discover: bool,
+
+    /// Print what would be sent to the outputs instead of connecting to them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Show the flags, drivers and last events live in the terminal instead of the log, which only
+    /// goes to --log-file then
+    #[arg(long, conflicts_with = "dry_run")]
+    tui: bool,
+
+    /// Append every flag shown and the game events behind them to this JSON lines file
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+
+    #[command(flatten)]
+    discovery: DiscoverArgs,
+}
+
+#[derive(Args, Debug)]
+struct DiscoverArgs {
+    /// Seconds to wait for devices to answer
+    #[arg(long, default_value_t = 3)]
+    discover_time: u64,
+
+    /// UDP port ESPHome devices listen on for the flags
+    #[arg(long, default_value_t = discovery::ESPHOME_UDP_PORT)]
+    esphome_port: u16,
 }
 
-fn show_based_on_local(
-    flag: Option<LocalFlag>,
-    penalty: bool,
-    finished: bool,
-) -> Option<Option<Flag>> {
-    match flag {
-        None if !penalty && !finished => Some(None),
-        Some(LocalFlag::Green) if !finished => Some(flag.map(Flag::from)),
-        Some(LocalFlag::Yellow) | Some(LocalFlag::Blue) => Some(flag.map(Flag::from)),
-        _ if finished => Some(Some(Flag::Finish)),
-        _ => None,
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    // Before the runtime starts its threads
+    #[cfg(unix)]
+    if cli.daemon {
+        daemon::detach()?;
+    }
+    #[cfg(windows)]
+    if let Some(Command::Service(ServiceCommand::Run { directory, .. })) = &cli.command {
+        std::env::set_current_dir(directory)?;
     }
+    let tui = match &cli.command {
+        None => cli.run.outputs.tui,
+        Some(Command::Replay(args)) => args.outputs.tui,
+        _ => false,
+    };
+    // The log would run through the status view
+    if !tui || cli.log_file.is_some() {
+        logging::init(cli.log_level, cli.log_format, cli.log_file.as_deref())?;
+    }
+    let _pid_file = match &cli.pid_file {
+        Some(path) => Some(PidFile::create(path)?),
+        None => None,
+    };
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    #[cfg(windows)]
+    let cli = match cli.command {
+        Some(Command::Service(ServiceCommand::Run { run: args, .. })) => {
+            return service::run(move || runtime.block_on(run(args)));
+        }
+        command => Cli { command, ..cli },
+    };
+    runtime.block_on(start(cli))
 }
 
-impl FlagManager {
-    fn new(output_socket: UdpSocket) -> Self {
-        Self {
-            global_flag: Default::default(),
-            local_flag: Default::default(),
-            race_finished: Default::default(),
-            showing_penalty_since: Default::default(),
-            driver_numbers: Default::default(),
-            output_socket,
+async fn start(cli: Cli) -> io::Result<()> {
+    match cli.command {
+        Some(Command::Discover(args)) => discover(args).await,
+        Some(Command::Replay(args)) => replay(args).await,
+        Some(Command::Simulate(args)) => simulate(args).await,
+        #[cfg(windows)]
+        Some(Command::Service(ServiceCommand::Install(_))) => {
+            service::install(&service_arguments()?)?;
+            info!("Installed and started the {SERVICE_NAME} service");
+            Ok(())
         }
+        #[cfg(windows)]
+        Some(Command::Service(ServiceCommand::Uninstall)) => {
+            service::uninstall()?;
+            info!("Removed the {SERVICE_NAME} service");
+            Ok(())
+        }
+        #[cfg(windows)]
+        Some(Command::Service(ServiceCommand::Run { .. })) => {
+            unreachable!("the service runs before the runtime does")
+        }
+        None => run(cli.run).await,
     }
+}
 
-    async fn reset(&mut self) {
-        self.global_flag = None;
-        self.local_flag = None;
-        self.race_finished = false;
-        self.showing_penalty_since = None;
-        self.driver_numbers = Default::default();
-        self.show(None).await;
+/// The command line for `service run`, with the options that were given to `service install`.
+#[cfg(windows)]
+fn service_arguments() -> io::Result<Vec<String>> {
+    let mut arguments: Vec<String> = std::env::args().skip(1).collect();
+    for word in ["service", "install"] {
+        if let Some(i) = arguments.iter().position(|argument| argument == word) {
+            arguments.remove(i);
+        }
     }
+    let directory = std::env::current_dir()?.to_string_lossy().into_owned();
+    Ok(["service", "run", "--directory", &directory]
+        .into_iter()
+        .map(String::from)
+        .chain(arguments)
+        .collect())
+}
 
-    async fn show(&mut self, flag: Option<Flag>) {
-        let string_input = flag.map(Flag::to_enum_str).unwrap_or("c".to_string());
-        if self
-            .output_socket
-            .send(string_input.as_bytes())
-            .await
-            .is_err()
-        {
-            println!("Failed to send show command");
-        };
+async fn discover(args: DiscoverArgs) -> io::Result<()> {
+    let Some(device) = pick_device(&args).await? else {
+        return Ok(());
+    };
+    match device.output_config(args.esphome_port) {
+        OutputConfig::Udp { destination, .. } => {
+            println!("Run with `f1_light_sync {destination}` or add to the config:\n");
+            println!("[[output]]\ntype = \"udp\"\ndestination = \"{destination}\"");
+        }
+        OutputConfig::Wled(config) => {
+            println!("Add to the config:\n");
+            println!("[[output]]\ntype = \"wled\"\nhost = \"{}\"", config.host);
+        }
+        _ => unreachable!("discovery only finds UDP and WLED devices"),
+    }
+    Ok(())
+}
+
+/// Lists the devices on the network and asks which one to use.
+async fn pick_device(args: &DiscoverArgs) -> io::Result<Option<Device>> {
+    println!("Looking for devices...");
+    let devices = discovery::browse(Duration::from_secs(args.discover_time)).await?;
+    if devices.is_empty() {
+        println!("No WLED or ESPHome devices found");
+        return Ok(None);
     }
 
-    async fn finish(&mut self) {
-        self.race_finished = true;
-        if self.global_flag.is_none() {
-            self.show(Some(Flag::Finish)).await;
+    for (i, device) in devices.iter().enumerate() {
+        println!("{}: {device}", i + 1);
+    }
+    loop {
+        print!("Pick a device (1-{}): ", devices.len());
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim().parse::<usize>() {
+            Ok(choice @ 1..) if choice <= devices.len() => {
+                return Ok(Some(devices[choice - 1].clone()));
+            }
+            _ => println!("Not a device number"),
         }
     }
+}
 
-    async fn set_penalty(&mut self, index: usize) {
-        self.showing_penalty_since = Some(Instant::now());
-        if self.global_flag.is_none() {
-            let driver_number = self.driver_numbers.get(index).cloned().unwrap_or_default();
-            self.show(Some(Flag::Penalty(if driver_number == 0 {
-                index
-            } else {
-                driver_number.into()
-            })))
-            .await;
+fn load_config(args: &OutputArgs) -> io::Result<Config> {
+    match &args.config {
+        Some(path) => Config::load(path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Connects to the flag and indicator outputs and hands them to a new manager.
+///
+/// Returns the manager together with a description of each flag output and one of all outputs.
+async fn connect_manager(
+    args: &OutputArgs,
+    config: Config,
+    session_uid: Option<&watch::Receiver<Option<u64>>>,
+) -> io::Result<(FlagManager, Vec<String>, String)> {
+    let indicators = [
+        (IndicatorKind::Drs, "DRS", config.drs.clone()),
+        (
+            IndicatorKind::RevLights,
+            "rev lights",
+            config.rev_lights.clone().unwrap_or_default().output,
+        ),
+        (
+            IndicatorKind::Rpm,
+            "RPM",
+            config
+                .rpm
+                .as_ref()
+                .map(|rpm| rpm.output.clone())
+                .unwrap_or_default(),
+        ),
+        (IndicatorKind::Ers, "ERS", config.ers.clone()),
+        (IndicatorKind::Tyre, "tyre", config.tyre.clone()),
+        (IndicatorKind::Gear, "gear", config.gear.clone()),
+        (
+            IndicatorKind::TyreWarning,
+            "tyre warning",
+            config
+                .tyre_warning
+                .as_ref()
+                .map(|tyre_warning| tyre_warning.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::PositionBoard,
+            "position board",
+            config.position_board.clone(),
+        ),
+        (
+            IndicatorKind::TimingTower,
+            "timing tower",
+            config
+                .timing_tower
+                .as_ref()
+                .map(|timing_tower| timing_tower.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::TemperatureWarning,
+            "temperature warning",
+            config
+                .temperature_warning
+                .as_ref()
+                .map(|temperature_warning| temperature_warning.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::LowFuel,
+            "low fuel",
+            config
+                .low_fuel
+                .as_ref()
+                .map(|low_fuel| low_fuel.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::Rain,
+            "rain",
+            config
+                .rain
+                .as_ref()
+                .map(|rain| rain.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::LapDelta,
+            "lap delta",
+            config
+                .lap_delta
+                .as_ref()
+                .map(|lap_delta| lap_delta.output.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            IndicatorKind::Gap,
+            "gap",
+            config
+                .gap
+                .as_ref()
+                .map(|gap| gap.output.clone())
+                .unwrap_or_default(),
+        ),
+        (IndicatorKind::Progress, "progress", config.progress.clone()),
+    ];
+    let settings = config.clone();
+    let (outputs, descriptions) = connect_outputs(args, config, session_uid).await?;
+    let mut description = descriptions.join(", ");
+    let mut manager = configure(FlagManager::new(outputs), &settings)?;
+
+    for (kind, name, output_configs) in indicators {
+        if output_configs.is_empty() {
+            continue;
         }
+        let mut outputs = Vec::with_capacity(output_configs.len());
+        for output_config in &output_configs {
+            outputs.push(
+                connect(
+                    output_config,
+                    &settings.rate_limit,
+                    args.dry_run,
+                    session_uid,
+                )
+                .await?,
+            );
+        }
+        manager.add_indicator_outputs(kind, outputs);
+        description.push_str(&format!(" and {name} on {}", describe(&output_configs)));
+    }
+    if settings.marshal_post.len() > MAX_MARSHAL_POSTS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("there can be at most {MAX_MARSHAL_POSTS} marshal posts"),
+        ));
+    }
+    let mut posts = Vec::new();
+    for (post, post_config) in settings.marshal_post.iter().enumerate() {
+        for output_config in &post_config.output {
+            let output_config = output_config.clone().with_default_codes(&settings.codes);
+            let output = connect(
+                &output_config,
+                &settings.rate_limit,
+                args.dry_run,
+                session_uid,
+            )
+            .await?;
+            posts.push(Box::new(MarshalPostOutput::new(output, post)) as Box<dyn Output>);
+        }
+        description.push_str(&format!(
+            " and marshal post {} on {}",
+            post + 1,
+            describe(&post_config.output)
+        ));
+    }
+    if !posts.is_empty() {
+        manager.add_indicator_outputs(IndicatorKind::MarshalPosts, posts);
+    }
+    for zone in &settings.zone {
+        let mut outputs = Vec::with_capacity(zone.output.len());
+        for output_config in &zone.output {
+            let output_config = output_config.clone().with_default_codes(&settings.codes);
+            outputs.push(
+                connect(
+                    &output_config,
+                    &settings.rate_limit,
+                    args.dry_run,
+                    session_uid,
+                )
+                .await?,
+            );
+        }
+        manager.add_zone(zone.flags.clone(), outputs);
+        if !description.is_empty() {
+            description.push_str(" and ");
+        }
+        let flags: Vec<_> = zone.flags.iter().map(|flag| format!("{flag:?}")).collect();
+        description.push_str(&format!(
+            "{} on {}",
+            flags.join(", "),
+            describe(&zone.output)
+        ));
     }
+    Ok((manager, descriptions, description))
+}
 
-    fn check_penalty(&mut self) {
-        if let Some(time) = self.showing_penalty_since
-            && time.elapsed() > PENALTY_SHOW_TIME
-        {
-            self.showing_penalty_since = None;
+/// Sets up how the manager shows the flags, the same for both players in split-screen.
+fn configure(mut manager: FlagManager, config: &Config) -> io::Result<FlagManager> {
+    let mut machine = FlagMachine::default();
+    if let Some(priority) = config.priority.clone() {
+        machine = machine.with_priority(priority);
+    }
+    if let Some(penalty) = &config.penalty {
+        machine = machine.with_ignored_penalties(penalty.ignore.clone());
+    }
+    if let Some(black_and_white) = &config.black_and_white {
+        machine = machine.with_black_and_white(seconds(black_and_white.seconds)?);
+    }
+    if let Some(green_flash) = &config.green_flash {
+        machine = machine.with_green_flash(seconds(green_flash.seconds)?);
+    }
+    if let Some(keepalive) = &config.keepalive {
+        let interval = seconds(keepalive.seconds)?;
+        if interval.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the keepalive interval has to be above 0",
+            ));
         }
+        manager = manager.with_keepalive(interval);
     }
+    if let Some(position) = &config.position {
+        machine = machine.with_position_changes(seconds(position.seconds)?);
+    }
+    if let Some(pit) = &config.pit {
+        machine = machine.with_pit_status(pit.lane);
+    }
+    if let Some(fastest_lap) = &config.fastest_lap {
+        machine = machine.with_fastest_lap(seconds(fastest_lap.seconds)?, fastest_lap.only_player);
+    }
+    if let Some(damage) = &config.damage {
+        machine = machine.with_damage(seconds(damage.seconds)?);
+    }
+    if let Some(collision) = &config.collision {
+        let interval = seconds(collision.interval)?;
+        if interval.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the collision interval has to be above 0",
+            ));
+        }
+        machine = machine.with_collision(seconds(collision.seconds)?, interval);
+    }
+    if let Some(animation) = config.finish_animation.clone() {
+        let [first, second] = animation
+            .flags
+            .map(|name| Flag::from_name(&name).map_err(io::Error::other));
+        let interval = seconds(animation.interval)?;
+        if interval.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the finish animation interval has to be above 0",
+            ));
+        }
+        machine =
+            machine.with_finish_animation([first?, second?], seconds(animation.seconds)?, interval);
+    }
+    // The parser waits a second of session time over the rates between updates
+    if let Some(lap_delta) = &config.lap_delta
+        && (lap_delta.rate <= 0.0 || lap_delta.rate.is_nan())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the lap delta rate has to be above 0",
+        ));
+    }
+    if let Some(gap) = &config.gap {
+        if gap.rate <= 0.0 || gap.rate.is_nan() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the gap rate has to be above 0",
+            ));
+        }
+        if gap.full <= 0.0 || gap.full.is_nan() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the full gap has to be above 0",
+            ));
+        }
+    }
+    Ok(manager.with_machine(machine))
+}
+
+/// Connects to the outputs in the config and on the command line.
+///
+/// Returns the outputs together with a description of each of them.
+async fn connect_outputs(
+    args: &OutputArgs,
+    config: Config,
+    session_uid: Option<&watch::Receiver<Option<u64>>>,
+) -> io::Result<(Vec<Box<dyn Output>>, Vec<String>)> {
+    let rate_limit = config.rate_limit;
+    let mut output_configs = config.output;
+    output_configs.extend(
+        args.destination
+            .iter()
+            .map(|destination| OutputConfig::Udp {
+                destination: destination.clone(),
+                codes: Default::default(),
+                format: Default::default(),
+                template: None,
+                patterns: Default::default(),
+            }),
+    );
+    if args.discover
+        && let Some(device) = pick_device(&args.discovery).await?
+    {
+        output_configs.push(device.output_config(args.discovery.esphome_port));
+    }
+    // Zones alone will do for the flags
+    if output_configs.is_empty() && config.zone.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no destination given and no output in the config",
+        ));
+    }
+
+    let output_configs: Vec<OutputConfig> = output_configs
+        .into_iter()
+        .map(|output_config| output_config.with_default_codes(&config.codes))
+        .collect();
+    let mut outputs = Vec::with_capacity(output_configs.len());
+    for output_config in &output_configs {
+        outputs.push(connect(output_config, &rate_limit, args.dry_run, session_uid).await?);
+    }
+    Ok((
+        outputs,
+        output_configs.iter().map(ToString::to_string).collect(),
+    ))
+}
+
+/// Connects to an output, behind the rate limit of the config when there is one. On a `dry_run`
+/// it is only printed what the output would get. Outputs sending JSON get the `session_uid`.
+async fn connect(
+    output_config: &OutputConfig,
+    rate_limit: &Option<RateLimitConfig>,
+    dry_run: bool,
+    session_uid: Option<&watch::Receiver<Option<u64>>>,
+) -> io::Result<Box<dyn Output>> {
+    let output = match dry_run {
+        true => Box::new(DryRunOutput::new(output_config.to_string())),
+        false => output::connect(output_config, session_uid.cloned()).await?,
+    };
+    Ok(match rate_limit {
+        Some(rate_limit) => Box::new(ThrottledOutput::new(output, rate_limit.per_second)?),
+        None => output,
+    })
+}
 
-    async fn set_global_flag_value(&mut self, flag: Option<GlobalFlag>) {
-        self.check_penalty();
-        if self.global_flag == flag {
-            return;
+/// A duration from the config.
+fn seconds(seconds: f64) -> io::Result<Duration> {
+    Duration::try_from_secs_f64(seconds).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn describe(output_configs: &[OutputConfig]) -> String {
+    output_configs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+async fn replay(args: ReplayArgs) -> io::Result<()> {
+    let config = load_config(&args.outputs)?;
+    let parser = args.parser.parser(&config);
+    let mut source = ReplayInput::new(CaptureReader::open(&args.file)?, parser, args.speed)?;
+    if let Some(SessionTime(start_at)) = args.start_at {
+        source = source.with_start_at(start_at);
+    }
+    let (commands, receiver) = mpsc::unbounded_channel();
+    source = source.with_commands(receiver);
+    if !args.outputs.tui {
+        read_replay_commands(commands.clone());
+    }
+    let handles = SourceHandles {
+        session_uid: Some(source.session_uid()),
+        replay: Some(commands),
+        ..SourceHandles::default()
+    };
+    let name = format!("{} at {}x", args.file.display(), args.speed);
+    run_source(
+        &name,
+        vec![Box::new(source)],
+        None,
+        handles,
+        &args.outputs,
+        config,
+    )
+    .await?;
+    info!("Replay finished");
+    Ok(())
+}
+
+/// Reads the commands of a replay from the lines on stdin, an empty line pauses or resumes and `n`
+/// steps to the next event.
+fn read_replay_commands(commands: mpsc::UnboundedSender<ReplayCommand>) {
+    if !io::stdin().is_terminal() {
+        return;
+    }
+    info!("Press Enter to pause or resume the replay, n and Enter to step to the next event");
+    // Reading stdin blocks, the thread goes with the program
+    std::thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let command = match line.as_deref().map(str::trim) {
+                Ok("") => ReplayCommand::TogglePause,
+                Ok("n") => ReplayCommand::Step,
+                Ok(_) => continue,
+                Err(_) => return,
+            };
+            if commands.send(command).is_err() {
+                return;
+            }
         }
+    });
+}
 
-        self.global_flag = flag;
+async fn simulate(args: SimulateArgs) -> io::Result<()> {
+    let step = Duration::try_from_secs_f64(args.step)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut config = load_config(&args.outputs)?;
+    // Nothing would send what the rate limit held back, and the flags only change every step
+    config.rate_limit = None;
+    let (mut outputs, descriptions) = connect_outputs(&args.outputs, config, None).await?;
+    info!("Simulating flags on {}", descriptions.join(", "));
+    let mut shutdown = Shutdown::install()?;
 
-        if flag.is_some() {
-            return;
+    loop {
+        for SimulationStep { flag, duration } in &args.sequence {
+            info!(flag = flag.map_or("off", Flag::name), "Showing flag");
+            let state = FlagState {
+                global_flag: match flag {
+                    Some(Flag::Global(global_flag)) => Some(*global_flag),
+                    _ => None,
+                },
+                local_flag: match flag {
+                    Some(Flag::Local(local_flag)) => Some(*local_flag),
+                    _ => None,
+                },
+                race_finished: *flag == Some(Flag::Finish),
+                shown: *flag,
+            };
+            for (i, output) in outputs.iter_mut().enumerate() {
+                if let Err(e) = output.show(*flag).await {
+                    warn!(output = i, error = %e, "Failed to send show command");
+                }
+                if let Err(e) = output.update_state(&state).await {
+                    warn!(output = i, error = %e, "Failed to send state update");
+                }
+            }
+            tokio::select! {
+                () = tokio::time::sleep(duration.unwrap_or(step)) => {}
+                () = shutdown.requested() => {
+                    info!("Stopping, turning the lights off");
+                    for (i, output) in outputs.iter_mut().enumerate() {
+                        let result = match output.show(None).await {
+                            Ok(()) => output.close().await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            warn!(output = i, error = %e, "Failed to turn the output off");
+                        }
+                    }
+                    return Ok(());
+                }
+            }
         }
 
-        if let Some(local_flag) = show_based_on_local(
-            self.local_flag,
-            self.showing_penalty_since.is_some(),
-            self.race_finished,
-        ) {
-            self.show(local_flag).await;
+        if args.once {
+            return Ok(());
         }
     }
+}
 
-    async fn set_local_flag_value(&mut self, flag: Option<LocalFlag>) {
-        self.check_penalty();
-        if self.local_flag == flag {
-            return;
+async fn run(args: RunArgs) -> io::Result<()> {
+    let config = load_config(&args.outputs)?;
+    let mut names = Vec::with_capacity(args.input.len());
+    let mut sources: Vec<Box<dyn InputSource>> = Vec::with_capacity(args.input.len());
+    let mut second_player = None;
+    let mut handles = SourceHandles::default();
+    for input in &args.input {
+        let (name, source): (_, Box<dyn InputSource>) = match input {
+            Input::F1 => {
+                // The first listener gets the dashboard and the second player
+                let mut inputs = f1_inputs(&args, &config).await?.into_iter();
+                let mut input = inputs.next().expect("there is always a listener");
+                handles.packets = Some(input.statistics());
+                handles.session_uid = Some(input.session_uid());
+                if config.second_player.is_some() {
+                    let parser = args.parser.parser(&config).with_second_player();
+                    let second;
+                    (input, second) = input.split_screen(parser);
+                    second_player = Some(second);
+                }
+                for other in inputs {
+                    names.push("F1");
+                    sources.push(Box::new(other));
+                }
+                ("F1", Box::new(input))
+            }
+            Input::Ams2 => (
+                "AMS2",
+                Box::new(Ams2Input::bind(("0.0.0.0", AMS2_PORT)).await?),
+            ),
+            #[cfg(windows)]
+            Input::Acc => ("ACC", Box::new(AccInput::new())),
+            #[cfg(windows)]
+            Input::Iracing => ("iRacing", Box::new(IracingInput::new())),
+            #[cfg(windows)]
+            Input::RFactor2 => ("rFactor 2", Box::new(RFactor2Input::new())),
+        };
+        names.push(name);
+        sources.push(source);
+    }
+
+    run_source(
+        &names.join(", "),
+        sources,
+        second_player,
+        handles,
+        &args.outputs,
+        config,
+    )
+    .await
+}
+
+/// The F1 UDP listeners with the multicast group, forwarding and recording asked for, one for
+/// every `--listen` address or socket systemd opened for the unit.
+async fn f1_inputs(args: &RunArgs, config: &Config) -> io::Result<Vec<F1Input>> {
+    let mut listeners = Vec::new();
+    #[cfg(target_os = "linux")]
+    for socket in systemd::take_sockets() {
+        let address = socket.local_addr()?.to_string();
+        listeners.push((
+            address,
+            F1Input::from_std(socket, args.parser.parser(config))?,
+        ));
+    }
+    if listeners.is_empty() {
+        let host = match args.multicast {
+            Some(_) => "0.0.0.0",
+            None => "127.0.0.1",
+        };
+        let addresses = match args.listen.is_empty() {
+            true => vec![format!("{host}:{}", args.source_port)],
+            false => args.listen.iter().map(ToString::to_string).collect(),
+        };
+        for address in addresses {
+            let input = F1Input::bind(&address, args.parser.parser(config)).await?;
+            listeners.push((address, input));
         }
+    }
+    if args.record.is_some() && listeners.len() > 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "recording only works with one --listen address",
+        ));
+    }
+    if args.dump_failures.is_some() && listeners.len() > 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "dumping only works with one --listen address",
+        ));
+    }
 
-        self.local_flag = flag;
+    let mut inputs = Vec::with_capacity(listeners.len());
+    for (address, mut input) in listeners {
+        info!(
+            "Listening to {address}{}",
+            match args.forward.is_empty() {
+                true => String::new(),
+                false => format!(" and forwarding to {}", args.forward.join(", ")),
+            }
+        );
+        if let Some(group) = args.multicast {
+            input = input.with_multicast(group)?;
+            info!("Joined multicast group {group}");
+        }
+        for destination in &args.forward {
+            input = input.with_forward(destination.clone());
+        }
+        if let Some(path) = &args.record {
+            input = input.with_capture(CaptureWriter::create(path)?);
+            info!("Recording packets to {}", path.display());
+        }
+        if let Some(path) = &args.dump_failures {
+            input = input.with_dump(PacketDump::create(path)?);
+        }
+        inputs.push(input);
+    }
+    Ok(inputs)
+}
+
+/// What the views follow of the sources besides their events.
+#[derive(Debug, Default)]
+struct SourceHandles {
+    /// Statistics of the F1 telemetry, for the dashboards
+    packets: Option<watch::Receiver<PacketStatistics>>,
+    /// Unique id of the F1 session, for the history
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+    /// Controls of a replay, for the keys of the status view
+    replay: Option<mpsc::UnboundedSender<ReplayCommand>>,
+}
 
-        if self.global_flag.is_some() {
-            return;
+/// Applies the events of all `sources` to the outputs until they end.
+///
+/// The events of the `second_player` go to the outputs of the second player in the config, the
+/// views get the `handles` of the sources. Asked to stop, all lights are turned off first.
+async fn run_source(
+    name: &str,
+    sources: Vec<Box<dyn InputSource>>,
+    second_player: Option<SecondPlayerInput>,
+    handles: SourceHandles,
+    args: &OutputArgs,
+    config: Config,
+) -> io::Result<()> {
+    let shutdown = Shutdown::install()?;
+    let second_player = match (second_player, &config.second_player) {
+        (Some(source), Some(second_player)) if !second_player.output.is_empty() => {
+            let mut outputs = Vec::with_capacity(second_player.output.len());
+            for output_config in &second_player.output {
+                let output_config = output_config.clone().with_default_codes(&config.codes);
+                outputs.push(
+                    connect(
+                        &output_config,
+                        &config.rate_limit,
+                        args.dry_run,
+                        handles.session_uid.as_ref(),
+                    )
+                    .await?,
+                );
+            }
+            info!(
+                "Showing the flags of the second player on {}",
+                describe(&second_player.output)
+            );
+            let manager = configure(FlagManager::new(outputs), &config)?;
+            Some((manager, source))
+        }
+        _ => None,
+    };
+    let profiles = match config.profiles.is_empty() {
+        true => None,
+        // The device picked would be asked for again on every switch
+        false if args.discover => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--discover does not work with profiles, add the device to the config instead",
+            ));
+        }
+        false => Some(Profiles {
+            args,
+            session_uid: handles.session_uid.clone(),
+            config: config.clone(),
+            active: None,
+        }),
+    };
+    let http = config.http.clone();
+    let (manager, descriptions, description) =
+        connect_manager(args, config, handles.session_uid.as_ref()).await?;
+    info!("Reading flags from {name} and outputting on {description}");
+    let tui = match args.tui {
+        true => {
+            let mut tui = StatusView::new(descriptions.clone())?;
+            if let Some(packets) = &handles.packets {
+                tui = tui.with_packet_statistics(packets.clone());
+            }
+            if let Some(replay) = handles.replay {
+                tui = tui.with_replay_commands(replay);
+            }
+            Some(tui)
+        }
+        false => None,
+    };
+    let http = match http {
+        Some(http) => {
+            let mut server = HttpServer::bind(
+                &http.listen,
+                descriptions,
+                http.control,
+                http.allow_origin.as_deref(),
+            )
+            .await?;
+            if let Some(packets) = handles.packets {
+                server = server.with_packet_statistics(packets);
+            }
+            server.update(&manager);
+            info!("Serving the dashboard on http://{}", http.listen);
+            Some(server)
         }
+        None => None,
+    };
+    let history = match &args.history {
+        Some(path) => {
+            let mut history = HistoryLog::open(path)?;
+            if let Some(session_uid) = handles.session_uid {
+                history = history.with_session_uid(session_uid);
+            }
+            info!("Writing the flag history to {}", path.display());
+            Some(history)
+        }
+        None => None,
+    };
+    #[cfg(target_os = "linux")]
+    systemd::ready();
+    let first_player = drive(
+        manager,
+        MergedInput::new(sources),
+        profiles,
+        http,
+        tui,
+        history,
+        shutdown.clone(),
+    );
+    match second_player {
+        Some((manager, source)) => {
+            let source = MergedInput::new(vec![Box::new(source)]);
+            let second_player = drive(manager, source, None, None, None, None, shutdown)
+                .instrument(info_span!("second_player"));
+            tokio::try_join!(first_player, second_player)?;
+            Ok(())
+        }
+        None => first_player.await,
+    }
+}
+
+/// The manager for the profile of the session type, the base config for types without one.
+struct Profiles<'a> {
+    args: &'a OutputArgs,
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+    config: Config,
+    /// Profile the manager is connected for, `None` for the base config
+    active: Option<SessionKind>,
+}
+
+impl Profiles<'_> {
+    /// The profile for `kind` when the manager has to switch to it, `Some(None)` for the base
+    /// config.
+    fn switch_to(&self, kind: SessionKind) -> Option<Option<SessionKind>> {
+        let wanted = self.config.profiles.contains_key(&kind).then_some(kind);
+        (wanted != self.active).then_some(wanted)
+    }
+
+    /// Replaces `manager` with one for the outputs and settings of `profile`, which carries on
+    /// with the session of the old one. Returns a description of each flag output.
+    async fn switch(
+        &mut self,
+        manager: &mut FlagManager,
+        profile: Option<SessionKind>,
+    ) -> io::Result<Vec<String>> {
+        let config = match profile {
+            Some(kind) => self.config.profiles[&kind].clone(),
+            None => self.config.clone(),
+        };
+        // Shutting down clears the state of the session
+        let state = manager.machine().clone();
+        // Outputs like serial ports can only be open once, so the old ones go first
+        manager.shut_down().await;
+        let (next, descriptions, description) =
+            connect_manager(self.args, config, self.session_uid.as_ref()).await?;
+        info!(
+            profile = profile.map_or("default", SessionKind::name),
+            "Switched profile, outputting on {description}"
+        );
+        self.active = profile;
+
+        let machine = next.machine().clone().with_state_of(&state);
+        *manager = next.with_machine(machine);
+        manager.tick().await;
+        Ok(descriptions)
+    }
+}
 
-        if let Some(local_flag) = show_based_on_local(
-            flag,
-            self.showing_penalty_since.is_some(),
-            self.race_finished,
-        ) {
-            self.show(local_flag).await;
+/// Hands the events of the source to the manager until it ends.
+///
+/// Everything the manager logs goes under a `session` span, numbered up every time a session
+/// starts or ends. With `profiles` the manager is swapped for one with the outputs and settings of
+/// the session type. The dashboard of `http` follows the manager, which carries out the commands
+/// of its API, and so does the `tui` with its keys. The events and the flags shown go to the
+/// `history`. Once asked to stop, the lights are turned off.
+async fn drive(
+    mut manager: FlagManager,
+    mut source: MergedInput,
+    mut profiles: Option<Profiles<'_>>,
+    mut http: Option<HttpServer>,
+    mut tui: Option<StatusView>,
+    mut history: Option<HistoryLog>,
+    mut shutdown: Shutdown,
+) -> io::Result<()> {
+    let mut session = 0;
+    let mut span = info_span!("session", number = session);
+    // The sources run in tasks of their own, so waiting for the next event can be cut short when
+    // the manager has to update the lights by itself
+    loop {
+        let deadline = manager.deadline();
+        let event = tokio::select! {
+            event = source.next_event() => event?,
+            () = sleep_until(deadline) => {
+                manager.tick().instrument(span.clone()).await;
+                if let Some(http) = &mut http {
+                    http.update(&manager);
+                }
+                log_shown(&mut history, &manager);
+                continue;
+            }
+            () = sleep_until(tui.as_ref().map(StatusView::next_draw)) => {
+                if let Some(view) = &mut tui
+                    && let Err(e) = view.draw(&manager)
+                {
+                    tui = None;
+                    warn!(error = %e, "Failed to draw the status view");
+                }
+                continue;
+            }
+            command = next_key(&mut tui) => {
+                info!(?command, "Command from the keyboard");
+                command.apply(&mut manager).instrument(span.clone()).await;
+                if let Some(http) = &mut http {
+                    http.update(&manager);
+                }
+                log_shown(&mut history, &manager);
+                continue;
+            }
+            command = next_command(&mut http) => {
+                info!(?command, "Command from the API");
+                command.apply(&mut manager).instrument(span.clone()).await;
+                if let Some(http) = &mut http {
+                    http.update(&manager);
+                }
+                log_shown(&mut history, &manager);
+                continue;
+            }
+            () = shutdown.requested() => {
+                info!("Stopping, turning the lights off");
+                #[cfg(target_os = "linux")]
+                if let Err(e) = systemd::notify("STOPPING=1") {
+                    warn!(error = %e, "Failed to notify systemd");
+                }
+                manager.shut_down().await;
+                log_shown(&mut history, &manager);
+                return Ok(());
+            }
+        };
+        let Some(event) = event else {
+            return Ok(());
+        };
+        if event == FlagEvent::Reset {
+            session += 1;
+            span = info_span!("session", number = session);
         }
+        if let Some(http) = &mut http {
+            http.event(&event);
+        }
+        if let Some(tui) = &mut tui {
+            tui.event(&event);
+        }
+        log_event(&mut history, &event);
+        if let FlagEvent::Session(kind) = event
+            && let Some(profiles) = &mut profiles
+            && let Some(profile) = profiles.switch_to(kind)
+        {
+            let descriptions = profiles
+                .switch(&mut manager, profile)
+                .instrument(span.clone())
+                .await?;
+            if let Some(http) = &mut http {
+                http.set_output_names(descriptions.clone());
+            }
+            if let Some(tui) = &mut tui {
+                tui.set_output_names(descriptions);
+            }
+        }
+        manager.handle(event).instrument(span.clone()).await;
+        if let Some(http) = &mut http {
+            http.update(&manager);
+        }
+        log_shown(&mut history, &manager);
     }
+}
 
-    async fn set_global_flag(&mut self, flag: GlobalFlag) {
-        self.set_global_flag_value(Some(flag)).await;
+/// Writes the event to the `history`, which is given up on once writing fails.
+fn log_event(history: &mut Option<HistoryLog>, event: &FlagEvent) {
+    if let Some(log) = history
+        && let Err(e) = log.event(event)
+    {
+        *history = None;
+        warn!(error = %e, "Failed to write the history, no longer writing it");
     }
+}
 
-    async fn reset_global_flag(&mut self) {
-        self.set_global_flag_value(None).await;
+/// Writes what `manager` shows to the `history` when it changed, giving up once writing fails.
+fn log_shown(history: &mut Option<HistoryLog>, manager: &FlagManager) {
+    if let Some(log) = history
+        && let Err(e) = log.update(manager)
+    {
+        *history = None;
+        warn!(error = %e, "Failed to write the history, no longer writing it");
     }
+}
 
-    async fn set_local_flag(&mut self, flag: LocalFlag) {
-        self.set_local_flag_value(Some(flag)).await;
+/// Waits for `deadline`, forever when there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
     }
+}
+
+/// The command of the next key pressed in the status view, never comes without one.
+async fn next_key(tui: &mut Option<StatusView>) -> ControlCommand {
+    match tui {
+        Some(tui) => tui.command().await,
+        None => std::future::pending().await,
+    }
+}
 
-    async fn reset_local_flag(&mut self) {
-        self.set_local_flag_value(None).await;
+/// The next command of the API, never comes without a server.
+async fn next_command(http: &mut Option<HttpServer>) -> ControlCommand {
+    match http {
+        Some(http) => http.command().await,
+        None => std::future::pending().await,
     }
 }
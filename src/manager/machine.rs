@@ -0,0 +1,1189 @@
+//! Which flag the lights show, worked out from the events in one place without any outputs.
+use std::time::Duration;
+use std::time::Instant;
+
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+
+use super::BLACK_AND_WHITE_SHOW_TIME;
+use super::DEFAULT_PRIORITY;
+use super::GREEN_FLASH_TIME;
+use super::PENALTY_SHOW_TIME;
+use super::Priority;
+use crate::event::FlagEvent;
+use crate::flag::Abbreviation;
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::flag::PenaltyKind;
+use crate::flag::PitStatus;
+
+/// What the lights show, `None` keeps them dark.
+pub type Display = Option<Flag>;
+
+/// Everything that changes what the [`FlagMachine`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// An event of the game, indicators and the session type change nothing
+    Event(FlagEvent),
+    /// Shows the flag over everything but the pit limiter for `ttl`, or until cleared when `None`
+    Force {
+        flag: Option<Flag>,
+        ttl: Option<Duration>,
+    },
+    ClearForced,
+    /// Turns the reminder to pit this lap on or off
+    PitReminder(bool),
+    /// Time passed, for what is only shown for a while
+    Tick,
+}
+
+/// The flag state of a session and the rules of what it shows.
+///
+/// Every [`Trigger`] goes through [`transition`](FlagMachine::transition), which applies it and
+/// picks the flag by the [`DEFAULT_PRIORITY`] unless set up otherwise. The time is passed in, so
+/// the same triggers at the same times always show the same.
+#[derive(Debug, Clone)]
+pub struct FlagMachine {
+    global_flag: Option<GlobalFlag>,
+    local_flag: Option<LocalFlag>,
+    race_finished: bool,
+    /// Temperature shown while no flag is out, when reported
+    ambient: Option<i8>,
+    /// Percentage of the speed or G force, shown over the temperature while no flag is out
+    motion: Option<u8>,
+    /// Percentage of the formation lap left, until the player is back on the grid
+    countdown: Option<u8>,
+    /// Number of start lights lit, until they go out
+    start_lights: Option<u8>,
+    /// How long green is shown after the start or a yellow, SC or VSC
+    green_flash: Duration,
+    showing_green_until: Option<Instant>,
+    /// The player was disqualified or retired
+    black_flag: bool,
+    /// The last penalty and when it was given
+    penalty: Option<(Flag, Instant)>,
+    /// Drive-through or stop-go the player still has to serve
+    unserved_penalty: Option<Flag>,
+    /// Kinds of penalties that are not shown
+    ignored_penalties: Vec<PenaltyKind>,
+    /// Whether to show the pit stop and also the pit lane, not shown when `None`
+    pit_lane: Option<bool>,
+    pit_status: PitStatus,
+    /// Told to pit this lap, until the player enters the pit lane
+    pit_reminder: bool,
+    /// How long a position change is shown, not shown when `None`
+    position_change: Option<Duration>,
+    /// Whether places were gained and until when that is shown
+    showing_position_until: Option<(bool, Instant)>,
+    /// How long a fastest lap is shown and whether only for the player, not shown when `None`
+    fastest_lap: Option<(Duration, bool)>,
+    showing_fastest_lap_until: Option<Instant>,
+    /// How long sudden damage is shown, not shown when `None`
+    damage: Option<Duration>,
+    showing_damage_until: Option<Instant>,
+    /// How long a collision strobes and how long each color stays, not shown when `None`
+    collision: Option<(Duration, Duration)>,
+    /// The strobe of a collision, red in frame 1
+    strobing: Option<Animating>,
+    /// How long a warning is shown
+    black_and_white: Duration,
+    showing_black_and_white_until: Option<Instant>,
+    finish_animation: Option<FinishAnimation>,
+    animating: Option<Animating>,
+    priority: Vec<Priority>,
+    /// Flag shown over the priority until when given, set from outside when the game gets it wrong
+    forced: Option<(Option<Flag>, Option<Instant>)>,
+    driver_numbers: [u8; MAX_CARS_IN_SESSION],
+    driver_abbreviations: [Option<Abbreviation>; MAX_CARS_IN_SESSION],
+    /// Shown over all flags while it is on
+    pit_limiter: bool,
+    /// What the last transition showed
+    display: Display,
+}
+
+/// Flags shown in turn when the race finishes, before settling on the finish.
+#[derive(Debug, Clone, Copy)]
+struct FinishAnimation {
+    frames: [Option<Flag>; 2],
+    duration: Duration,
+    interval: Duration,
+}
+
+/// Where a running finish animation or collision strobe is.
+#[derive(Debug, Clone, Copy)]
+struct Animating {
+    frame: usize,
+    next_frame: Instant,
+    until: Instant,
+}
+
+impl Default for FlagMachine {
+    fn default() -> Self {
+        Self {
+            global_flag: None,
+            local_flag: None,
+            race_finished: false,
+            ambient: None,
+            motion: None,
+            countdown: None,
+            start_lights: None,
+            green_flash: GREEN_FLASH_TIME,
+            showing_green_until: None,
+            black_flag: false,
+            penalty: None,
+            unserved_penalty: None,
+            ignored_penalties: Vec::new(),
+            pit_lane: None,
+            pit_status: PitStatus::None,
+            pit_reminder: false,
+            position_change: None,
+            showing_position_until: None,
+            fastest_lap: None,
+            showing_fastest_lap_until: None,
+            damage: None,
+            showing_damage_until: None,
+            collision: None,
+            strobing: None,
+            black_and_white: BLACK_AND_WHITE_SHOW_TIME,
+            showing_black_and_white_until: None,
+            finish_animation: None,
+            animating: None,
+            priority: DEFAULT_PRIORITY.to_vec(),
+            forced: None,
+            driver_numbers: [0; MAX_CARS_IN_SESSION],
+            driver_abbreviations: [None; MAX_CARS_IN_SESSION],
+            pit_limiter: false,
+            display: None,
+        }
+    }
+}
+
+impl FlagMachine {
+    /// Shows while the player is stopped in the box, and in the pit lane as well when `lane`.
+    pub fn with_pit_status(mut self, lane: bool) -> Self {
+        self.pit_lane = Some(lane);
+        self
+    }
+
+    /// Shows for `duration` that the player gained or lost places.
+    pub fn with_position_changes(mut self, duration: Duration) -> Self {
+        self.position_change = Some(duration);
+        self
+    }
+
+    /// Shows fastest laps for `duration`, only the ones of the player when `only_player`.
+    pub fn with_fastest_lap(mut self, duration: Duration, only_player: bool) -> Self {
+        self.fastest_lap = Some((duration, only_player));
+        self
+    }
+
+    /// Shows sudden damage to the player's car for `duration`.
+    pub fn with_damage(mut self, duration: Duration) -> Self {
+        self.damage = Some(duration);
+        self
+    }
+
+    /// Strobes white and red every `interval` for `duration` when the player crashes or hits a car.
+    pub fn with_collision(mut self, duration: Duration, interval: Duration) -> Self {
+        self.collision = Some((duration, interval));
+        self
+    }
+
+    /// Shows green for `duration` when the start lights go out or a yellow, SC or VSC ends, not at
+    /// all when zero.
+    pub fn with_green_flash(mut self, duration: Duration) -> Self {
+        self.green_flash = duration;
+        self
+    }
+
+    /// Shows the black and white flag for `duration` after a warning.
+    pub fn with_black_and_white(mut self, duration: Duration) -> Self {
+        self.black_and_white = duration;
+        self
+    }
+
+    /// Alternates the two `frames` every `interval` for `duration` when the race finishes, then
+    /// shows the finish.
+    pub fn with_finish_animation(
+        mut self,
+        frames: [Option<Flag>; 2],
+        duration: Duration,
+        interval: Duration,
+    ) -> Self {
+        self.finish_animation = Some(FinishAnimation {
+            frames,
+            duration,
+            interval,
+        });
+        self
+    }
+
+    /// Does not show penalties of the `kinds` given, like warnings.
+    pub fn with_ignored_penalties(mut self, kinds: Vec<PenaltyKind>) -> Self {
+        self.ignored_penalties = kinds;
+        self
+    }
+
+    /// Shows the flags in the order of `priority`, anything left out goes after it in the default
+    /// order.
+    pub fn with_priority(mut self, priority: Vec<Priority>) -> Self {
+        self.priority = priority;
+        for default in DEFAULT_PRIORITY {
+            if !self.priority.contains(&default) {
+                self.priority.push(default);
+            }
+        }
+        self
+    }
+
+    /// Takes the state of the session from `machine`, like the flags out, penalties, the race
+    /// finish and a forced flag, keeping how this one is set up and what it showed last. For
+    /// settings that change during a session.
+    pub fn with_state_of(self, machine: &FlagMachine) -> Self {
+        Self {
+            display: self.display,
+            green_flash: self.green_flash,
+            ignored_penalties: self.ignored_penalties,
+            pit_lane: self.pit_lane,
+            position_change: self.position_change,
+            fastest_lap: self.fastest_lap,
+            damage: self.damage,
+            collision: self.collision,
+            black_and_white: self.black_and_white,
+            finish_animation: self.finish_animation,
+            priority: self.priority,
+            ..machine.clone()
+        }
+    }
+
+    /// Applies `trigger` at `now`, what to show when that changed.
+    pub fn transition(&mut self, trigger: Trigger, now: Instant) -> Option<Display> {
+        match trigger {
+            Trigger::Event(event) => self.event(event, now),
+            Trigger::Force { flag, ttl } => self.forced = Some((flag, ttl.map(|ttl| now + ttl))),
+            Trigger::ClearForced => self.forced = None,
+            Trigger::PitReminder(on) => self.pit_reminder = on,
+            Trigger::Tick => {}
+        }
+        self.expire(now);
+
+        let display = match self.pit_limiter {
+            true => Some(Flag::PitLimiter),
+            false => self.pick(),
+        };
+        (display != self.display).then(|| {
+            self.display = display;
+            display
+        })
+    }
+
+    /// What the last transition showed.
+    pub fn display(&self) -> Display {
+        self.display
+    }
+
+    /// The first flag in the order of `priority` that is active, leaving out everything else. For
+    /// lights that only show some of the flags.
+    pub fn display_for(&self, priority: &[Priority]) -> Display {
+        priority
+            .iter()
+            .find_map(|&priority| self.flag_for(priority))
+            .flatten()
+    }
+
+    /// The currently active global flag.
+    pub fn global_flag(&self) -> Option<GlobalFlag> {
+        self.global_flag
+    }
+
+    /// The currently active local flag.
+    pub fn local_flag(&self) -> Option<LocalFlag> {
+        self.local_flag
+    }
+
+    /// Whether the chequered flag has been shown this session.
+    pub fn race_finished(&self) -> bool {
+        self.race_finished
+    }
+
+    /// The flag shown over the priority and until when, when one is forced.
+    pub fn forced(&self) -> Option<(Option<Flag>, Option<Instant>)> {
+        self.forced
+    }
+
+    /// The penalty that competes for the lights at `now` and until when, `None` for a
+    /// drive-through or stop-go that lasts until it is served.
+    pub fn penalty(&self, now: Instant) -> Option<(Flag, Option<Instant>)> {
+        self.penalty
+            .filter(|&(_, time)| now.saturating_duration_since(time) <= PENALTY_SHOW_TIME)
+            .map(|(flag, time)| (flag, Some(time + PENALTY_SHOW_TIME)))
+            .or(self.unserved_penalty.map(|flag| (flag, None)))
+    }
+
+    /// Race numbers of all cars, indexed by vehicle index. Zero when unknown.
+    pub fn driver_numbers(&self) -> &[u8; MAX_CARS_IN_SESSION] {
+        &self.driver_numbers
+    }
+
+    /// Name abbreviations of all cars, indexed by vehicle index. `None` when unknown.
+    pub fn driver_abbreviations(&self) -> &[Option<Abbreviation>; MAX_CARS_IN_SESSION] {
+        &self.driver_abbreviations
+    }
+
+    /// When a [`Trigger::Tick`] changes what is shown without a new event, like a fastest lap
+    /// that is over.
+    pub fn deadline(&self) -> Option<Instant> {
+        let animation = self
+            .animating
+            .map(|animating| animating.next_frame.min(animating.until));
+        let strobe = self
+            .strobing
+            .map(|strobing| strobing.next_frame.min(strobing.until));
+        [
+            self.showing_position_until.map(|(_, until)| until),
+            self.showing_green_until,
+            self.showing_fastest_lap_until,
+            self.showing_damage_until,
+            self.showing_black_and_white_until,
+            self.forced.and_then(|(_, until)| until),
+            animation,
+            strobe,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    fn event(&mut self, event: FlagEvent, now: Instant) {
+        match event {
+            FlagEvent::Participants {
+                numbers,
+                abbreviations,
+            } => {
+                self.driver_numbers = numbers;
+                self.driver_abbreviations = abbreviations;
+            }
+            FlagEvent::SetGlobalFlag(flag) => self.set_global_flag(Some(flag), now),
+            FlagEvent::ResetGlobalFlag => self.set_global_flag(None, now),
+            FlagEvent::SetLocalFlag(flag) => self.set_local_flag(Some(flag), now),
+            FlagEvent::ResetLocalFlag => self.set_local_flag(None, now),
+            FlagEvent::Penalty {
+                vehicle_index,
+                kind,
+            } => {
+                if !self.ignored_penalties.contains(&kind) {
+                    self.penalty = Some((self.penalty_flag(vehicle_index, kind), now));
+                }
+            }
+            FlagEvent::UnservedPenalty {
+                vehicle_index,
+                kind,
+            } => {
+                self.unserved_penalty = kind
+                    .filter(|kind| !self.ignored_penalties.contains(kind))
+                    .map(|kind| self.penalty_flag(vehicle_index, kind));
+            }
+            FlagEvent::PositionChange { gained } => {
+                if let Some(duration) = self.position_change {
+                    self.showing_position_until = Some((gained, now + duration));
+                }
+            }
+            FlagEvent::FastestLap { player, .. } => {
+                if let Some((duration, only_player)) = self.fastest_lap
+                    && (player || !only_player)
+                {
+                    self.showing_fastest_lap_until = Some(now + duration);
+                }
+            }
+            FlagEvent::Damage => {
+                if let Some(duration) = self.damage {
+                    self.showing_damage_until = Some(now + duration);
+                }
+            }
+            FlagEvent::Collision => {
+                if let Some((duration, interval)) = self.collision {
+                    self.strobing = Some(Animating {
+                        frame: 0,
+                        next_frame: now + interval,
+                        until: now + duration,
+                    });
+                }
+            }
+            FlagEvent::Finish => {
+                self.race_finished = true;
+                if let Some(animation) = self.finish_animation {
+                    self.animating = Some(Animating {
+                        frame: 0,
+                        next_frame: now + animation.interval,
+                        until: now + animation.duration,
+                    });
+                }
+            }
+            FlagEvent::Ambient(temperature) => self.ambient = Some(temperature),
+            FlagEvent::Motion(percent) => self.motion = Some(percent),
+            FlagEvent::Countdown(left) => self.countdown = (left > 0).then_some(left),
+            FlagEvent::StartLights(lights) => {
+                self.countdown = None;
+                self.start_lights = Some(lights);
+            }
+            // Also ends the global flag of the formation lap
+            FlagEvent::LightsOut => {
+                self.countdown = None;
+                self.start_lights = None;
+                self.global_flag = None;
+                self.flash_green(now);
+            }
+            FlagEvent::BlackFlag => self.black_flag = true,
+            FlagEvent::BlackAndWhite => {
+                self.showing_black_and_white_until = Some(now + self.black_and_white);
+            }
+            FlagEvent::PitLimiter(on) => self.pit_limiter = on,
+            FlagEvent::PitStatus(status) => {
+                self.pit_status = status;
+                // Coming in ends the reminder to pit
+                if status != PitStatus::None {
+                    self.pit_reminder = false;
+                }
+            }
+            FlagEvent::Reset => self.reset(),
+            FlagEvent::Session(_) | FlagEvent::Indicator(_) => {}
+        }
+    }
+
+    /// Clears the state of the session, the forced flag stays.
+    fn reset(&mut self) {
+        *self = Self {
+            forced: self.forced,
+            display: self.display,
+            green_flash: self.green_flash,
+            ignored_penalties: std::mem::take(&mut self.ignored_penalties),
+            pit_lane: self.pit_lane,
+            position_change: self.position_change,
+            fastest_lap: self.fastest_lap,
+            damage: self.damage,
+            collision: self.collision,
+            black_and_white: self.black_and_white,
+            finish_animation: self.finish_animation,
+            priority: std::mem::take(&mut self.priority),
+            ..Self::default()
+        };
+    }
+
+    /// Forgets what was only shown for a while once `now` is past it.
+    fn expire(&mut self, now: Instant) {
+        if self
+            .showing_fastest_lap_until
+            .is_some_and(|until| until <= now)
+        {
+            self.showing_fastest_lap_until = None;
+        }
+        if self.showing_damage_until.is_some_and(|until| until <= now) {
+            self.showing_damage_until = None;
+        }
+        if self
+            .showing_position_until
+            .is_some_and(|(_, until)| until <= now)
+        {
+            self.showing_position_until = None;
+        }
+        if self.showing_green_until.is_some_and(|until| until <= now) {
+            self.showing_green_until = None;
+        }
+        if self
+            .showing_black_and_white_until
+            .is_some_and(|until| until <= now)
+        {
+            self.showing_black_and_white_until = None;
+        }
+        if self
+            .forced
+            .is_some_and(|(_, until)| until.is_some_and(|until| until <= now))
+        {
+            self.forced = None;
+        }
+        if let Some((_, time)) = self.penalty
+            && now.saturating_duration_since(time) > PENALTY_SHOW_TIME
+        {
+            self.penalty = None;
+        }
+        if let (Some(animating), Some(animation)) = (self.animating, self.finish_animation) {
+            if animating.until <= now {
+                self.animating = None;
+            } else if animating.next_frame <= now {
+                self.animating = Some(Animating {
+                    frame: 1 - animating.frame,
+                    next_frame: animating.next_frame + animation.interval,
+                    until: animating.until,
+                });
+            }
+        }
+        if let (Some(strobing), Some((_, interval))) = (self.strobing, self.collision) {
+            if strobing.until <= now {
+                self.strobing = None;
+            } else if strobing.next_frame <= now {
+                self.strobing = Some(Animating {
+                    frame: 1 - strobing.frame,
+                    next_frame: strobing.next_frame + interval,
+                    until: strobing.until,
+                });
+            }
+        }
+    }
+
+    /// The forced flag, otherwise the flag with the highest priority or the motion or temperature.
+    fn pick(&self) -> Display {
+        match self.forced {
+            Some((flag, _)) => flag,
+            None => self
+                .priority
+                .iter()
+                .find_map(|&priority| self.flag_for(priority))
+                .unwrap_or(
+                    self.motion
+                        .map(Flag::Motion)
+                        .or(self.ambient.map(Flag::Ambient)),
+                ),
+        }
+    }
+
+    /// The flag to show for `priority`, `None` when there is nothing to show for it.
+    fn flag_for(&self, priority: Priority) -> Option<Display> {
+        let flag = match priority {
+            Priority::StartLights => self
+                .start_lights
+                .map(Flag::StartLights)
+                .or(self.countdown.map(Flag::Countdown)),
+            Priority::Global => self.global_flag.map(Flag::from),
+            Priority::Black => self.black_flag.then_some(Flag::Black),
+            Priority::Yellow => self
+                .local_flag
+                .filter(|flag| matches!(flag, LocalFlag::Yellow | LocalFlag::DoubleYellow))
+                .map(Flag::from),
+            Priority::Blue => self
+                .local_flag
+                .filter(|&flag| flag == LocalFlag::Blue)
+                .map(Flag::from),
+            Priority::BlackAndWhite => self
+                .showing_black_and_white_until
+                .map(|_| Flag::BlackAndWhite),
+            Priority::Penalty => self
+                .penalty
+                .map(|(penalty, _)| penalty)
+                .or(self.unserved_penalty),
+            Priority::Position => self.showing_position_until.map(|(gained, _)| match gained {
+                true => Flag::PositionGained,
+                false => Flag::PositionLost,
+            }),
+            Priority::Pit => match (self.pit_status, self.pit_lane) {
+                (PitStatus::PitStop, Some(_)) => Some(Flag::PitStop),
+                (PitStatus::PitLane, Some(true)) => Some(Flag::PitLane),
+                _ => self.pit_reminder.then_some(Flag::PitThisLap),
+            },
+            Priority::FastestLap => self.showing_fastest_lap_until.map(|_| Flag::FastestLap),
+            Priority::Collision => self.strobing.map(|strobing| Flag::Collision {
+                red: strobing.frame == 1,
+            }),
+            Priority::Damage => self.showing_damage_until.map(|_| Flag::Damage),
+            Priority::Finish if !self.race_finished => None,
+            // The off frame of the finish animation shows nothing but still wins
+            Priority::Finish => {
+                return Some(match (self.animating, self.finish_animation) {
+                    (Some(animating), Some(animation)) => animation.frames[animating.frame],
+                    _ => Some(Flag::Finish),
+                });
+            }
+            Priority::Green => self
+                .local_flag
+                .filter(|&flag| flag == LocalFlag::Green)
+                .or(self.showing_green_until.map(|_| LocalFlag::Green))
+                .map(Flag::from),
+        };
+        flag.map(Some)
+    }
+
+    /// The penalty flag for the car with the given vehicle index.
+    fn penalty_flag(&self, index: usize, kind: PenaltyKind) -> Flag {
+        let driver_number = self.driver_numbers.get(index).cloned().unwrap_or_default();
+        let number = match driver_number {
+            0 => index,
+            driver_number => driver_number.into(),
+        };
+        Flag::Penalty {
+            number,
+            kind,
+            driver: self.driver_abbreviations.get(index).copied().flatten(),
+        }
+    }
+
+    /// Shows green for the green flash time, unless turned off.
+    fn flash_green(&mut self, now: Instant) {
+        if !self.green_flash.is_zero() {
+            self.showing_green_until = Some(now + self.green_flash);
+        }
+    }
+
+    /// An SC or VSC that ends flashes green.
+    fn set_global_flag(&mut self, flag: Option<GlobalFlag>, now: Instant) {
+        if flag.is_none()
+            && matches!(
+                self.global_flag,
+                Some(
+                    GlobalFlag::Sc | GlobalFlag::ScEnding | GlobalFlag::Vsc | GlobalFlag::VscEnding
+                )
+            )
+        {
+            self.flash_green(now);
+        }
+        self.global_flag = flag;
+    }
+
+    /// A yellow that ends flashes green.
+    fn set_local_flag(&mut self, flag: Option<LocalFlag>, now: Instant) {
+        if flag.is_none()
+            && matches!(
+                self.local_flag,
+                Some(LocalFlag::Yellow | LocalFlag::DoubleYellow)
+            )
+        {
+            self.flash_green(now);
+        }
+        self.local_flag = flag;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the events a second apart, what is shown after the last one.
+    fn shown_after(machine: &mut FlagMachine, start: Instant, events: &[FlagEvent]) -> Display {
+        for (i, &event) in events.iter().enumerate() {
+            let now = start + Duration::from_secs(i as u64);
+            machine.transition(Trigger::Event(event), now);
+        }
+        machine.display()
+    }
+
+    fn penalty(kind: PenaltyKind) -> FlagEvent {
+        FlagEvent::Penalty {
+            vehicle_index: 3,
+            kind,
+        }
+    }
+
+    #[test]
+    fn sc_goes_over_yellow() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+            FlagEvent::SetGlobalFlag(GlobalFlag::Sc),
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Global(GlobalFlag::Sc)));
+    }
+
+    #[test]
+    fn yellow_comes_back_after_sc() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetGlobalFlag(GlobalFlag::Sc),
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+            FlagEvent::ResetGlobalFlag,
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Yellow)));
+    }
+
+    #[test]
+    fn red_flag_goes_over_penalty() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        machine.transition(Trigger::Event(penalty(PenaltyKind::Time)), start);
+        let shown = machine.transition(
+            Trigger::Event(FlagEvent::SetGlobalFlag(GlobalFlag::Red)),
+            start + Duration::from_millis(100),
+        );
+        assert_eq!(shown, Some(Some(Flag::Global(GlobalFlag::Red))));
+    }
+
+    #[test]
+    fn penalty_loses_priority_after_its_show_time() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        let shown = machine.transition(Trigger::Event(penalty(PenaltyKind::Time)), start);
+        assert_eq!(
+            shown,
+            Some(Some(Flag::Penalty {
+                number: 3,
+                kind: PenaltyKind::Time,
+                driver: None,
+            }))
+        );
+        assert!(machine.penalty(start + PENALTY_SHOW_TIME).is_some());
+
+        let later = start + PENALTY_SHOW_TIME + Duration::from_millis(1);
+        assert_eq!(machine.penalty(later), None);
+        assert_eq!(machine.transition(Trigger::Tick, later), Some(None));
+    }
+
+    #[test]
+    fn penalty_shows_the_race_number_and_driver() {
+        let mut machine = FlagMachine::default();
+        let mut numbers = [0; MAX_CARS_IN_SESSION];
+        numbers[3] = 44;
+        let mut abbreviations = [None; MAX_CARS_IN_SESSION];
+        abbreviations[3] = Abbreviation::of("Lewis HAMILTON");
+        let events = [
+            FlagEvent::Participants {
+                numbers,
+                abbreviations,
+            },
+            penalty(PenaltyKind::DriveThrough),
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(
+            shown,
+            Some(Flag::Penalty {
+                number: 44,
+                kind: PenaltyKind::DriveThrough,
+                driver: abbreviations[3],
+            })
+        );
+    }
+
+    #[test]
+    fn ignored_penalties_are_not_shown() {
+        let mut machine = FlagMachine::default().with_ignored_penalties(vec![PenaltyKind::Warning]);
+        let shown = machine.transition(
+            Trigger::Event(penalty(PenaltyKind::Warning)),
+            Instant::now(),
+        );
+        assert_eq!(shown, None);
+        assert_eq!(machine.display(), None);
+    }
+
+    #[test]
+    fn unserved_penalty_lasts_until_served() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        let unserved = |kind| {
+            Trigger::Event(FlagEvent::UnservedPenalty {
+                vehicle_index: 3,
+                kind,
+            })
+        };
+        machine.transition(unserved(Some(PenaltyKind::StopGo)), start);
+        let later = start + PENALTY_SHOW_TIME * 10;
+        assert_eq!(machine.transition(Trigger::Tick, later), None);
+        assert_eq!(machine.penalty(later).map(|(_, until)| until), Some(None));
+        assert_eq!(machine.transition(unserved(None), later), Some(None));
+    }
+
+    #[test]
+    fn blue_goes_over_finish() {
+        let mut machine = FlagMachine::default();
+        let events = [FlagEvent::Finish, FlagEvent::SetLocalFlag(LocalFlag::Blue)];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Blue)));
+        assert!(machine.race_finished());
+    }
+
+    #[test]
+    fn finish_stays_after_blue() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetLocalFlag(LocalFlag::Blue),
+            FlagEvent::Finish,
+            FlagEvent::ResetLocalFlag,
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Finish));
+    }
+
+    #[test]
+    fn finish_animation_alternates_then_settles() {
+        let interval = Duration::from_millis(500);
+        let mut machine = FlagMachine::default().with_finish_animation(
+            [Some(Flag::Finish), None],
+            Duration::from_secs(2),
+            interval,
+        );
+        let start = Instant::now();
+        machine.transition(Trigger::Event(FlagEvent::Finish), start);
+        assert_eq!(machine.display(), Some(Flag::Finish));
+        assert_eq!(machine.deadline(), Some(start + interval));
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + interval),
+            Some(None)
+        );
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + interval * 2),
+            Some(Some(Flag::Finish))
+        );
+        machine.transition(Trigger::Tick, start + interval * 3);
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + interval * 4),
+            Some(Some(Flag::Finish))
+        );
+        assert_eq!(machine.deadline(), None);
+    }
+
+    #[test]
+    fn yellow_ending_flashes_green() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        machine.transition(
+            Trigger::Event(FlagEvent::SetLocalFlag(LocalFlag::Yellow)),
+            start,
+        );
+        let shown = machine.transition(Trigger::Event(FlagEvent::ResetLocalFlag), start);
+        assert_eq!(shown, Some(Some(Flag::Local(LocalFlag::Green))));
+        assert_eq!(machine.deadline(), Some(start + GREEN_FLASH_TIME));
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + GREEN_FLASH_TIME),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn sc_ending_flashes_green() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetGlobalFlag(GlobalFlag::ScEnding),
+            FlagEvent::ResetGlobalFlag,
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Green)));
+    }
+
+    #[test]
+    fn red_flag_ending_does_not_flash_green() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetGlobalFlag(GlobalFlag::Red),
+            FlagEvent::ResetGlobalFlag,
+        ];
+        assert_eq!(shown_after(&mut machine, Instant::now(), &events), None);
+    }
+
+    #[test]
+    fn no_green_flash_when_turned_off() {
+        let mut machine = FlagMachine::default().with_green_flash(Duration::ZERO);
+        let events = [
+            FlagEvent::SetLocalFlag(LocalFlag::DoubleYellow),
+            FlagEvent::ResetLocalFlag,
+        ];
+        assert_eq!(shown_after(&mut machine, Instant::now(), &events), None);
+    }
+
+    #[test]
+    fn start_lights_go_over_the_formation_lap_until_lights_out() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        let events = [
+            FlagEvent::SetGlobalFlag(GlobalFlag::FormationLap),
+            FlagEvent::Countdown(40),
+        ];
+        let shown = shown_after(&mut machine, start, &events);
+        assert_eq!(shown, Some(Flag::Countdown(40)));
+
+        let shown = shown_after(&mut machine, start, &[FlagEvent::StartLights(3)]);
+        assert_eq!(shown, Some(Flag::StartLights(3)));
+
+        let shown = shown_after(&mut machine, start, &[FlagEvent::LightsOut]);
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Green)));
+        assert_eq!(machine.global_flag(), None);
+    }
+
+    #[test]
+    fn black_flag_goes_over_yellow_until_reset() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::BlackFlag,
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Black));
+
+        let shown = shown_after(&mut machine, Instant::now(), &[FlagEvent::Reset]);
+        assert_eq!(shown, None);
+    }
+
+    #[test]
+    fn black_and_white_is_shown_for_a_while() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        let shown = machine.transition(Trigger::Event(FlagEvent::BlackAndWhite), start);
+        assert_eq!(shown, Some(Some(Flag::BlackAndWhite)));
+        let over = start + BLACK_AND_WHITE_SHOW_TIME;
+        assert_eq!(machine.transition(Trigger::Tick, over), Some(None));
+    }
+
+    #[test]
+    fn pit_limiter_covers_the_flags_until_turned_off() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::SetLocalFlag(LocalFlag::Yellow),
+            FlagEvent::PitLimiter(true),
+            FlagEvent::SetLocalFlag(LocalFlag::Blue),
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::PitLimiter));
+
+        let shown = shown_after(
+            &mut machine,
+            Instant::now(),
+            &[FlagEvent::PitLimiter(false)],
+        );
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Blue)));
+    }
+
+    #[test]
+    fn pit_limiter_goes_over_a_forced_flag() {
+        let mut machine = FlagMachine::default();
+        let now = Instant::now();
+        let force = Trigger::Force {
+            flag: Some(Flag::Global(GlobalFlag::Red)),
+            ttl: None,
+        };
+        machine.transition(force, now);
+        let shown = machine.transition(Trigger::Event(FlagEvent::PitLimiter(true)), now);
+        assert_eq!(shown, Some(Some(Flag::PitLimiter)));
+    }
+
+    #[test]
+    fn forced_flag_goes_over_the_game_until_its_ttl() {
+        let mut machine = FlagMachine::default();
+        let start = Instant::now();
+        let force = Trigger::Force {
+            flag: None,
+            ttl: Some(Duration::from_secs(5)),
+        };
+        machine.transition(
+            Trigger::Event(FlagEvent::SetGlobalFlag(GlobalFlag::Sc)),
+            start,
+        );
+        assert_eq!(machine.transition(force, start), Some(None));
+        assert_eq!(machine.deadline(), Some(start + Duration::from_secs(5)));
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + Duration::from_secs(5)),
+            Some(Some(Flag::Global(GlobalFlag::Sc)))
+        );
+        assert_eq!(machine.forced(), None);
+    }
+
+    #[test]
+    fn reset_clears_the_session_but_keeps_the_forced_flag() {
+        let mut machine = FlagMachine::default().with_priority(vec![Priority::Green]);
+        let now = Instant::now();
+        let force = Trigger::Force {
+            flag: Some(Flag::Finish),
+            ttl: None,
+        };
+        shown_after(
+            &mut machine,
+            now,
+            &[FlagEvent::SetGlobalFlag(GlobalFlag::Vsc), FlagEvent::Finish],
+        );
+        machine.transition(force, now);
+        assert_eq!(
+            machine.transition(Trigger::Event(FlagEvent::Reset), now),
+            None
+        );
+        assert_eq!(machine.global_flag(), None);
+        assert!(!machine.race_finished());
+
+        machine.transition(Trigger::ClearForced, now);
+        // The priority set up stays as well
+        let shown = shown_after(
+            &mut machine,
+            now,
+            &[
+                FlagEvent::SetGlobalFlag(GlobalFlag::Vsc),
+                FlagEvent::SetLocalFlag(LocalFlag::Green),
+            ],
+        );
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Green)));
+    }
+
+    #[test]
+    fn state_of_another_machine_is_taken_with_own_settings() {
+        let mut old = FlagMachine::default();
+        let now = Instant::now();
+        shown_after(
+            &mut old,
+            now,
+            &[
+                FlagEvent::SetGlobalFlag(GlobalFlag::Sc),
+                FlagEvent::UnservedPenalty {
+                    vehicle_index: 3,
+                    kind: Some(PenaltyKind::StopGo),
+                },
+                FlagEvent::Finish,
+            ],
+        );
+        old.transition(Trigger::PitReminder(true), now);
+        old.transition(
+            Trigger::Force {
+                flag: Some(Flag::Black),
+                ttl: None,
+            },
+            now,
+        );
+
+        let mut machine = FlagMachine::default()
+            .with_priority(vec![Priority::Penalty])
+            .with_state_of(&old);
+        assert_eq!(machine.display(), None);
+        assert_eq!(
+            machine.transition(Trigger::Tick, now),
+            Some(Some(Flag::Black))
+        );
+        assert!(machine.race_finished());
+
+        machine.transition(Trigger::ClearForced, now);
+        // The penalty goes over the SC by the priority of the new settings
+        assert!(matches!(machine.display(), Some(Flag::Penalty { .. })));
+        machine.transition(
+            Trigger::Event(FlagEvent::UnservedPenalty {
+                vehicle_index: 3,
+                kind: None,
+            }),
+            now,
+        );
+        assert_eq!(machine.display(), Some(Flag::Global(GlobalFlag::Sc)));
+        machine.transition(Trigger::Event(FlagEvent::ResetGlobalFlag), now);
+        assert_eq!(machine.display(), Some(Flag::PitThisLap));
+        machine.transition(Trigger::PitReminder(false), now);
+        assert_eq!(machine.display(), Some(Flag::Finish));
+    }
+
+    #[test]
+    fn priority_puts_penalties_over_global_flags() {
+        let mut machine = FlagMachine::default().with_priority(vec![Priority::Penalty]);
+        let events = [
+            FlagEvent::SetGlobalFlag(GlobalFlag::Vsc),
+            penalty(PenaltyKind::Time),
+        ];
+        let start = Instant::now();
+        machine.transition(Trigger::Event(events[0]), start);
+        machine.transition(Trigger::Event(events[1]), start);
+        assert!(matches!(machine.display(), Some(Flag::Penalty { .. })));
+    }
+
+    #[test]
+    fn pit_stop_and_reminder() {
+        let mut machine = FlagMachine::default().with_pit_status(false);
+        let now = Instant::now();
+        machine.transition(Trigger::PitReminder(true), now);
+        assert_eq!(machine.display(), Some(Flag::PitThisLap));
+        // The pit lane itself is not shown, but coming in ends the reminder
+        let shown = shown_after(
+            &mut machine,
+            now,
+            &[FlagEvent::PitStatus(PitStatus::PitLane)],
+        );
+        assert_eq!(shown, None);
+        let shown = shown_after(
+            &mut machine,
+            now,
+            &[FlagEvent::PitStatus(PitStatus::PitStop)],
+        );
+        assert_eq!(shown, Some(Flag::PitStop));
+    }
+
+    #[test]
+    fn fastest_laps_of_others_are_left_out_when_only_player() {
+        let mut machine = FlagMachine::default().with_fastest_lap(Duration::from_secs(2), true);
+        let fastest_lap = |player| FlagEvent::FastestLap {
+            vehicle_index: 1,
+            player,
+        };
+        assert_eq!(
+            shown_after(&mut machine, Instant::now(), &[fastest_lap(false)]),
+            None
+        );
+        let shown = shown_after(&mut machine, Instant::now(), &[fastest_lap(true)]);
+        assert_eq!(shown, Some(Flag::FastestLap));
+    }
+
+    #[test]
+    fn damage_waits_under_the_global_flags_and_ends() {
+        let mut machine = FlagMachine::default().with_damage(Duration::from_secs(3));
+        let start = Instant::now();
+        let shown = shown_after(
+            &mut machine,
+            start,
+            &[FlagEvent::SetGlobalFlag(GlobalFlag::Sc), FlagEvent::Damage],
+        );
+        assert_eq!(shown, Some(Flag::Global(GlobalFlag::Sc)));
+        machine.transition(Trigger::Event(FlagEvent::ResetGlobalFlag), start);
+        assert_eq!(machine.display(), Some(Flag::Damage));
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + Duration::from_secs(4)),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn collisions_strobe_white_and_red() {
+        let interval = Duration::from_millis(100);
+        let mut machine = FlagMachine::default().with_collision(Duration::from_secs(1), interval);
+        let start = Instant::now();
+        let shown = shown_after(&mut machine, start, &[FlagEvent::Collision]);
+        assert_eq!(shown, Some(Flag::Collision { red: false }));
+        assert_eq!(machine.deadline(), Some(start + interval));
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + interval),
+            Some(Some(Flag::Collision { red: true }))
+        );
+        assert_eq!(
+            machine.transition(Trigger::Tick, start + Duration::from_secs(1)),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn position_changes_only_when_set_up() {
+        let change = [FlagEvent::PositionChange { gained: true }];
+        let mut machine = FlagMachine::default();
+        assert_eq!(shown_after(&mut machine, Instant::now(), &change), None);
+
+        let mut machine = FlagMachine::default().with_position_changes(Duration::from_secs(2));
+        let shown = shown_after(&mut machine, Instant::now(), &change);
+        assert_eq!(shown, Some(Flag::PositionGained));
+    }
+
+    #[test]
+    fn ambient_is_shown_without_a_flag() {
+        let mut machine = FlagMachine::default();
+        let events = [
+            FlagEvent::Ambient(24),
+            FlagEvent::SetLocalFlag(LocalFlag::Blue),
+        ];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Local(LocalFlag::Blue)));
+        let shown = shown_after(&mut machine, Instant::now(), &[FlagEvent::ResetLocalFlag]);
+        assert_eq!(shown, Some(Flag::Ambient(24)));
+    }
+
+    #[test]
+    fn motion_is_shown_over_the_temperature() {
+        let mut machine = FlagMachine::default();
+        let events = [FlagEvent::Ambient(24), FlagEvent::Motion(40)];
+        let shown = shown_after(&mut machine, Instant::now(), &events);
+        assert_eq!(shown, Some(Flag::Motion(40)));
+        let shown = shown_after(&mut machine, Instant::now(), &[FlagEvent::Reset]);
+        assert_eq!(shown, None);
+    }
+
+    #[test]
+    fn nothing_to_show_when_unchanged() {
+        let mut machine = FlagMachine::default();
+        let yellow = Trigger::Event(FlagEvent::SetLocalFlag(LocalFlag::Yellow));
+        let now = Instant::now();
+        assert!(machine.transition(yellow, now).is_some());
+        assert_eq!(machine.transition(yellow, now), None);
+        assert_eq!(machine.transition(Trigger::Tick, now), None);
+    }
+}
@@ -0,0 +1,824 @@
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+use futures_util::future::join_all;
+use serde::Deserialize;
+use tracing::debug;
+use tracing::info;
+use tracing::trace;
+use tracing::warn;
+
+use crate::event::FlagEvent;
+use crate::flag::Abbreviation;
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::flag::PenaltyKind;
+use crate::flag::PitStatus;
+use crate::indicator::Indicator;
+use crate::indicator::IndicatorKind;
+use crate::output::Output;
+
+mod machine;
+
+pub use machine::Display;
+pub use machine::FlagMachine;
+pub use machine::Trigger;
+
+/// How long a penalty keeps its priority after it was given.
+pub const PENALTY_SHOW_TIME: Duration = Duration::from_secs(2);
+
+/// How long the black and white flag is shown after a warning, unless set up otherwise.
+pub const BLACK_AND_WHITE_SHOW_TIME: Duration = Duration::from_secs(3);
+
+/// How long green is shown when the start lights go out or a yellow, SC or VSC ends, unless set up
+/// otherwise.
+pub const GREEN_FLASH_TIME: Duration = Duration::from_secs(2);
+
+/// How long a failed output waits before it gets the shown flag again, doubling with every failure
+/// up to [`MAX_RETRY_DELAY`].
+pub const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// The longest a failed output waits before it is tried again.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Failures in a row after which an output reconnects, and again after as many more.
+const RECONNECT_AFTER_FAILURES: u32 = 3;
+
+/// How long outputs that send in the background get to send the lights off before shutting down.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(300);
+
+/// Snapshot of the state of a [`FlagManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlagState {
+    pub global_flag: Option<GlobalFlag>,
+    pub local_flag: Option<LocalFlag>,
+    pub race_finished: bool,
+    /// What was last sent to the output
+    pub shown: Option<Flag>,
+}
+
+/// What competes for the lights, the manager shows the first one in its priority order that is
+/// active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// The countdown of the formation lap, then from the first start light until the lights go out
+    StartLights,
+    Global,
+    /// Once the player is disqualified or retired
+    Black,
+    /// Single and double yellows
+    Yellow,
+    Blue,
+    /// For a while after the player got a warning
+    BlackAndWhite,
+    /// Only for [`PENALTY_SHOW_TIME`] after the penalty, or until a drive-through or stop-go is
+    /// served
+    Penalty,
+    /// For a while after the player crashed or hit a car
+    Collision,
+    /// For a while after the player's car got damaged
+    Damage,
+    /// While the player is in the pit lane or the box, and when told to pit this lap
+    Pit,
+    /// For a while after the player gained or lost places
+    Position,
+    FastestLap,
+    Finish,
+    /// Green from the game, and for a while after the start or a yellow, SC or VSC
+    Green,
+}
+
+/// The start lights take precedence over global flags, then the black flag, yellows and blue, which
+/// in turn take precedence over warnings, penalties, collisions, damage, pit stops, position
+/// changes, the fastest lap, the finish and green.
+pub const DEFAULT_PRIORITY: [Priority; 14] = [
+    Priority::StartLights,
+    Priority::Global,
+    Priority::Black,
+    Priority::Yellow,
+    Priority::Blue,
+    Priority::BlackAndWhite,
+    Priority::Penalty,
+    Priority::Collision,
+    Priority::Damage,
+    Priority::Pit,
+    Priority::Position,
+    Priority::FastestLap,
+    Priority::Finish,
+    Priority::Green,
+];
+
+/// Keeps track of the flag state of a session and sends the flag that should be shown to the
+/// output whenever it changes.
+///
+/// Which flag that is comes from its [`FlagMachine`]. It follows the [`DEFAULT_PRIORITY`] unless
+/// set up otherwise, a penalty that lost its priority stays on until something else changes. A
+/// [forced](FlagManager::force) flag goes over the priority, the pit limiter is shown over all of
+/// them while it is on.
+#[derive(Debug)]
+pub struct FlagManager {
+    machine: FlagMachine,
+    /// How often the shown flag is sent again while it does not change, not at all when `None`
+    keepalive: Option<Duration>,
+    next_keepalive: Option<Instant>,
+    published_state: FlagState,
+    outputs: Vec<Box<dyn Output>>,
+    /// Error of the last send to each flag output, `None` when it went through
+    output_errors: Vec<Option<String>>,
+    /// When each failing flag output gets the shown flag again
+    retries: Vec<Option<Retry>>,
+    indicators: Vec<IndicatorOutputs>,
+    zones: Vec<Zone>,
+}
+
+/// Outputs that show only some of the flags, independent of the other outputs.
+#[derive(Debug)]
+struct Zone {
+    priority: Vec<Priority>,
+    outputs: Vec<Box<dyn Output>>,
+    /// What was last sent, `None` before anything was
+    shown: Option<Display>,
+}
+
+/// Outputs that show one kind of indicator instead of the flags.
+#[derive(Debug)]
+struct IndicatorOutputs {
+    kind: IndicatorKind,
+    outputs: Vec<Box<dyn Output>>,
+    shown: Option<Indicator>,
+}
+
+/// A flag output that failed, tried again with the latest flag until it works.
+#[derive(Debug, Clone, Copy)]
+struct Retry {
+    /// Sends that failed in a row
+    failures: u32,
+    at: Instant,
+}
+
+impl FlagManager {
+    /// Creates a manager that sends the flags to all `outputs`.
+    pub fn new(outputs: Vec<Box<dyn Output>>) -> Self {
+        Self {
+            machine: FlagMachine::default(),
+            keepalive: None,
+            next_keepalive: None,
+            published_state: FlagState::default(),
+            output_errors: vec![None; outputs.len()],
+            retries: vec![None; outputs.len()],
+            outputs,
+            indicators: Vec::new(),
+            zones: Vec::new(),
+        }
+    }
+
+    /// Decides what to show with `machine`, for one set up before.
+    pub fn with_machine(mut self, machine: FlagMachine) -> Self {
+        self.machine = machine;
+        self
+    }
+
+    /// Sends the shown flag and indicators again every `interval` while they do not change, for
+    /// devices that lose what they show when they restart or miss a packet.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(interval);
+        self.next_keepalive = Some(Instant::now() + interval);
+        self
+    }
+
+    /// Adds outputs that show the indicator of `kind`, they never get the flags.
+    pub fn add_indicator_outputs(&mut self, kind: IndicatorKind, outputs: Vec<Box<dyn Output>>) {
+        self.indicators.push(IndicatorOutputs {
+            kind,
+            outputs,
+            shown: None,
+        });
+    }
+
+    /// Adds outputs that only show the flags of `priority`, in that order, and are off while none
+    /// of them is out. The forced flag and the pit limiter do not go to them.
+    pub fn add_zone(&mut self, priority: Vec<Priority>, outputs: Vec<Box<dyn Output>>) {
+        self.zones.push(Zone {
+            priority,
+            outputs,
+            shown: None,
+        });
+    }
+
+    /// The machine that decides what is shown.
+    pub fn machine(&self) -> &FlagMachine {
+        &self.machine
+    }
+
+    /// The currently active global flag.
+    pub fn global_flag(&self) -> Option<GlobalFlag> {
+        self.machine.global_flag()
+    }
+
+    /// The currently active local flag.
+    pub fn local_flag(&self) -> Option<LocalFlag> {
+        self.machine.local_flag()
+    }
+
+    /// Whether the chequered flag has been shown this session.
+    pub fn race_finished(&self) -> bool {
+        self.machine.race_finished()
+    }
+
+    /// Snapshot of the current state.
+    pub fn state(&self) -> FlagState {
+        FlagState {
+            global_flag: self.machine.global_flag(),
+            local_flag: self.machine.local_flag(),
+            race_finished: self.machine.race_finished(),
+            shown: self.machine.display(),
+        }
+    }
+
+    /// The flag shown over the priority and until when, when one is forced.
+    pub fn forced(&self) -> Option<(Option<Flag>, Option<Instant>)> {
+        self.machine.forced()
+    }
+
+    /// The penalty that competes for the lights and until when, `None` for a drive-through or
+    /// stop-go that lasts until it is served.
+    pub fn penalty(&self) -> Option<(Flag, Option<Instant>)> {
+        self.machine.penalty(Instant::now())
+    }
+
+    /// Race numbers of all cars, indexed by vehicle index. Zero when unknown.
+    pub fn driver_numbers(&self) -> &[u8; MAX_CARS_IN_SESSION] {
+        self.machine.driver_numbers()
+    }
+
+    /// Name abbreviations of all cars, indexed by vehicle index. `None` when unknown.
+    pub fn driver_abbreviations(&self) -> &[Option<Abbreviation>; MAX_CARS_IN_SESSION] {
+        self.machine.driver_abbreviations()
+    }
+
+    /// Error of the last send to each flag output, in the order they were given, `None` for the
+    /// outputs that work.
+    pub fn output_errors(&self) -> &[Option<String>] {
+        &self.output_errors
+    }
+
+    /// Applies a single event to the state, updating the output if needed.
+    pub async fn handle(&mut self, event: FlagEvent) {
+        trace!(?event, "Handling event");
+        match event {
+            FlagEvent::Reset => self.reset().await,
+            FlagEvent::Indicator(indicator) => self.show_indicator(indicator).await,
+            event => self.apply(Trigger::Event(event)).await,
+        }
+    }
+
+    /// Passes `trigger` to the machine, sending what it shows when that changed.
+    async fn apply(&mut self, trigger: Trigger) {
+        if let Some(display) = self.machine.transition(trigger, Instant::now()) {
+            self.send(display).await;
+        }
+        self.publish_state().await;
+        self.update_zones(false).await;
+    }
+
+    /// Sends what each zone shows to its outputs, only when that changed unless `again`.
+    async fn update_zones(&mut self, again: bool) {
+        for (zone_index, zone) in self.zones.iter_mut().enumerate() {
+            let display = self.machine.display_for(&zone.priority);
+            if !again && zone.shown == Some(display) {
+                continue;
+            }
+
+            zone.shown = Some(display);
+            let results =
+                join_all(zone.outputs.iter_mut().map(|output| output.show(display))).await;
+            for (i, result) in results.into_iter().enumerate() {
+                if let Err(e) = result {
+                    warn!(zone = zone_index, output = i, error = %e, "Failed to send zone flag");
+                }
+            }
+        }
+    }
+
+    /// Clears all state and turns the lights off.
+    pub async fn reset(&mut self) {
+        self.machine
+            .transition(Trigger::Event(FlagEvent::Reset), Instant::now());
+        // Sent even when nothing changed, for lights that show something of their own
+        self.send(self.machine.display()).await;
+        self.update_zones(true).await;
+        for i in 0..self.indicators.len() {
+            let kind = self.indicators[i].kind;
+            self.show_indicator(Indicator::off(kind)).await;
+        }
+    }
+
+    /// Turns all lights off, the pit limiter and forced flags too, and closes the outputs. For when
+    /// the program stops.
+    pub async fn shut_down(&mut self) {
+        self.machine
+            .transition(Trigger::ClearForced, Instant::now());
+        self.reset().await;
+        tokio::time::sleep(SHUTDOWN_GRACE).await;
+        let outputs = self
+            .outputs
+            .iter_mut()
+            .chain(
+                self.indicators
+                    .iter_mut()
+                    .flat_map(|indicators| &mut indicators.outputs),
+            )
+            .chain(self.zones.iter_mut().flat_map(|zone| &mut zone.outputs));
+        let results = join_all(outputs.map(|output| output.close())).await;
+        for (i, result) in results.into_iter().enumerate() {
+            if let Err(e) = result {
+                warn!(output = i, error = %e, "Failed to close output");
+            }
+        }
+    }
+
+    /// Sends an indicator to the outputs added for its kind, when it changed.
+    pub async fn show_indicator(&mut self, indicator: Indicator) {
+        for indicators in &mut self.indicators {
+            if indicators.kind != indicator.kind() || indicators.shown == Some(indicator) {
+                continue;
+            }
+
+            indicators.shown = Some(indicator);
+            let results = join_all(
+                indicators
+                    .outputs
+                    .iter_mut()
+                    .map(|output| output.show_indicator(indicator)),
+            )
+            .await;
+            for (i, result) in results.into_iter().enumerate() {
+                if let Err(e) = result {
+                    warn!(output = i, error = %e, "Failed to send indicator");
+                }
+            }
+        }
+    }
+
+    /// Shows the pit limiter over the flags, or the flag it covered when turned off.
+    pub async fn set_pit_limiter(&mut self, on: bool) {
+        self.apply(Trigger::Event(FlagEvent::PitLimiter(on))).await;
+    }
+
+    async fn send(&mut self, flag: Option<Flag>) {
+        debug!(flag = flag.map_or("off", Flag::name), "Showing flag");
+        self.next_keepalive = self.keepalive.map(|interval| Instant::now() + interval);
+        let results = join_all(self.outputs.iter_mut().map(|output| output.show(flag))).await;
+        for (i, result) in results.into_iter().enumerate() {
+            if let Err(e) = &result {
+                warn!(output = i, error = %e, "Failed to send show command");
+            }
+            self.record(i, result);
+        }
+        self.publish_state().await;
+    }
+
+    /// Keeps track of whether flag output `i` works, and when to try a failing one again.
+    fn record(&mut self, i: usize, result: io::Result<()>) {
+        match result {
+            Ok(()) => {
+                if self.retries[i].take().is_some() {
+                    info!(output = i, "Output works again");
+                }
+                self.output_errors[i] = None;
+            }
+            Err(e) => {
+                let failures = self.retries[i].map_or(0, |retry| retry.failures) + 1;
+                let delay = RETRY_DELAY
+                    .saturating_mul(2u32.saturating_pow(failures - 1))
+                    .min(MAX_RETRY_DELAY);
+                self.retries[i] = Some(Retry {
+                    failures,
+                    at: Instant::now() + delay,
+                });
+                self.output_errors[i] = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Sends the shown flag and state again to flag output `i`, after reconnecting it when it has
+    /// been failing for a while.
+    async fn retry(&mut self, i: usize) {
+        let failures = self.retries[i].map_or(0, |retry| retry.failures);
+        let output = &mut self.outputs[i];
+        if failures.is_multiple_of(RECONNECT_AFTER_FAILURES) {
+            debug!(output = i, failures, "Reconnecting output");
+            if let Err(e) = output.reconnect().await {
+                warn!(output = i, error = %e, "Failed to reconnect output");
+            }
+        }
+        let result = match output.show(self.machine.display()).await {
+            Ok(()) => output.update_state(&self.published_state).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            warn!(output = i, failures, error = %e, "Failed to send show command again");
+        }
+        self.record(i, result);
+    }
+
+    /// Lets the output know the state changed, for outputs that track more than the shown flag.
+    async fn publish_state(&mut self) {
+        let state = self.state();
+        if state == self.published_state {
+            return;
+        }
+
+        self.published_state = state;
+        let results = join_all(
+            self.outputs
+                .iter_mut()
+                .map(|output| output.update_state(&state)),
+        )
+        .await;
+        for (i, result) in results.into_iter().enumerate() {
+            if let Err(e) = result {
+                warn!(output = i, error = %e, "Failed to send state update");
+                self.record(i, Err(e));
+            }
+        }
+    }
+
+    /// Shows the temperature while no flag is out.
+    pub async fn set_ambient(&mut self, temperature: i8) {
+        self.apply(Trigger::Event(FlagEvent::Ambient(temperature)))
+            .await;
+    }
+
+    /// Shows how much of the formation lap is left, `0` ends the countdown.
+    pub async fn set_countdown(&mut self, left: u8) {
+        self.apply(Trigger::Event(FlagEvent::Countdown(left))).await;
+    }
+
+    /// Shows how many of the start lights are lit, which ends the countdown.
+    pub async fn set_start_lights(&mut self, lights: u8) {
+        self.apply(Trigger::Event(FlagEvent::StartLights(lights)))
+            .await;
+    }
+
+    /// Ends the start lights and the global flag of the formation lap, and shows green for a
+    /// while.
+    pub async fn lights_out(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::LightsOut)).await;
+    }
+
+    /// Shows the black flag until the session ends, the player was disqualified or retired.
+    pub async fn set_black_flag(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::BlackFlag)).await;
+    }
+
+    /// Shows the black and white flag for a while, the player got a warning.
+    pub async fn set_black_and_white(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::BlackAndWhite)).await;
+    }
+
+    /// Marks the race as finished and shows the chequered flag, after the finish animation when
+    /// set up.
+    pub async fn finish(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::Finish)).await;
+    }
+
+    /// Shows a penalty for the car with the given vehicle index, unless penalties of its kind are
+    /// ignored.
+    pub async fn set_penalty(&mut self, index: usize, kind: PenaltyKind) {
+        self.apply(Trigger::Event(FlagEvent::Penalty {
+            vehicle_index: index,
+            kind,
+        }))
+        .await;
+    }
+
+    /// Keeps reminding of the drive-through or stop-go the car with the given vehicle index still
+    /// has to serve, until `kind` is `None`.
+    pub async fn set_unserved_penalty(&mut self, index: usize, kind: Option<PenaltyKind>) {
+        self.apply(Trigger::Event(FlagEvent::UnservedPenalty {
+            vehicle_index: index,
+            kind,
+        }))
+        .await;
+    }
+
+    /// Shows where the player is in a pit stop, when set up to. Coming in ends the reminder to pit.
+    pub async fn set_pit_status(&mut self, status: PitStatus) {
+        self.apply(Trigger::Event(FlagEvent::PitStatus(status)))
+            .await;
+    }
+
+    /// Tells the player to pit at the end of the lap until they enter the pit lane, or stops
+    /// telling them to.
+    pub async fn set_pit_reminder(&mut self, on: bool) {
+        self.apply(Trigger::PitReminder(on)).await;
+    }
+
+    /// Shows `flag` over everything but the pit limiter for `ttl`, or until cleared when `None`.
+    /// `None` for the flag keeps the lights off.
+    pub async fn force(&mut self, flag: Option<Flag>, ttl: Option<Duration>) {
+        self.apply(Trigger::Force { flag, ttl }).await;
+    }
+
+    /// Goes back to the flags of the game after [`force`](Self::force).
+    pub async fn clear_forced(&mut self) {
+        self.apply(Trigger::ClearForced).await;
+    }
+
+    /// Shows that the player gained or lost places, when set up to.
+    pub async fn set_position_change(&mut self, gained: bool) {
+        self.apply(Trigger::Event(FlagEvent::PositionChange { gained }))
+            .await;
+    }
+
+    /// Shows the fastest lap of the car at `vehicle_index`, when set up to show the fastest laps of
+    /// everyone or `player` is set.
+    pub async fn set_fastest_lap(&mut self, vehicle_index: usize, player: bool) {
+        self.apply(Trigger::Event(FlagEvent::FastestLap {
+            vehicle_index,
+            player,
+        }))
+        .await;
+    }
+
+    /// Sets the global flag, by default shown over all other flags until it is reset.
+    pub async fn set_global_flag(&mut self, flag: GlobalFlag) {
+        self.apply(Trigger::Event(FlagEvent::SetGlobalFlag(flag)))
+            .await;
+    }
+
+    /// Clears the global flag and falls back to the local state.
+    pub async fn reset_global_flag(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::ResetGlobalFlag)).await;
+    }
+
+    /// Sets the local flag of the player.
+    pub async fn set_local_flag(&mut self, flag: LocalFlag) {
+        self.apply(Trigger::Event(FlagEvent::SetLocalFlag(flag)))
+            .await;
+    }
+
+    /// Clears the local flag of the player.
+    pub async fn reset_local_flag(&mut self) {
+        self.apply(Trigger::Event(FlagEvent::ResetLocalFlag)).await;
+    }
+
+    /// When the shown flag has to change without a new event, like a fastest lap that is over.
+    pub fn deadline(&self) -> Option<Instant> {
+        [
+            self.machine.deadline(),
+            self.next_keepalive,
+            self.retries.iter().flatten().map(|retry| retry.at).min(),
+            self.outputs
+                .iter()
+                .chain(
+                    self.indicators
+                        .iter()
+                        .flat_map(|indicators| &indicators.outputs),
+                )
+                .chain(self.zones.iter().flat_map(|zone| &zone.outputs))
+                .filter_map(|output| output.flush_at())
+                .min(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Updates the output once the [`deadline`](Self::deadline) passed.
+    pub async fn tick(&mut self) {
+        let now = Instant::now();
+        self.apply(Trigger::Tick).await;
+        // Unless the update just sent something
+        if self.next_keepalive.is_some_and(|next| next <= now) {
+            self.send_again().await;
+        }
+        for i in 0..self.outputs.len() {
+            if self.retries[i].is_some_and(|retry| retry.at <= now) {
+                self.retry(i).await;
+            } else if self.outputs[i].flush_at().is_some_and(|at| at <= now) {
+                let result = self.outputs[i].flush().await;
+                if let Err(e) = &result {
+                    warn!(output = i, error = %e, "Failed to send held back messages");
+                }
+                self.record(i, result);
+            }
+        }
+        for indicators in &mut self.indicators {
+            for (i, output) in indicators.outputs.iter_mut().enumerate() {
+                if output.flush_at().is_some_and(|at| at <= now)
+                    && let Err(e) = output.flush().await
+                {
+                    warn!(output = i, error = %e, "Failed to send held back indicator");
+                }
+            }
+        }
+        for (zone, outputs) in self.zones.iter_mut().enumerate() {
+            for (i, output) in outputs.outputs.iter_mut().enumerate() {
+                if output.flush_at().is_some_and(|at| at <= now)
+                    && let Err(e) = output.flush().await
+                {
+                    warn!(zone, output = i, error = %e, "Failed to send held back zone flag");
+                }
+            }
+        }
+    }
+
+    /// Sends what is shown to all outputs again, the flag, the zones and the indicators.
+    async fn send_again(&mut self) {
+        trace!("Sending the shown flag again");
+        self.send(self.machine.display()).await;
+        self.update_zones(true).await;
+        for indicators in &mut self.indicators {
+            let Some(indicator) = indicators.shown else {
+                continue;
+            };
+            let results = join_all(
+                indicators
+                    .outputs
+                    .iter_mut()
+                    .map(|output| output.show_indicator(indicator)),
+            )
+            .await;
+            for (i, result) in results.into_iter().enumerate() {
+                if let Err(e) = result {
+                    warn!(output = i, error = %e, "Failed to send indicator");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::indicator::Drs;
+    use crate::output::ChannelOutput;
+    use crate::output::channel::OutputMessage;
+
+    fn manager() -> (FlagManager, mpsc::UnboundedReceiver<OutputMessage>) {
+        let (output, messages) = ChannelOutput::new();
+        (FlagManager::new(vec![Box::new(output)]), messages)
+    }
+
+    /// The flags sent so far, leaving out the state updates.
+    fn shown(messages: &mut mpsc::UnboundedReceiver<OutputMessage>) -> Vec<Option<Flag>> {
+        let mut shown = Vec::new();
+        while let Ok(message) = messages.try_recv() {
+            if let OutputMessage::Show(flag) = message {
+                shown.push(flag);
+            }
+        }
+        shown
+    }
+
+    #[tokio::test]
+    async fn sends_only_what_changes() {
+        let (mut manager, mut messages) = manager();
+        manager
+            .handle(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+            .await;
+        manager
+            .handle(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+            .await;
+        manager
+            .handle(FlagEvent::SetGlobalFlag(GlobalFlag::Sc))
+            .await;
+        assert_eq!(
+            shown(&mut messages),
+            [
+                Some(Flag::Local(LocalFlag::Yellow)),
+                Some(Flag::Global(GlobalFlag::Sc)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_the_state() {
+        let (mut manager, mut messages) = manager();
+        manager.handle(FlagEvent::Finish).await;
+        let state = FlagState {
+            global_flag: None,
+            local_flag: None,
+            race_finished: true,
+            shown: Some(Flag::Finish),
+        };
+        assert_eq!(
+            messages.try_recv().ok(),
+            Some(OutputMessage::Show(Some(Flag::Finish)))
+        );
+        assert_eq!(messages.try_recv().ok(), Some(OutputMessage::State(state)));
+        assert_eq!(manager.state(), state);
+    }
+
+    #[tokio::test]
+    async fn reset_turns_the_lights_off_even_when_dark() {
+        let (mut manager, mut messages) = manager();
+        manager.handle(FlagEvent::Reset).await;
+        manager.handle(FlagEvent::BlackFlag).await;
+        manager.handle(FlagEvent::Reset).await;
+        assert_eq!(shown(&mut messages), [None, Some(Flag::Black), None]);
+    }
+
+    #[tokio::test]
+    async fn indicators_only_go_to_their_outputs() {
+        let (mut manager, mut flags) = manager();
+        let (output, mut drs) = ChannelOutput::new();
+        manager.add_indicator_outputs(IndicatorKind::Drs, vec![Box::new(output)]);
+        let indicator = Indicator::Drs(Drs::Open);
+        manager.handle(FlagEvent::Indicator(indicator)).await;
+        manager.handle(FlagEvent::Indicator(indicator)).await;
+
+        assert_eq!(
+            drs.try_recv().ok(),
+            Some(OutputMessage::Indicator(indicator))
+        );
+        assert!(drs.try_recv().is_err());
+        assert!(flags.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn zones_show_only_their_flags() {
+        let (mut manager, mut flags) = manager();
+        let (output, mut global) = ChannelOutput::new();
+        manager.add_zone(vec![Priority::Global], vec![Box::new(output)]);
+        let (output, mut blue) = ChannelOutput::new();
+        manager.add_zone(vec![Priority::Blue], vec![Box::new(output)]);
+        manager
+            .handle(FlagEvent::SetLocalFlag(LocalFlag::Blue))
+            .await;
+        manager
+            .handle(FlagEvent::SetGlobalFlag(GlobalFlag::Sc))
+            .await;
+        manager.handle(FlagEvent::ResetLocalFlag).await;
+
+        let sc = Some(Flag::Global(GlobalFlag::Sc));
+        let blue_flag = Some(Flag::Local(LocalFlag::Blue));
+        assert_eq!(shown(&mut flags), [blue_flag, sc]);
+        assert_eq!(shown(&mut global), [None, sc]);
+        assert_eq!(shown(&mut blue), [blue_flag, None]);
+    }
+
+    #[tokio::test]
+    async fn forced_flag_goes_over_the_game() {
+        let (mut manager, mut messages) = manager();
+        manager
+            .handle(FlagEvent::SetGlobalFlag(GlobalFlag::Vsc))
+            .await;
+        manager
+            .force(Some(Flag::Global(GlobalFlag::Red)), None)
+            .await;
+        manager
+            .handle(FlagEvent::SetGlobalFlag(GlobalFlag::Sc))
+            .await;
+        manager.clear_forced().await;
+        assert_eq!(
+            shown(&mut messages),
+            [
+                Some(Flag::Global(GlobalFlag::Vsc)),
+                Some(Flag::Global(GlobalFlag::Red)),
+                Some(Flag::Global(GlobalFlag::Sc)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn shut_down_turns_off_and_closes() {
+        let (mut manager, mut messages) = manager();
+        manager.set_pit_limiter(true).await;
+        manager.force(Some(Flag::Finish), None).await;
+        manager.shut_down().await;
+
+        let mut sent = Vec::new();
+        while let Ok(message) = messages.try_recv() {
+            if !matches!(message, OutputMessage::State(_)) {
+                sent.push(message);
+            }
+        }
+        assert_eq!(
+            sent,
+            [
+                OutputMessage::Show(Some(Flag::PitLimiter)),
+                OutputMessage::Show(None),
+                OutputMessage::Close,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_output_is_reported_and_retried() {
+        let (mut manager, messages) = manager();
+        drop(messages);
+        manager.handle(FlagEvent::Finish).await;
+        assert!(manager.output_errors()[0].is_some());
+        let deadline = manager.deadline().expect("the output is retried");
+        assert!(deadline <= Instant::now() + MAX_RETRY_DELAY);
+    }
+}
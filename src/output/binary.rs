@@ -0,0 +1,97 @@
+//! Fixed-size binary frames, for firmwares that would rather not parse text.
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::flag::PenaltyKind;
+
+/// Id of the frame that clears the lights.
+const OFF: u8 = 0xff;
+
+/// The two byte frame for the flag, `None` clears the lights.
+///
+/// The first byte is the id of the flag, the number of its default code, and [`OFF`] to clear the
+/// lights. The second byte is the race number of a penalty, the percentage of a countdown, the
+/// start lights lit or the temperature as a signed byte, and 0 for the other flags.
+pub(crate) fn frame(flag: Option<Flag>) -> [u8; 2] {
+    let Some(flag) = flag else {
+        return [OFF, 0];
+    };
+    match flag {
+        Flag::Global(global_flag) => [
+            match global_flag {
+                GlobalFlag::Vsc => 5,
+                GlobalFlag::VscEnding => 14,
+                GlobalFlag::Sc => 4,
+                GlobalFlag::ScEnding => 13,
+                GlobalFlag::Red => 12,
+                GlobalFlag::FormationLap => 15,
+            },
+            0,
+        ],
+        Flag::Local(local_flag) => [
+            match local_flag {
+                LocalFlag::Green => 1,
+                LocalFlag::Yellow => 2,
+                LocalFlag::DoubleYellow => 3,
+                LocalFlag::Blue => 8,
+            },
+            0,
+        ],
+        Flag::Penalty { number, kind, .. } => [
+            match kind {
+                PenaltyKind::DriveThrough => 17,
+                PenaltyKind::StopGo => 18,
+                _ => 11,
+            },
+            // Race numbers go up to 99
+            number.try_into().unwrap_or(u8::MAX),
+        ],
+        Flag::Finish => [16, 0],
+        Flag::PitLimiter => [6, 0],
+        Flag::FastestLap => [9, 0],
+        Flag::Damage => [26, 0],
+        Flag::Collision { red } => [28, u8::from(red)],
+        Flag::Black => [7, 0],
+        Flag::BlackAndWhite => [10, 0],
+        Flag::Countdown(left) => [22, left],
+        Flag::StartLights(lights) => [19, lights],
+        Flag::Ambient(temperature) => [0, temperature as u8],
+        Flag::Motion(percent) => [27, percent],
+        Flag::PositionGained => [20, 0],
+        Flag::PositionLost => [21, 0],
+        Flag::PitLane => [23, 0],
+        Flag::PitStop => [24, 0],
+        Flag::PitThisLap => [25, 0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_have_the_id_and_argument() {
+        assert_eq!(frame(None), [OFF, 0]);
+        assert_eq!(frame(Some(Flag::Global(GlobalFlag::Sc))), [4, 0]);
+        let penalty = Flag::Penalty {
+            number: 44,
+            kind: PenaltyKind::StopGo,
+            driver: None,
+        };
+        assert_eq!(frame(Some(penalty)), [18, 44]);
+        assert_eq!(frame(Some(Flag::Ambient(-5))), [0, 0xfb]);
+    }
+
+    #[test]
+    fn ids_are_the_default_codes() {
+        let flags = [
+            Flag::Global(GlobalFlag::VscEnding),
+            Flag::Local(LocalFlag::Blue),
+            Flag::Finish,
+            Flag::PitThisLap,
+        ];
+        for flag in flags {
+            assert_eq!(frame(Some(flag))[0].to_string(), flag.to_enum_str());
+        }
+    }
+}
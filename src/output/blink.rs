@@ -0,0 +1,71 @@
+//! Blinking for controllers that only show the code they got last.
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// How a flag is shown by outputs that send codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    #[default]
+    Solid,
+    /// On and off once per second
+    SlowBlink,
+    /// On and off two and a half times per second
+    FastBlink,
+}
+
+impl Pattern {
+    /// How long the flag is on and off for, `None` when it stays on.
+    pub fn interval(self) -> Option<Duration> {
+        match self {
+            Pattern::Solid => None,
+            Pattern::SlowBlink => Some(Duration::from_millis(500)),
+            Pattern::FastBlink => Some(Duration::from_millis(200)),
+        }
+    }
+}
+
+/// Resends the off code and the code of the flag in turn until stopped.
+#[derive(Debug, Default)]
+pub(crate) struct Blinker {
+    task: Option<JoinHandle<()>>,
+}
+
+impl Blinker {
+    pub(crate) fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Calls `send` every `interval`, with `false` for the off code and `true` for the flag,
+    /// starting with off as the flag was just sent.
+    pub(crate) fn start<F, Fut>(&mut self, interval: Duration, mut send: F)
+    where
+        F: FnMut(bool) -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<()>> + Send,
+    {
+        self.stop();
+        self.task = Some(tokio::spawn(async move {
+            let mut on = true;
+            loop {
+                tokio::time::sleep(interval).await;
+                on = !on;
+                if let Err(e) = send(on).await {
+                    warn!(error = %e, "Failed to blink");
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for Blinker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,58 @@
+//! Handing what the outputs get to the program itself, for tests and for embedding the manager.
+use std::io;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::Output;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::manager::FlagState;
+
+/// What a [`ChannelOutput`] was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMessage {
+    Show(Option<Flag>),
+    State(FlagState),
+    Indicator(Indicator),
+    Close,
+}
+
+/// Passes everything it gets on to a channel, in order.
+#[derive(Debug)]
+pub struct ChannelOutput {
+    sender: mpsc::UnboundedSender<OutputMessage>,
+}
+
+impl ChannelOutput {
+    /// The output with the receiving end of its channel. Sends fail once the receiver is dropped.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<OutputMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    fn send(&self, message: OutputMessage) -> io::Result<()> {
+        self.sender
+            .send(message)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the receiver is gone"))
+    }
+}
+
+#[async_trait]
+impl Output for ChannelOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.send(OutputMessage::Show(flag))
+    }
+
+    async fn update_state(&mut self, state: &FlagState) -> io::Result<()> {
+        self.send(OutputMessage::State(*state))
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.send(OutputMessage::Indicator(indicator))
+    }
+
+    async fn close(&mut self) -> io::Result<()> {
+        self.send(OutputMessage::Close)
+    }
+}
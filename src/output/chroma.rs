@@ -0,0 +1,207 @@
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::warn;
+
+use super::Output;
+use crate::color::Rgb;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::indicator::Indicator;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// The SDK drops the session when it does not hear from us for 15 seconds.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const FLASH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Config for [`ChromaOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChromaConfig {
+    #[serde(default = "default_url")]
+    pub url: String,
+    /// Device types that get the effects
+    #[serde(default = "default_devices")]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub colors: FlagTable<Rgb>,
+    /// Flags that flash instead of showing a static color, by default the pit limiter, fastest lap
+    /// and the end of a (virtual) safety car
+    #[serde(default)]
+    pub flashing: FlagTable<bool>,
+}
+
+fn default_url() -> String {
+    String::from("http://localhost:54235/razer/chromasdk")
+}
+
+fn default_devices() -> Vec<String> {
+    [
+        "keyboard",
+        "mouse",
+        "mousepad",
+        "headset",
+        "keypad",
+        "chromalink",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+#[derive(Debug, Deserialize)]
+struct Session {
+    uri: String,
+}
+
+/// Shows the flags on Razer devices through the Chroma SDK REST API.
+#[derive(Debug)]
+pub struct ChromaOutput {
+    config: ChromaConfig,
+    effect: watch::Sender<(Rgb, bool)>,
+}
+
+impl ChromaOutput {
+    /// Registers with the SDK and keeps the session alive in the background.
+    pub async fn connect(config: ChromaConfig) -> io::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(io::Error::other)?;
+
+        let session: Session = client
+            .post(&config.url)
+            .json(&json!({
+                "title": "f1_light_sync",
+                "description": "Flags from the F1 games",
+                "author": {
+                    "name": "f1_light_sync",
+                    "contact": "https://github.com/Wouter17/f1_light_sync",
+                },
+                "device_supported": config.devices,
+                "category": "application",
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(io::Error::other)?
+            .json()
+            .await
+            .map_err(io::Error::other)?;
+
+        let (effect, effect_changed) = watch::channel((Rgb::OFF, false));
+        let session = ChromaSession {
+            client,
+            uri: session.uri,
+            devices: config.devices.clone(),
+        };
+        tokio::spawn(session.run(effect_changed));
+
+        Ok(Self { config, effect })
+    }
+}
+
+#[async_trait]
+impl Output for ChromaOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        let color = self
+            .config
+            .colors
+            .get(flag)
+            .copied()
+            .unwrap_or_else(|| Rgb::default_for(flag));
+        let flashing = self.config.flashing.get(flag).copied().unwrap_or(matches!(
+            flag,
+            Some(
+                Flag::PitLimiter
+                    | Flag::FastestLap
+                    | Flag::Damage
+                    | Flag::PitThisLap
+                    | Flag::Global(GlobalFlag::ScEnding | GlobalFlag::VscEnding)
+            )
+        ));
+        self.effect
+            .send((color, flashing))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Chroma session stopped"))
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.effect
+            .send((indicator.color(), indicator.flashing()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Chroma session stopped"))
+    }
+}
+
+struct ChromaSession {
+    client: reqwest::Client,
+    uri: String,
+    devices: Vec<String>,
+}
+
+impl ChromaSession {
+    async fn run(self, mut effect_changed: watch::Receiver<(Rgb, bool)>) {
+        let mut flash = tokio::time::interval(FLASH_INTERVAL);
+        let mut last_heartbeat = Instant::now();
+        let mut lit = true;
+        loop {
+            tokio::select! {
+                changed = effect_changed.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    lit = true;
+                    flash.reset();
+                }
+                _ = flash.tick() => {
+                    if last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
+                        last_heartbeat = Instant::now();
+                        self.put("heartbeat", None).await;
+                    }
+                    if !effect_changed.borrow().1 {
+                        continue;
+                    }
+                    lit = !lit;
+                }
+            }
+
+            let (color, _) = *effect_changed.borrow_and_update();
+            self.set_color(if lit { color } else { Rgb::OFF }).await;
+        }
+
+        if let Err(e) = self.client.delete(&self.uri).send().await {
+            warn!(error = %e, "Failed to close Chroma session");
+        }
+    }
+
+    async fn set_color(&self, color: Rgb) {
+        let effect = if color == Rgb::OFF {
+            json!({ "effect": "CHROMA_NONE" })
+        } else {
+            // The SDK wants the color as 0x00BBGGRR
+            let bgr = u32::from_le_bytes([color.r(), color.g(), color.b(), 0]);
+            json!({ "effect": "CHROMA_STATIC", "param": { "color": bgr } })
+        };
+        for device in &self.devices {
+            self.put(device, Some(&effect)).await;
+        }
+    }
+
+    async fn put(&self, path: &str, body: Option<&serde_json::Value>) {
+        let mut request = self.client.put(format!("{}/{path}", self.uri));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        if let Err(e) = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            warn!(path, error = %e, "Failed to send Chroma request");
+        }
+    }
+}
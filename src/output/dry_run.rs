@@ -0,0 +1,34 @@
+//! Printing what would be sent instead of sending it, for going through a recorded session.
+use std::io;
+
+use async_trait::async_trait;
+
+use super::Output;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+/// Prints every flag and indicator to stdout, prefixed with the output it stands in for.
+#[derive(Debug)]
+pub struct DryRunOutput {
+    name: String,
+}
+
+impl DryRunOutput {
+    /// Stands in for the output described by `name`.
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+#[async_trait]
+impl Output for DryRunOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        println!("{}: {}", self.name, flag.map_or("off", Flag::name));
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        println!("{}: {indicator:?}", self.name);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Messages as JSON objects, for receivers that would rather not look the codes up.
+use std::time::SystemTime;
+
+use serde_json::Value;
+use serde_json::json;
+
+use super::board;
+use super::tower;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::indicator::WarningLevel;
+
+/// The JSON message for the flag with its `code`, `None` clears the lights.
+///
+/// Besides the `flag` name (`off` without one) and the `code`, it has the race number of a
+/// penalized `driver`, the unique id of the F1 `session` as a string and the Unix `time` in
+/// seconds. The driver and session are `null` when there is none.
+pub(crate) fn message(flag: Option<Flag>, code: &str, session_uid: Option<u64>) -> String {
+    let driver = match flag {
+        Some(Flag::Penalty { number, .. }) => Some(number),
+        _ => None,
+    };
+    json!({
+        "flag": flag.map_or("off", Flag::name),
+        "code": code,
+        "driver": driver,
+        // As a string, JSON numbers lose the lower bits of a u64
+        "session": session_uid.map(|session_uid| session_uid.to_string()),
+        "time": now(),
+    })
+    .to_string()
+}
+
+/// The indicator as a JSON object with its name as the key, like `{"drs": "open"}`.
+pub(crate) fn indicator(indicator: Indicator) -> Value {
+    match indicator {
+        Indicator::Drs(drs) => json!({ "drs": drs.name() }),
+        Indicator::RevLights(percent) => json!({ "rev_lights": percent }),
+        Indicator::Rpm { level, redline } => {
+            json!({ "rpm": { "level": level, "redline": redline } })
+        }
+        Indicator::Ers { mode, charge } => {
+            json!({ "ers": { "mode": mode.name(), "charge": charge } })
+        }
+        Indicator::Tyre(tyre) => json!({ "tyre": tyre.name() }),
+        Indicator::Gear(gear) => json!({ "gear": gear }),
+        Indicator::TyreWarning(levels) => {
+            json!({ "tyre_warning": levels.map(WarningLevel::name) })
+        }
+        Indicator::TemperatureWarning { engine, brakes } => {
+            json!({ "temperature_warning": { "engine": engine.name(), "brakes": brakes.name() } })
+        }
+        Indicator::LowFuel(low) => json!({ "low_fuel": low }),
+        Indicator::Rain(coming) => json!({ "rain": coming }),
+        Indicator::LapDelta { delta, purple } => {
+            json!({ "lap_delta": { "delta": delta, "purple": purple } })
+        }
+        Indicator::Gap { gap, .. } => json!({ "gap": gap }),
+        Indicator::Progress(percent) => json!({ "progress": percent }),
+        Indicator::MarshalPosts(lit) => json!({ "marshal_posts": lit }),
+        Indicator::PositionBoard(teams) => json!({ "position_board": board(teams) }),
+        Indicator::TimingTower(intervals) => json!({ "timing_tower": tower(intervals) }),
+    }
+}
+
+/// The JSON message for the indicator with its `code`, the [`indicator`] object with the `code`,
+/// `session` and `time` of the message of a flag.
+pub(crate) fn indicator_message(
+    indicator: Indicator,
+    code: &str,
+    session_uid: Option<u64>,
+) -> String {
+    let mut message = self::indicator(indicator);
+    message["code"] = json!(code);
+    message["session"] = json!(session_uid.map(|session_uid| session_uid.to_string()));
+    message["time"] = json!(now());
+    message.to_string()
+}
+
+/// The Unix time in seconds.
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flag::PenaltyKind;
+
+    #[test]
+    fn describes_the_flag() {
+        let penalty = Flag::Penalty {
+            number: 44,
+            driver: None,
+            kind: PenaltyKind::DriveThrough,
+        };
+        let shown: Value = serde_json::from_str(&message(Some(penalty), "17,44", Some(7))).unwrap();
+        assert_eq!(shown["flag"], "drive_through");
+        assert_eq!(shown["code"], "17,44");
+        assert_eq!(shown["driver"], 44);
+        assert_eq!(shown["session"], "7");
+        assert!(shown["time"].as_f64().unwrap() > 0.0);
+
+        let off: Value = serde_json::from_str(&message(None, "c", None)).unwrap();
+        assert_eq!(off["flag"], "off");
+        assert_eq!(off["driver"], Value::Null);
+        assert_eq!(off["session"], Value::Null);
+    }
+
+    #[test]
+    fn describes_the_indicator() {
+        let shown: Value =
+            serde_json::from_str(&indicator_message(Indicator::Gear(3), "64,3", Some(7))).unwrap();
+        assert_eq!(shown["gear"], 3);
+        assert_eq!(shown["code"], "64,3");
+        assert_eq!(shown["session"], "7");
+        assert!(shown["time"].as_f64().unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,62 @@
+//! One light of a string of marshal posts around the room.
+use std::io;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use super::Output;
+use crate::flag::Flag;
+use crate::flag::LocalFlag;
+use crate::indicator::Indicator;
+
+/// Shows the yellow flag on the output it wraps while its marshal post is lit, and turns it off
+/// otherwise.
+///
+/// It is added for the [`MarshalPosts`](crate::indicator::IndicatorKind::MarshalPosts) indicator,
+/// so the output gets a flag no matter the indicator.
+#[derive(Debug)]
+pub struct MarshalPostOutput {
+    inner: Box<dyn Output>,
+    /// Which bit of the indicator is this post
+    post: usize,
+}
+
+impl MarshalPostOutput {
+    /// Wraps `inner` for the marshal post with index `post`.
+    pub fn new(inner: Box<dyn Output>, post: usize) -> Self {
+        Self { inner, post }
+    }
+}
+
+#[async_trait]
+impl Output for MarshalPostOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.inner.show(flag).await
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        let Indicator::MarshalPosts(posts) = indicator else {
+            return Ok(());
+        };
+        let lit = posts & (1 << self.post) != 0;
+        self.inner
+            .show(lit.then_some(Flag::Local(LocalFlag::Yellow)))
+            .await
+    }
+
+    fn flush_at(&self) -> Option<Instant> {
+        self.inner.flush_at()
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn reconnect(&mut self) -> io::Result<()> {
+        self.inner.reconnect().await
+    }
+
+    async fn close(&mut self) -> io::Result<()> {
+        self.inner.close().await
+    }
+}
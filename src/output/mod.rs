@@ -0,0 +1,237 @@
+//! The devices the flags can be sent to.
+
+use std::fmt::Debug;
+use std::io;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use f1_game_library_models_25::constants::MAX_CARS_IN_SESSION;
+use tokio::sync::watch;
+
+use crate::config::FlagTable;
+use crate::config::OutputConfig;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::indicator::Interval;
+use crate::indicator::NO_CAR;
+use crate::manager::FlagState;
+use crate::output::simhub::Format;
+
+mod binary;
+pub mod blink;
+pub mod channel;
+pub mod chroma;
+pub mod dry_run;
+mod json;
+pub mod marshal;
+pub mod mqtt;
+pub mod openrgb;
+pub mod sacn;
+pub mod serial;
+pub mod simhub;
+mod template;
+pub mod throttle;
+pub mod udp;
+pub mod websocket;
+pub mod wled;
+#[cfg(all(feature = "ws2812", target_os = "linux"))]
+pub mod ws2812;
+
+pub use channel::ChannelOutput;
+pub use chroma::ChromaOutput;
+pub use dry_run::DryRunOutput;
+pub use marshal::MarshalPostOutput;
+pub use mqtt::MqttOutput;
+pub use openrgb::OpenRgbOutput;
+pub use sacn::SacnOutput;
+pub use serial::SerialOutput;
+pub use throttle::ThrottledOutput;
+pub use udp::UdpOutput;
+pub use websocket::WebSocketOutput;
+pub use wled::WledOutput;
+#[cfg(all(feature = "ws2812", target_os = "linux"))]
+pub use ws2812::Ws2812Output;
+
+/// A device the flags are shown on.
+///
+/// Implement this to send the flags somewhere the crate does not support out of the box.
+#[async_trait]
+pub trait Output: Debug + Send {
+    /// Shows the flag on the device, `None` clears it.
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()>;
+
+    /// Called with the full state whenever it changes, for outputs that publish more than the
+    /// shown flag.
+    async fn update_state(&mut self, _state: &FlagState) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Shows an indicator like DRS, only called on outputs set up for indicators.
+    async fn show_indicator(&mut self, _indicator: Indicator) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// When [`flush`](Self::flush) should be called, for outputs that held a message back.
+    fn flush_at(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Sends the messages that were held back.
+    async fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called when sends keep failing, for outputs that can look their device up or open it again,
+    /// like after it got a new address.
+    async fn reconnect(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once the lights were turned off before the program stops, for outputs that have
+    /// to say goodbye to the device.
+    async fn close(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sets up the output described by the config. Outputs that send JSON tell the session from
+/// `session_uid`.
+pub async fn connect(
+    config: &OutputConfig,
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+) -> io::Result<Box<dyn Output>> {
+    Ok(match config {
+        OutputConfig::Udp {
+            destination,
+            codes,
+            format,
+            template,
+            patterns,
+        } => {
+            let mut output = UdpOutput::connect(destination)
+                .await?
+                .with_codes(codes.clone())
+                .with_format(*format)
+                .with_patterns(patterns.clone());
+            if let Some(template) = template {
+                output = output.with_template(template.clone());
+            }
+            if let Some(session_uid) = session_uid {
+                output = output.with_session_uid(session_uid);
+            }
+            Box::new(output)
+        }
+        OutputConfig::Wled(config) => Box::new(WledOutput::new(config.clone())?),
+        OutputConfig::Mqtt(config) => Box::new(MqttOutput::connect(config.clone())),
+        OutputConfig::Sacn(config) => Box::new(SacnOutput::connect(config.clone()).await?),
+        OutputConfig::Serial(config) => {
+            let mut output = SerialOutput::open(config.clone())?;
+            if let Some(session_uid) = session_uid {
+                output = output.with_session_uid(session_uid);
+            }
+            Box::new(output)
+        }
+        OutputConfig::OpenRgb(config) => Box::new(OpenRgbOutput::connect(config.clone()).await?),
+        OutputConfig::Chroma(config) => Box::new(ChromaOutput::connect(config.clone()).await?),
+        OutputConfig::WebSocket(config) => Box::new(WebSocketOutput::bind(config.clone()).await?),
+        #[cfg(all(feature = "ws2812", target_os = "linux"))]
+        OutputConfig::Ws2812(config) => Box::new(Ws2812Output::open(config.clone())?),
+    })
+}
+
+/// What the UDP and serial outputs send for the flag in `format`, filled into the `template` when
+/// there is one. JSON messages have the `session_uid` in them, binary frames are never filled in.
+pub(crate) fn message(
+    flag: Option<Flag>,
+    format: Format,
+    codes: &FlagTable<String>,
+    template: Option<&str>,
+    session_uid: Option<u64>,
+) -> Vec<u8> {
+    let code = match format {
+        Format::Codes => code(flag, codes),
+        Format::SimHub => simhub::message(flag),
+        Format::Json => json::message(flag, &code(flag, codes), session_uid),
+        Format::Binary => return binary::frame(flag).to_vec(),
+    };
+    match template {
+        Some(template) => template::fill(template, flag, &code).into_bytes(),
+        None => code.into_bytes(),
+    }
+}
+
+/// What the UDP and serial outputs send for the indicator in `format`, filled into the `template`
+/// like the [`message`] of a flag. SimHub has no indicators and binary frames only have ids for
+/// the flags, both get the code as text.
+pub(crate) fn indicator_message(
+    indicator: Indicator,
+    format: Format,
+    template: Option<&str>,
+    session_uid: Option<u64>,
+) -> Vec<u8> {
+    let code = indicator.to_enum_str();
+    let code = match format {
+        Format::Codes | Format::SimHub => code,
+        Format::Json => json::indicator_message(indicator, &code, session_uid),
+        Format::Binary => return code.into_bytes(),
+    };
+    match template {
+        Some(template) => template::fill_indicator(template, indicator, &code).into_bytes(),
+        None => code.into_bytes(),
+    }
+}
+
+/// The teams of the cars on the position board, leaving out the places without a car.
+pub(crate) fn board(teams: [u8; MAX_CARS_IN_SESSION]) -> Vec<u8> {
+    teams.into_iter().filter(|&team| team != NO_CAR).collect()
+}
+
+/// The names of the gaps on the timing tower, leaving out the places without a car.
+pub(crate) fn tower(intervals: [Option<Interval>; MAX_CARS_IN_SESSION]) -> Vec<&'static str> {
+    intervals
+        .into_iter()
+        .flatten()
+        .map(Interval::name)
+        .collect()
+}
+
+/// The session the outputs were told about, if any.
+pub(crate) fn session_uid(session_uid: &Option<watch::Receiver<Option<u64>>>) -> Option<u64> {
+    session_uid
+        .as_ref()
+        .and_then(|session_uid| *session_uid.borrow())
+}
+
+/// The enum string of the flag, or `c` to clear the lights.
+pub(crate) fn enum_str(flag: Option<Flag>) -> String {
+    flag.map(Flag::to_enum_str).unwrap_or("c".to_string())
+}
+
+/// The code from `codes` for the flag, the enum string when it is not in there. `{number}` in
+/// the penalty code is replaced by the race number and `{driver}` by the name abbreviation, in
+/// the start lights code `{number}` is the number of lights lit, in the countdown code the
+/// percentage left, in the ambient code the temperature, in the motion code the percentage and in
+/// the collision code `1` while the strobe is red.
+pub(crate) fn code(flag: Option<Flag>, codes: &FlagTable<String>) -> String {
+    match (codes.get(flag), flag) {
+        (Some(code), Some(Flag::Penalty { number, driver, .. })) => {
+            code.replace("{number}", &number.to_string()).replace(
+                "{driver}",
+                &driver.map(|driver| driver.to_string()).unwrap_or_default(),
+            )
+        }
+        (Some(code), Some(Flag::StartLights(lights))) => {
+            code.replace("{number}", &lights.to_string())
+        }
+        (Some(code), Some(Flag::Countdown(left))) => code.replace("{number}", &left.to_string()),
+        (Some(code), Some(Flag::Ambient(temperature))) => {
+            code.replace("{number}", &temperature.to_string())
+        }
+        (Some(code), Some(Flag::Motion(percent))) => code.replace("{number}", &percent.to_string()),
+        (Some(code), Some(Flag::Collision { red })) => {
+            code.replace("{number}", &u8::from(red).to_string())
+        }
+        (Some(code), _) => code.clone(),
+        (None, _) => enum_str(flag),
+    }
+}
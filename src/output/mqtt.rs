@@ -0,0 +1,302 @@
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rumqttc::AsyncClient;
+use rumqttc::MqttOptions;
+use rumqttc::QoS;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+use super::Output;
+use super::board;
+use super::tower;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::indicator::WarningLevel;
+use crate::manager::FlagState;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Config for [`MqttOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    /// Host of the MQTT broker
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Topic that gets the active global flag
+    #[serde(default = "default_global_topic")]
+    pub global_topic: String,
+    /// Topic that gets the active local flag
+    #[serde(default = "default_local_topic")]
+    pub local_topic: String,
+    /// Topic that gets the flag shown on the lights
+    #[serde(default = "default_shown_topic")]
+    pub shown_topic: String,
+    /// Topic that gets the DRS state, for outputs set up for DRS
+    #[serde(default = "default_drs_topic")]
+    pub drs_topic: String,
+    /// Topic that gets the lit percentage of the rev lights, for outputs set up for rev lights
+    #[serde(default = "default_rev_lights_topic")]
+    pub rev_lights_topic: String,
+    /// Topic that gets the RPM level and whether it is past the redline as JSON, for outputs set up
+    /// for the RPM
+    #[serde(default = "default_rpm_topic")]
+    pub rpm_topic: String,
+    /// Topic that gets the ERS deploy mode and charge as JSON, for outputs set up for ERS
+    #[serde(default = "default_ers_topic")]
+    pub ers_topic: String,
+    /// Topic that gets `on` or `off`, for outputs set up for the low fuel warning
+    #[serde(default = "default_low_fuel_topic")]
+    pub low_fuel_topic: String,
+    /// Topic that gets `on` or `off`, for outputs set up for the rain warning
+    #[serde(default = "default_rain_topic")]
+    pub rain_topic: String,
+    /// Topic that gets the lap delta and whether it is purple as JSON, for outputs set up for the
+    /// lap delta
+    #[serde(default = "default_lap_delta_topic")]
+    pub lap_delta_topic: String,
+    /// Topic that gets the gap to the leader or the car ahead in milliseconds as JSON, for outputs
+    /// set up for the gap
+    #[serde(default = "default_gap_topic")]
+    pub gap_topic: String,
+    /// Topic that gets the lowercase tyre compound, for outputs set up for the tyre
+    #[serde(default = "default_tyre_topic")]
+    pub tyre_topic: String,
+    /// Topic that gets the gear, `-1` for reverse and `0` for neutral, for outputs set up for the
+    /// gear
+    #[serde(default = "default_gear_topic")]
+    pub gear_topic: String,
+    /// Topic that gets the warning level of every tyre as JSON, for outputs set up for the tyre
+    /// warning
+    #[serde(default = "default_tyre_warning_topic")]
+    pub tyre_warning_topic: String,
+    /// Topic that gets the warning level of the engine and the brakes as JSON, for outputs set up
+    /// for the temperature warning
+    #[serde(default = "default_temperature_warning_topic")]
+    pub temperature_warning_topic: String,
+    /// Topic that gets the percentage of the session done, for outputs set up for the progress
+    #[serde(default = "default_progress_topic")]
+    pub progress_topic: String,
+    /// Topic that gets the team ids of the cars in race order as JSON, for outputs set up for the
+    /// position board
+    #[serde(default = "default_position_board_topic")]
+    pub position_board_topic: String,
+    /// Topic that gets the gap of every car to the car ahead in race order as JSON, for outputs
+    /// set up for the timing tower
+    #[serde(default = "default_timing_tower_topic")]
+    pub timing_tower_topic: String,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    String::from("f1_light_sync")
+}
+
+fn default_global_topic() -> String {
+    String::from("f1/flags/global")
+}
+
+fn default_local_topic() -> String {
+    String::from("f1/flags/local")
+}
+
+fn default_shown_topic() -> String {
+    String::from("f1/flags/shown")
+}
+
+fn default_drs_topic() -> String {
+    String::from("f1/drs")
+}
+
+fn default_rev_lights_topic() -> String {
+    String::from("f1/rev_lights")
+}
+
+fn default_rpm_topic() -> String {
+    String::from("f1/rpm")
+}
+
+fn default_ers_topic() -> String {
+    String::from("f1/ers")
+}
+
+fn default_low_fuel_topic() -> String {
+    String::from("f1/low_fuel")
+}
+
+fn default_rain_topic() -> String {
+    String::from("f1/rain")
+}
+
+fn default_lap_delta_topic() -> String {
+    String::from("f1/lap_delta")
+}
+
+fn default_gap_topic() -> String {
+    String::from("f1/gap")
+}
+
+fn default_tyre_topic() -> String {
+    String::from("f1/tyre")
+}
+
+fn default_gear_topic() -> String {
+    String::from("f1/gear")
+}
+
+fn default_tyre_warning_topic() -> String {
+    String::from("f1/tyre_warning")
+}
+
+fn default_temperature_warning_topic() -> String {
+    String::from("f1/temperature_warning")
+}
+
+fn default_progress_topic() -> String {
+    String::from("f1/progress")
+}
+
+fn default_position_board_topic() -> String {
+    String::from("f1/position_board")
+}
+
+fn default_timing_tower_topic() -> String {
+    String::from("f1/timing_tower")
+}
+
+/// Publishes the flags as retained messages to an MQTT broker.
+///
+/// Each topic gets the lowercase name of the flag, or `none` when there is no flag.
+#[derive(Debug)]
+pub struct MqttOutput {
+    config: MqttConfig,
+    client: AsyncClient,
+    published: Option<FlagState>,
+}
+
+impl MqttOutput {
+    /// Starts the connection to the broker in the background, reconnecting when it drops.
+    pub fn connect(config: MqttConfig) -> Self {
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 16);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = event_loop.poll().await {
+                    warn!(error = %e, "MQTT connection error");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        });
+
+        Self {
+            config,
+            client,
+            published: None,
+        }
+    }
+
+    fn publish(&self, topic: &str, payload: &str) -> io::Result<()> {
+        self.client
+            .try_publish(topic, QoS::AtLeastOnce, true, payload)
+            .map_err(io::Error::other)
+    }
+}
+
+#[async_trait]
+impl Output for MqttOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.publish(&self.config.shown_topic, flag.map_or("none", Flag::name))
+    }
+
+    async fn update_state(&mut self, state: &FlagState) -> io::Result<()> {
+        let published = self.published.replace(*state);
+        if published.is_none_or(|published| published.global_flag != state.global_flag) {
+            self.publish(
+                &self.config.global_topic,
+                state.global_flag.map_or("none", |flag| flag.name()),
+            )?;
+        }
+        if published.is_none_or(|published| published.local_flag != state.local_flag) {
+            self.publish(
+                &self.config.local_topic,
+                state.local_flag.map_or("none", |flag| flag.name()),
+            )?;
+        }
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        match indicator {
+            Indicator::Drs(drs) => self.publish(&self.config.drs_topic, drs.name()),
+            Indicator::RevLights(percent) => {
+                self.publish(&self.config.rev_lights_topic, &percent.to_string())
+            }
+            Indicator::Rpm { level, redline } => {
+                let payload = json!({ "level": level, "redline": redline });
+                self.publish(&self.config.rpm_topic, &payload.to_string())
+            }
+            Indicator::Ers { mode, charge } => {
+                let payload = json!({ "mode": mode.name(), "charge": charge });
+                self.publish(&self.config.ers_topic, &payload.to_string())
+            }
+            Indicator::Tyre(tyre) => self.publish(&self.config.tyre_topic, tyre.name()),
+            Indicator::Gear(gear) => self.publish(&self.config.gear_topic, &gear.to_string()),
+            Indicator::TyreWarning(levels) => {
+                let payload = json!(levels.map(WarningLevel::name));
+                self.publish(&self.config.tyre_warning_topic, &payload.to_string())
+            }
+            Indicator::TemperatureWarning { engine, brakes } => {
+                let payload = json!({ "engine": engine.name(), "brakes": brakes.name() });
+                self.publish(&self.config.temperature_warning_topic, &payload.to_string())
+            }
+            Indicator::LowFuel(low) => {
+                self.publish(&self.config.low_fuel_topic, if low { "on" } else { "off" })
+            }
+            Indicator::Rain(coming) => {
+                self.publish(&self.config.rain_topic, if coming { "on" } else { "off" })
+            }
+            Indicator::LapDelta { delta, purple } => {
+                let payload = json!({ "delta": delta, "purple": purple });
+                self.publish(&self.config.lap_delta_topic, &payload.to_string())
+            }
+            Indicator::Gap { gap, .. } => {
+                let payload = json!({ "gap": gap });
+                self.publish(&self.config.gap_topic, &payload.to_string())
+            }
+            Indicator::Progress(percent) => {
+                self.publish(&self.config.progress_topic, &percent.to_string())
+            }
+            // Marshal posts are shown as the yellow flag by their MarshalPostOutput
+            Indicator::MarshalPosts(_) => Ok(()),
+            Indicator::PositionBoard(teams) => {
+                let payload = json!(board(teams));
+                self.publish(&self.config.position_board_topic, &payload.to_string())
+            }
+            Indicator::TimingTower(intervals) => {
+                let payload = json!(tower(intervals));
+                self.publish(&self.config.timing_tower_topic, &payload.to_string())
+            }
+        }
+    }
+
+    /// Disconnects once the messages before it were sent.
+    async fn close(&mut self) -> io::Result<()> {
+        self.client.disconnect().await.map_err(io::Error::other)
+    }
+}
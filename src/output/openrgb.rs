@@ -0,0 +1,217 @@
+use std::io;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use super::Output;
+use crate::color::Rgb;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+const REQUEST_CONTROLLER_COUNT: u32 = 0;
+const REQUEST_CONTROLLER_DATA: u32 = 1;
+const SET_CLIENT_NAME: u32 = 50;
+const UPDATE_LEDS: u32 = 1050;
+const SET_CUSTOM_MODE: u32 = 1100;
+
+/// Config for [`OpenRgbOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenRgbConfig {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub colors: FlagTable<Rgb>,
+}
+
+fn default_host() -> String {
+    String::from("127.0.0.1")
+}
+
+fn default_port() -> u16 {
+    6742
+}
+
+/// Sets every device known to an OpenRGB SDK server to the flag color.
+///
+/// Uses protocol version 0 of the SDK, which every server version understands.
+#[derive(Debug)]
+pub struct OpenRgbOutput {
+    config: OpenRgbConfig,
+    stream: TcpStream,
+    /// Number of leds of every device, indexed by device id
+    devices: Vec<u16>,
+}
+
+impl OpenRgbOutput {
+    /// Connects to the server and switches all devices to direct control.
+    pub async fn connect(config: OpenRgbConfig) -> io::Result<Self> {
+        let mut stream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+        send(&mut stream, 0, SET_CLIENT_NAME, b"f1_light_sync\0").await?;
+
+        send(&mut stream, 0, REQUEST_CONTROLLER_COUNT, &[]).await?;
+        let count = Reader::new(&receive(&mut stream, REQUEST_CONTROLLER_COUNT).await?).u32()?;
+
+        let mut devices = Vec::new();
+        for device in 0..count {
+            send(&mut stream, device, REQUEST_CONTROLLER_DATA, &[]).await?;
+            let data = receive(&mut stream, REQUEST_CONTROLLER_DATA).await?;
+            devices.push(led_count(&data)?);
+            send(&mut stream, device, SET_CUSTOM_MODE, &[]).await?;
+        }
+
+        Ok(Self {
+            config,
+            stream,
+            devices,
+        })
+    }
+
+    /// Number of devices found on the server.
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Gives every device the colors `colors` returns for its number of leds.
+    async fn set_colors(&mut self, colors: impl Fn(usize) -> Vec<Rgb>) -> io::Result<()> {
+        for (device, &leds) in self.devices.iter().enumerate() {
+            let size = 4 + 2 + 4 * usize::from(leds);
+            let mut data = Vec::with_capacity(size);
+            data.extend_from_slice(&(size as u32).to_le_bytes());
+            data.extend_from_slice(&leds.to_le_bytes());
+            for color in colors(usize::from(leds)) {
+                data.extend_from_slice(&[color.r(), color.g(), color.b(), 0]);
+            }
+            send(&mut self.stream, device as u32, UPDATE_LEDS, &data).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Output for OpenRgbOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        let color = self
+            .config
+            .colors
+            .get(flag)
+            .copied()
+            .unwrap_or_else(|| Rgb::default_for(flag));
+        self.set_colors(|leds| vec![color; leds]).await
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.set_colors(|leds| indicator.lights(leds)).await
+    }
+
+    /// Connects again, for a server that was restarted, and finds the devices anew.
+    async fn reconnect(&mut self) -> io::Result<()> {
+        *self = Self::connect(self.config.clone()).await?;
+        Ok(())
+    }
+}
+
+async fn send(stream: &mut TcpStream, device: u32, packet: u32, data: &[u8]) -> io::Result<()> {
+    let mut message = Vec::with_capacity(16 + data.len());
+    message.extend_from_slice(b"ORGB");
+    message.extend_from_slice(&device.to_le_bytes());
+    message.extend_from_slice(&packet.to_le_bytes());
+    message.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    message.extend_from_slice(data);
+    stream.write_all(&message).await
+}
+
+/// Reads messages until the reply to `packet` arrives, skipping notifications.
+async fn receive(stream: &mut TcpStream, packet: u32) -> io::Result<Vec<u8>> {
+    loop {
+        let mut header = [0; 16];
+        stream.read_exact(&mut header).await?;
+        if &header[..4] != b"ORGB" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an OpenRGB server",
+            ));
+        }
+
+        let mut reader = Reader::new(&header[8..]);
+        let id = reader.u32()?;
+        let mut data = vec![0; reader.u32()? as usize];
+        stream.read_exact(&mut data).await?;
+        if id == packet {
+            return Ok(data);
+        }
+    }
+}
+
+/// Digs the number of leds out of a protocol version 0 controller description.
+fn led_count(data: &[u8]) -> io::Result<u16> {
+    let mut reader = Reader::new(data);
+    reader.skip(4 + 4)?; // data size, type
+    for _ in 0..5 {
+        reader.string()?; // name, description, version, serial, location
+    }
+
+    let modes = reader.u16()?;
+    reader.skip(4)?; // active mode
+    for _ in 0..modes {
+        reader.string()?;
+        reader.skip(4 * 9)?; // value, flags, speed min/max, colors min/max, speed, direction, color mode
+        let colors = reader.u16()?;
+        reader.skip(4 * usize::from(colors))?;
+    }
+
+    let zones = reader.u16()?;
+    for _ in 0..zones {
+        reader.string()?;
+        reader.skip(4 * 4)?; // type, leds min/max/count
+        let matrix_length = reader.u16()?;
+        reader.skip(usize::from(matrix_length))?;
+    }
+
+    reader.u16()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < length {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "OpenRGB message too short",
+            ));
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn skip(&mut self, length: usize) -> io::Result<()> {
+        self.take(length).map(|_| ())
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<()> {
+        let length = self.u16()?;
+        self.skip(usize::from(length))
+    }
+}
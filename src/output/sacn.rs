@@ -0,0 +1,217 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+use tracing::warn;
+
+use super::Output;
+use crate::color::Rgb;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+pub const SACN_PORT: u16 = 5568;
+
+/// Receivers consider a source lost after 2.5 seconds, so the frame is repeated well within that.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+const DMX_SLOTS: usize = 512;
+const ROOT_LAYER_START: usize = 16;
+const FRAMING_LAYER_START: usize = 38;
+const DMP_LAYER_START: usize = 115;
+const DATA_START: usize = 126;
+
+/// Config for [`SacnOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SacnConfig {
+    pub universe: u16,
+    /// Unicast destination, the universe's multicast group is used when left out
+    pub destination: Option<String>,
+    /// DMX address (1-512) of the first channel of the first pixel
+    #[serde(default = "default_start_channel")]
+    pub start_channel: u16,
+    /// Number of consecutive RGB pixels that get the flag color
+    #[serde(default = "default_pixels")]
+    pub pixels: u16,
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+    #[serde(default = "default_source_name")]
+    pub source_name: String,
+    #[serde(default)]
+    pub colors: FlagTable<Rgb>,
+}
+
+fn default_start_channel() -> u16 {
+    1
+}
+
+fn default_pixels() -> u16 {
+    1
+}
+
+fn default_priority() -> u8 {
+    100
+}
+
+fn default_source_name() -> String {
+    String::from("f1_light_sync")
+}
+
+/// Sends the flag color as an E1.31 (sACN) DMX universe.
+#[derive(Debug)]
+pub struct SacnOutput {
+    config: SacnConfig,
+    frame: watch::Sender<[u8; DMX_SLOTS]>,
+}
+
+impl SacnOutput {
+    /// Connects to the destination and starts sending the (initially dark) universe.
+    pub async fn connect(config: SacnConfig) -> io::Result<Self> {
+        if config.start_channel == 0
+            || usize::from(config.start_channel) + usize::from(config.pixels) * 3 - 1 > DMX_SLOTS
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sACN pixels do not fit in the universe",
+            ));
+        }
+
+        let destination: SocketAddr = match &config.destination {
+            Some(destination) => tokio::net::lookup_host(destination)
+                .await?
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unknown sACN host"))?,
+            None => SocketAddr::from((multicast_address(config.universe), SACN_PORT)),
+        };
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(destination).await?;
+
+        let mut packet = PacketBuilder::new(&config);
+        let (frame, mut frame_changed) = watch::channel([0; DMX_SLOTS]);
+        tokio::spawn(async move {
+            let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+            loop {
+                tokio::select! {
+                    changed = frame_changed.changed() => if changed.is_err() {
+                        break;
+                    },
+                    _ = refresh.tick() => {},
+                }
+
+                let data = *frame_changed.borrow_and_update();
+                if let Err(e) = socket.send(packet.build(&data)).await {
+                    warn!(error = %e, "Failed to send sACN packet");
+                }
+            }
+        });
+
+        Ok(Self { config, frame })
+    }
+
+    fn set_colors(&self, colors: &[Rgb]) {
+        let start = usize::from(self.config.start_channel) - 1;
+        let end = start + usize::from(self.config.pixels) * 3;
+        self.frame.send_modify(|data| {
+            data[start..end]
+                .chunks_exact_mut(3)
+                .zip(colors)
+                .for_each(|(pixel, color)| pixel.copy_from_slice(&color.0))
+        });
+    }
+}
+
+#[async_trait]
+impl Output for SacnOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        let color = self
+            .config
+            .colors
+            .get(flag)
+            .copied()
+            .unwrap_or_else(|| Rgb::default_for(flag));
+        self.set_colors(&vec![color; usize::from(self.config.pixels)]);
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.set_colors(&indicator.lights(usize::from(self.config.pixels)));
+        Ok(())
+    }
+}
+
+/// The multicast group a universe is sent to, `239.255.<high>.<low>`.
+pub fn multicast_address(universe: u16) -> Ipv4Addr {
+    let [high, low] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, high, low)
+}
+
+/// Keeps a complete data packet around so only the sequence number and slots change per send.
+struct PacketBuilder {
+    packet: [u8; DATA_START + DMX_SLOTS],
+    sequence: u8,
+}
+
+impl PacketBuilder {
+    fn new(config: &SacnConfig) -> Self {
+        let mut packet = [0; DATA_START + DMX_SLOTS];
+        let length = packet.len();
+
+        // Root layer
+        packet[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+        packet[4..16].copy_from_slice(b"ASC-E1.17\0\0\0");
+        packet[16..18].copy_from_slice(&flags_and_length(length - ROOT_LAYER_START));
+        packet[18..22].copy_from_slice(&0x0000_0004u32.to_be_bytes());
+        packet[22..38].copy_from_slice(&random_cid());
+
+        // Framing layer
+        packet[38..40].copy_from_slice(&flags_and_length(length - FRAMING_LAYER_START));
+        packet[40..44].copy_from_slice(&0x0000_0002u32.to_be_bytes());
+        let name = config.source_name.as_bytes();
+        let name_length = name.len().min(63);
+        packet[44..44 + name_length].copy_from_slice(&name[..name_length]);
+        packet[108] = config.priority.min(200);
+        packet[113..115].copy_from_slice(&config.universe.to_be_bytes());
+
+        // DMP layer
+        packet[115..117].copy_from_slice(&flags_and_length(length - DMP_LAYER_START));
+        packet[117] = 0x02;
+        packet[118] = 0xa1;
+        packet[121..123].copy_from_slice(&1u16.to_be_bytes());
+        packet[123..125].copy_from_slice(&(DMX_SLOTS as u16 + 1).to_be_bytes());
+
+        Self {
+            packet,
+            sequence: 0,
+        }
+    }
+
+    fn build(&mut self, data: &[u8; DMX_SLOTS]) -> &[u8] {
+        self.packet[111] = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+        self.packet[DATA_START..].copy_from_slice(data);
+        &self.packet
+    }
+}
+
+fn flags_and_length(length: usize) -> [u8; 2] {
+    (0x7000 | length as u16).to_be_bytes()
+}
+
+/// Every source should have its own component identifier, a fresh random one is fine.
+fn random_cid() -> [u8; 16] {
+    let state = RandomState::new();
+    let high = state.hash_one(std::process::id());
+    let low = state.hash_one(high);
+    let mut cid = [0; 16];
+    cid[..8].copy_from_slice(&high.to_be_bytes());
+    cid[8..].copy_from_slice(&low.to_be_bytes());
+    cid
+}
@@ -0,0 +1,151 @@
+use std::io;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::sync::watch;
+use tokio_serial::SerialPortBuilderExt;
+use tokio_serial::SerialStream;
+
+use super::Output;
+use super::blink::Blinker;
+use super::blink::Pattern;
+use super::indicator_message;
+use super::message;
+use super::session_uid;
+use super::simhub::Format;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+/// Config for [`SerialOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SerialConfig {
+    /// Serial device, like `COM3` or `/dev/ttyUSB0`
+    pub port: String,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: u32,
+    /// Written after every flag code so the controller knows where it ends
+    #[serde(default = "default_line_ending")]
+    pub line_ending: String,
+    /// Codes written instead of the enum strings
+    #[serde(default)]
+    pub codes: FlagTable<String>,
+    /// `simhub` writes SimHub's flag properties, `json` a JSON object and `binary` a two byte
+    /// frame instead of the codes
+    #[serde(default)]
+    pub format: Format,
+    /// Written instead of just the code, with the placeholders of the flag filled in
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Flags that blink by writing the off code and the flag in turn
+    #[serde(default)]
+    pub patterns: FlagTable<Pattern>,
+}
+
+fn default_baud_rate() -> u32 {
+    115200
+}
+
+fn default_line_ending() -> String {
+    String::from("\n")
+}
+
+/// Writes the same enum strings as [`UdpOutput`](super::UdpOutput) to a serial port.
+#[derive(Debug)]
+pub struct SerialOutput {
+    config: SerialConfig,
+    /// Shared with the task blinking the flag
+    stream: Arc<Mutex<SerialStream>>,
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+    blinker: Blinker,
+}
+
+impl SerialOutput {
+    pub fn open(config: SerialConfig) -> io::Result<Self> {
+        let stream = open_port(&config)?;
+        Ok(Self {
+            config,
+            stream: Arc::new(Mutex::new(stream)),
+            session_uid: None,
+            blinker: Blinker::default(),
+        })
+    }
+
+    /// The message for the flag with the line ending, binary frames have a fixed size instead.
+    fn line(&self, flag: Option<Flag>, session_uid: Option<u64>) -> Vec<u8> {
+        let mut line = message(
+            flag,
+            self.config.format,
+            &self.config.codes,
+            self.config.template.as_deref(),
+            session_uid,
+        );
+        if self.config.format != Format::Binary {
+            line.extend_from_slice(self.config.line_ending.as_bytes());
+        }
+        line
+    }
+
+    /// Puts the unique id of the session into JSON messages, as the F1 input or replay reports it.
+    pub fn with_session_uid(mut self, session_uid: watch::Receiver<Option<u64>>) -> Self {
+        self.session_uid = Some(session_uid);
+        self
+    }
+}
+
+fn open_port(config: &SerialConfig) -> io::Result<SerialStream> {
+    Ok(tokio_serial::new(&config.port, config.baud_rate).open_native_async()?)
+}
+
+/// Writes a code that already ends in the line ending.
+async fn write_line(stream: &Mutex<SerialStream>, line: &[u8]) -> io::Result<()> {
+    let mut stream = stream.lock().await;
+    stream.write_all(line).await?;
+    stream.flush().await
+}
+
+#[async_trait]
+impl Output for SerialOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.blinker.stop();
+        let session_uid = session_uid(&self.session_uid);
+        let on = self.line(flag, session_uid);
+        write_line(&self.stream, &on).await?;
+
+        let pattern = self.config.patterns.get(flag).copied().unwrap_or_default();
+        if let Some(interval) = pattern.interval() {
+            let stream = self.stream.clone();
+            let off = self.line(None, session_uid);
+            self.blinker.start(interval, move |lit| {
+                let stream = stream.clone();
+                let line = if lit { on.clone() } else { off.clone() };
+                async move { write_line(&stream, &line).await }
+            });
+        }
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.blinker.stop();
+        let mut line = indicator_message(
+            indicator,
+            self.config.format,
+            self.config.template.as_deref(),
+            session_uid(&self.session_uid),
+        );
+        // Indicators are text in every format
+        line.extend_from_slice(self.config.line_ending.as_bytes());
+        write_line(&self.stream, &line).await
+    }
+
+    /// Opens the port again, for a controller that was unplugged.
+    async fn reconnect(&mut self) -> io::Result<()> {
+        self.blinker.stop();
+        *self.stream.lock().await = open_port(&self.config)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Messages in the layout of SimHub's flag properties, for controllers flashed with a SimHub
+//! custom serial profile.
+use serde::Deserialize;
+
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+
+/// What the UDP and serial outputs send for a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// The enum strings, or the codes set up for them
+    #[default]
+    Codes,
+    /// `Flag_Name;Flag_Green;Flag_Yellow;Flag_Blue;Flag_White;Flag_Black;Flag_Checkered;Flag_Orange`
+    /// as SimHub's `format()` writes them, like `Yellow;0;1;0;0;0;0;0`
+    #[serde(rename = "simhub")]
+    SimHub,
+    /// A JSON object with the name, code and penalized driver of the flag, the F1 session and the
+    /// time
+    Json,
+    /// Two bytes, the id of the flag and its argument
+    Binary,
+}
+
+/// The SimHub flags in the order of the message, after the name
+const FLAGS: [&str; 7] = [
+    "Green",
+    "Yellow",
+    "Blue",
+    "White",
+    "Black",
+    "Checkered",
+    "Orange",
+];
+
+/// SimHub's name for the flag, empty for what SimHub has no flag for.
+fn name(flag: Option<Flag>) -> &'static str {
+    match flag {
+        Some(Flag::Local(LocalFlag::Green) | Flag::Global(GlobalFlag::FormationLap)) => "Green",
+        // SimHub shows the safety cars as a yellow flag
+        Some(
+            Flag::Local(LocalFlag::Yellow | LocalFlag::DoubleYellow)
+            | Flag::Global(
+                GlobalFlag::Vsc | GlobalFlag::VscEnding | GlobalFlag::Sc | GlobalFlag::ScEnding,
+            ),
+        ) => "Yellow",
+        Some(Flag::Local(LocalFlag::Blue)) => "Blue",
+        Some(Flag::Black | Flag::Penalty { .. }) => "Black",
+        Some(Flag::Finish) => "Checkered",
+        // The black flag with the orange disc for a damaged car
+        Some(Flag::Damage) => "Orange",
+        _ => "",
+    }
+}
+
+/// The SimHub message for the flag, `None` clears all of them.
+pub(crate) fn message(flag: Option<Flag>) -> String {
+    let name = name(flag);
+    let mut message = name.to_string();
+    for simhub_flag in FLAGS {
+        message.push(';');
+        message.push(if simhub_flag == name { '1' } else { '0' });
+    }
+    message
+}
@@ -0,0 +1,86 @@
+//! Payloads laid out by the user, for controllers that expect more than the code of the flag or
+//! indicator.
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+/// `template` with `{name}` replaced by the name of the flag (`off` without one), `{code}` by its
+/// code, `{number}` by the race number of a penalty, the lights lit, the countdown, the
+/// temperature, the motion or whether a collision strobe is red, and `{driver}` by the
+/// abbreviation of a penalized driver. Both are empty for the other flags.
+pub(crate) fn fill(template: &str, flag: Option<Flag>, code: &str) -> String {
+    let number = match flag {
+        Some(Flag::Penalty { number, .. }) => number.to_string(),
+        Some(Flag::StartLights(lights)) => lights.to_string(),
+        Some(Flag::Countdown(left)) => left.to_string(),
+        Some(Flag::Ambient(temperature)) => temperature.to_string(),
+        Some(Flag::Motion(percent)) => percent.to_string(),
+        Some(Flag::Collision { red }) => u8::from(red).to_string(),
+        _ => String::new(),
+    };
+    let driver = match flag {
+        Some(Flag::Penalty {
+            driver: Some(driver),
+            ..
+        }) => driver.to_string(),
+        _ => String::new(),
+    };
+    // The code goes last, so placeholders in a code of the config stay as written
+    template
+        .replace("{name}", flag.map_or("off", Flag::name))
+        .replace("{number}", &number)
+        .replace("{driver}", &driver)
+        .replace("{code}", code)
+}
+
+/// `template` with `{name}` replaced by the name of the indicator and `{code}` by its code, the
+/// number and driver of the flags are left empty.
+pub(crate) fn fill_indicator(template: &str, indicator: Indicator, code: &str) -> String {
+    template
+        .replace("{name}", indicator.kind().name())
+        .replace("{number}", "")
+        .replace("{driver}", "")
+        .replace("{code}", code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flag::Abbreviation;
+    use crate::flag::GlobalFlag;
+    use crate::flag::PenaltyKind;
+
+    #[test]
+    fn fills_the_flag_in() {
+        let template = r#"{"flag":"{name}","code":"{code}","driver":"{driver}"}"#;
+        assert_eq!(
+            fill(template, Some(Flag::Global(GlobalFlag::Sc)), "4"),
+            r#"{"flag":"sc","code":"4","driver":""}"#
+        );
+        assert_eq!(fill("FLAG={code}\n", None, "c"), "FLAG=c\n");
+    }
+
+    #[test]
+    fn fills_the_penalized_driver_in() {
+        let penalty = Flag::Penalty {
+            number: 44,
+            driver: Abbreviation::of("Lewis HAMILTON"),
+            kind: PenaltyKind::DriveThrough,
+        };
+        assert_eq!(
+            fill("{name} #{number} {driver}: {code}", Some(penalty), "17,44"),
+            "drive_through #44 HAM: 17,44"
+        );
+    }
+
+    #[test]
+    fn fills_the_indicator_in() {
+        assert_eq!(
+            fill_indicator(
+                "{name}={code} {driver}\n",
+                Indicator::RevLights(40),
+                "51,40"
+            ),
+            "rev_lights=51,40 \n"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Rate limiting in front of an output, for controllers that cannot keep up with every change.
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use super::Output;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::manager::FlagState;
+
+/// Sends at most one message per `interval` to the output it wraps.
+///
+/// What comes in between is held back and only the latest of it is sent once the interval is
+/// over, left out when the device already shows it. The manager sends it when
+/// [`flush_at`](Output::flush_at) comes.
+#[derive(Debug)]
+pub struct ThrottledOutput {
+    inner: Box<dyn Output>,
+    interval: Duration,
+    /// When the inner output may get the next message, `None` when it can right away
+    next_send: Option<Instant>,
+    shown: Option<Option<Flag>>,
+    state: Option<FlagState>,
+    indicator: Option<Indicator>,
+    pending_flag: Option<Option<Flag>>,
+    pending_state: Option<FlagState>,
+    pending_indicator: Option<Indicator>,
+}
+
+impl ThrottledOutput {
+    /// Wraps `inner`, sending it at most `per_second` messages every second.
+    pub fn new(inner: Box<dyn Output>, per_second: f64) -> io::Result<Self> {
+        if per_second.is_nan() || per_second <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the rate limit has to be above 0",
+            ));
+        }
+        let interval = Duration::try_from_secs_f64(1.0 / per_second)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(Self {
+            inner,
+            interval,
+            next_send: None,
+            shown: None,
+            state: None,
+            indicator: None,
+            pending_flag: None,
+            pending_state: None,
+            pending_indicator: None,
+        })
+    }
+
+    /// Whether a message can go out now, otherwise it is held back.
+    fn may_send(&self) -> bool {
+        self.next_send
+            .is_none_or(|next_send| next_send <= Instant::now())
+    }
+
+    /// Sends what is held back that the device does not show yet, all of it when `all`.
+    async fn send_pending(&mut self, all: bool) -> io::Result<()> {
+        let flag = self
+            .pending_flag
+            .take()
+            .filter(|&flag| all || self.shown != Some(flag));
+        let state = self
+            .pending_state
+            .take()
+            .filter(|&state| all || self.state != Some(state));
+        let indicator = self
+            .pending_indicator
+            .take()
+            .filter(|&indicator| all || self.indicator != Some(indicator));
+        if flag.is_none() && state.is_none() && indicator.is_none() {
+            return Ok(());
+        }
+
+        self.next_send = Some(Instant::now() + self.interval);
+        if let Some(flag) = flag {
+            self.inner.show(flag).await?;
+            self.shown = Some(flag);
+        }
+        if let Some(state) = state {
+            self.inner.update_state(&state).await?;
+            self.state = Some(state);
+        }
+        if let Some(indicator) = indicator {
+            self.inner.show_indicator(indicator).await?;
+            self.indicator = Some(indicator);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Output for ThrottledOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.pending_flag = Some(flag);
+        match self.may_send() {
+            true => self.send_pending(true).await,
+            false => Ok(()),
+        }
+    }
+
+    async fn update_state(&mut self, state: &FlagState) -> io::Result<()> {
+        self.pending_state = Some(*state);
+        match self.may_send() {
+            true => self.send_pending(true).await,
+            false => Ok(()),
+        }
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.pending_indicator = Some(indicator);
+        match self.may_send() {
+            true => self.send_pending(true).await,
+            false => Ok(()),
+        }
+    }
+
+    fn flush_at(&self) -> Option<Instant> {
+        let pending = self.pending_flag.is_some()
+            || self.pending_state.is_some()
+            || self.pending_indicator.is_some();
+        match pending {
+            true => Some(self.next_send.unwrap_or_else(Instant::now)),
+            false => None,
+        }
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        self.send_pending(false).await
+    }
+
+    async fn reconnect(&mut self) -> io::Result<()> {
+        self.inner.reconnect().await
+    }
+
+    async fn close(&mut self) -> io::Result<()> {
+        self.send_pending(false).await?;
+        self.inner.close().await
+    }
+}
@@ -0,0 +1,147 @@
+use std::io;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+
+use super::Output;
+use super::blink::Blinker;
+use super::blink::Pattern;
+use super::indicator_message;
+use super::message;
+use super::session_uid;
+use super::simhub::Format;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+/// Sends the enum string of the flag (or `c` to clear) as a single UDP packet, or the code set up
+/// for it. Indicators get their own codes.
+#[derive(Debug)]
+pub struct UdpOutput {
+    socket: Arc<UdpSocket>,
+    /// Looked up again on reconnects, unknown for sockets connected elsewhere
+    destination: Option<String>,
+    codes: FlagTable<String>,
+    format: Format,
+    template: Option<String>,
+    session_uid: Option<watch::Receiver<Option<u64>>>,
+    patterns: FlagTable<Pattern>,
+    blinker: Blinker,
+}
+
+impl UdpOutput {
+    /// Wraps an already connected socket.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket: Arc::new(socket),
+            destination: None,
+            codes: FlagTable::default(),
+            format: Format::default(),
+            template: None,
+            session_uid: None,
+            patterns: FlagTable::default(),
+            blinker: Blinker::default(),
+        }
+    }
+
+    /// Sends the codes in `codes` instead of the enum strings, for controllers with codes of their
+    /// own.
+    pub fn with_codes(mut self, codes: FlagTable<String>) -> Self {
+        self.codes = codes;
+        self
+    }
+
+    /// Sends the flags in `format`, the codes are used by [`Format::Codes`] and [`Format::Json`].
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sends `template` with the placeholders of the flag filled in instead of just the code, like
+    /// `FLAG={code}`.
+    pub fn with_template(mut self, template: String) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Puts the unique id of the session into JSON messages, as the F1 input or replay reports it.
+    pub fn with_session_uid(mut self, session_uid: watch::Receiver<Option<u64>>) -> Self {
+        self.session_uid = Some(session_uid);
+        self
+    }
+
+    /// Blinks the flags with a blinking pattern, for controllers that cannot blink by themselves.
+    pub fn with_patterns(mut self, patterns: FlagTable<Pattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// Binds a new socket and connects it to `destination`.
+    pub async fn connect(destination: &str) -> io::Result<Self> {
+        let mut output = Self::new(bind(destination).await?);
+        output.destination = Some(destination.to_string());
+        Ok(output)
+    }
+}
+
+async fn bind(destination: &str) -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(destination).await?;
+    Ok(socket)
+}
+
+#[async_trait]
+impl Output for UdpOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.blinker.stop();
+        let session_uid = session_uid(&self.session_uid);
+        let on = message(
+            flag,
+            self.format,
+            &self.codes,
+            self.template.as_deref(),
+            session_uid,
+        );
+        self.socket.send(&on).await?;
+
+        let pattern = self.patterns.get(flag).copied().unwrap_or_default();
+        if let Some(interval) = pattern.interval() {
+            let socket = self.socket.clone();
+            let off = message(
+                None,
+                self.format,
+                &self.codes,
+                self.template.as_deref(),
+                session_uid,
+            );
+            self.blinker.start(interval, move |lit| {
+                let socket = socket.clone();
+                let code = if lit { on.clone() } else { off.clone() };
+                async move { socket.send(&code).await.map(|_| ()) }
+            });
+        }
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        self.blinker.stop();
+        let message = indicator_message(
+            indicator,
+            self.format,
+            self.template.as_deref(),
+            session_uid(&self.session_uid),
+        );
+        self.socket.send(&message).await.map(|_| ())
+    }
+
+    /// Looks the destination up again on a new socket.
+    async fn reconnect(&mut self) -> io::Result<()> {
+        if let Some(destination) = &self.destination {
+            self.blinker.stop();
+            self.socket = Arc::new(bind(destination).await?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::warn;
+
+use super::Output;
+use super::json;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+use crate::manager::FlagState;
+
+/// Config for [`WebSocketOutput`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebSocketConfig {
+    /// Address (with port) the server listens on
+    #[serde(default = "default_listen")]
+    pub listen: String,
+}
+
+fn default_listen() -> String {
+    String::from("0.0.0.0:8765")
+}
+
+/// Serves the flag state as JSON to every connected WebSocket client.
+///
+/// Clients get the current state right after connecting and again whenever it changes:
+/// `{"global": "sc", "local": "yellow", "race_finished": false, "shown": "sc"}`, with `null` for
+/// flags that are not set. Set up for an indicator the clients get that instead, like
+/// `{"drs": "open"}` or `{"rev_lights": 60}`.
+#[derive(Debug)]
+pub struct WebSocketOutput {
+    state: watch::Sender<String>,
+}
+
+impl WebSocketOutput {
+    /// Starts listening, clients are served in the background.
+    pub async fn bind(config: WebSocketConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(&config.listen).await?;
+        let (state, _) = watch::channel(state_json(&FlagState::default()));
+
+        let clients = state.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, address)) => {
+                        tokio::spawn(serve(stream, address, clients.subscribe()));
+                    }
+                    Err(e) => warn!(error = %e, "Failed to accept WebSocket client"),
+                }
+            }
+        });
+
+        Ok(Self { state })
+    }
+}
+
+#[async_trait]
+impl Output for WebSocketOutput {
+    async fn show(&mut self, _flag: Option<Flag>) -> io::Result<()> {
+        // The shown flag is part of the state, which always follows a show
+        Ok(())
+    }
+
+    async fn update_state(&mut self, state: &FlagState) -> io::Result<()> {
+        self.state.send_replace(state_json(state));
+        Ok(())
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        // Marshal posts are shown as the yellow flag by their MarshalPostOutput
+        if let Indicator::MarshalPosts(_) = indicator {
+            return Ok(());
+        }
+        let json = json::indicator(indicator);
+        self.state.send_replace(json.to_string());
+        Ok(())
+    }
+}
+
+fn state_json(state: &FlagState) -> String {
+    json!({
+        "global": state.global_flag.map(|flag| flag.name()),
+        "local": state.local_flag.map(|flag| flag.name()),
+        "shown": state.shown.map(Flag::name),
+        "race_finished": state.race_finished,
+    })
+    .to_string()
+}
+
+async fn serve(stream: TcpStream, address: SocketAddr, mut state: watch::Receiver<String>) {
+    let mut socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!(%address, error = %e, "WebSocket handshake failed");
+            return;
+        }
+    };
+
+    loop {
+        let message = Message::text(state.borrow_and_update().as_str());
+        if socket.send(message).await.is_err() {
+            return;
+        }
+
+        // Reading keeps pings and close frames answered while waiting for a change
+        loop {
+            tokio::select! {
+                changed = state.changed() => match changed {
+                    Ok(()) => break,
+                    Err(_) => return,
+                },
+                incoming = socket.next() => match incoming {
+                    Some(Ok(_)) => {}
+                    _ => return,
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::Output;
+use crate::color::Rgb;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::indicator::Indicator;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Config for [`WledOutput`].
+///
+/// A flag with a preset in `presets` activates that preset, every other flag sets the color of
+/// `segment` using `colors` (or the default color of the flag).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WledConfig {
+    /// Host (with optional port) of the WLED device
+    pub host: String,
+    /// Segment that gets the flag color
+    #[serde(default)]
+    pub segment: u8,
+    #[serde(default)]
+    pub presets: FlagTable<u16>,
+    #[serde(default)]
+    pub colors: FlagTable<Rgb>,
+}
+
+/// Sets the state of a WLED device through its HTTP JSON API.
+#[derive(Debug)]
+pub struct WledOutput {
+    config: WledConfig,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WledOutput {
+    pub fn new(config: WledConfig) -> io::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            url: format!("http://{}/json/state", config.host),
+            config,
+            client,
+        })
+    }
+
+    fn state_for(&self, flag: Option<Flag>) -> serde_json::Value {
+        let segment = self.config.segment;
+        if let Some(preset) = self.config.presets.get(flag) {
+            return json!({ "ps": preset });
+        }
+
+        match flag {
+            None if self.config.colors.off.is_none() => {
+                json!({ "seg": [{ "id": segment, "on": false }] })
+            }
+            _ => {
+                let color = self
+                    .config
+                    .colors
+                    .get(flag)
+                    .copied()
+                    .unwrap_or_else(|| Rgb::default_for(flag));
+                // Effect 0 is solid, set every time so a blinking indicator does not stay blinking
+                json!({
+                    "on": true,
+                    "seg": [{ "id": segment, "on": true, "col": [color.0], "fx": 0 }],
+                })
+            }
+        }
+    }
+
+    async fn post(&self, state: serde_json::Value) -> io::Result<()> {
+        self.client
+            .post(&self.url)
+            .json(&state)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
+
+#[async_trait]
+impl Output for WledOutput {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        self.post(self.state_for(flag)).await
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        let segment = self.config.segment;
+        let color = indicator.color();
+        self.post(match color {
+            Rgb::OFF => json!({ "seg": [{ "id": segment, "on": false }] }),
+            // Effect 1 is blink
+            _ if indicator.flashing() => json!({
+                "on": true,
+                "seg": [{ "id": segment, "on": true, "col": [color.0], "fx": 1 }],
+            }),
+            _ => json!({
+                "on": true,
+                "seg": [{ "id": segment, "on": true, "col": [color.0], "fx": 0 }],
+            }),
+        })
+        .await
+    }
+}
@@ -0,0 +1,237 @@
+use std::f32::consts::TAU;
+use std::io;
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use spidev::SpiModeFlags;
+use spidev::Spidev;
+use spidev::SpidevOptions;
+use tracing::warn;
+
+use super::Output;
+use crate::color::Rgb;
+use crate::config::FlagTable;
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::indicator::Indicator;
+
+/// Every WS2812 bit is sent as three SPI bits, `100` for a zero and `110` for a one.
+const SPI_SPEED_HZ: u32 = 2_400_000;
+/// Low time after a frame that latches the colors, a bit over 280µs for the newer chips.
+const RESET_BYTES: usize = 90;
+const FRAME_TIME: Duration = Duration::from_millis(20);
+
+/// How the color of a flag is shown on the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Animation {
+    #[default]
+    Solid,
+    /// On and off once per second
+    Blink,
+    /// Fades in and out every two seconds
+    Pulse,
+    /// A lit block running along the strip
+    Chase,
+}
+
+impl Animation {
+    /// The animation used for a flag when the config does not specify one.
+    pub fn default_for(flag: Option<Flag>) -> Self {
+        match flag {
+            Some(Flag::Global(GlobalFlag::FormationLap)) => Animation::Pulse,
+            Some(
+                Flag::PitLimiter
+                | Flag::FastestLap
+                | Flag::Damage
+                | Flag::PitThisLap
+                | Flag::Global(GlobalFlag::ScEnding | GlobalFlag::VscEnding),
+            ) => Animation::Blink,
+            _ => Animation::Solid,
+        }
+    }
+}
+
+/// Config for [`Ws2812Output`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ws2812Config {
+    /// SPI device the strip's data line is connected to (MOSI, GPIO 10 on a Raspberry Pi)
+    #[serde(default = "default_device")]
+    pub device: String,
+    pub leds: usize,
+    /// Scales all colors, 255 is full brightness
+    #[serde(default = "default_brightness")]
+    pub brightness: u8,
+    #[serde(default)]
+    pub colors: FlagTable<Rgb>,
+    #[serde(default)]
+    pub animations: FlagTable<Animation>,
+}
+
+fn default_device() -> String {
+    String::from("/dev/spidev0.0")
+}
+
+fn default_brightness() -> u8 {
+    255
+}
+
+/// Drives a WS2812 (NeoPixel) strip directly over SPI.
+///
+/// A thread owns the SPI device and renders the animations, so a slow write never holds up the
+/// telemetry. Frames longer than the spidev buffer (4096 bytes by default, about 450 leds) need
+/// `spidev.bufsiz` raised on the kernel command line.
+#[derive(Debug)]
+pub struct Ws2812Output {
+    config: Ws2812Config,
+    /// The color of every led
+    sender: mpsc::Sender<(Vec<Rgb>, Animation)>,
+}
+
+impl Ws2812Output {
+    pub fn open(config: Ws2812Config) -> io::Result<Self> {
+        let mut spi = Spidev::open(&config.device)?;
+        spi.configure(
+            &SpidevOptions::new()
+                .bits_per_word(8)
+                .max_speed_hz(SPI_SPEED_HZ)
+                .mode(SpiModeFlags::SPI_MODE_0)
+                .build(),
+        )?;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut renderer = Renderer::new(config.leds, config.brightness);
+        std::thread::spawn(move || renderer.run(spi, receiver));
+
+        Ok(Self { config, sender })
+    }
+}
+
+#[async_trait]
+impl Output for Ws2812Output {
+    async fn show(&mut self, flag: Option<Flag>) -> io::Result<()> {
+        let color = self
+            .config
+            .colors
+            .get(flag)
+            .copied()
+            .unwrap_or_else(|| Rgb::default_for(flag));
+        let animation = self
+            .config
+            .animations
+            .get(flag)
+            .copied()
+            .unwrap_or_else(|| Animation::default_for(flag));
+        let mut lights = vec![color; self.config.leds];
+        match flag {
+            // Half of the strip stays dark, like the flag
+            Some(Flag::BlackAndWhite) => lights[self.config.leds / 2..].fill(Rgb::OFF),
+            // The strip fills up a fifth for every light
+            Some(Flag::StartLights(lit)) => {
+                lights[self.config.leds * usize::from(lit.min(5)) / 5..].fill(Rgb::OFF)
+            }
+            // The strip empties as the formation lap goes on
+            Some(Flag::Countdown(left)) => {
+                lights[self.config.leds * usize::from(left.min(100)) / 100..].fill(Rgb::OFF)
+            }
+            _ => {}
+        }
+        self.sender
+            .send((lights, animation))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "WS2812 thread stopped"))
+    }
+
+    async fn show_indicator(&mut self, indicator: Indicator) -> io::Result<()> {
+        let animation = match indicator {
+            Indicator::Rain(true) => Animation::Pulse,
+            _ if indicator.flashing() => Animation::Blink,
+            _ => Animation::Solid,
+        };
+        self.sender
+            .send((indicator.lights(self.config.leds), animation))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "WS2812 thread stopped"))
+    }
+}
+
+struct Renderer {
+    leds: usize,
+    brightness: u8,
+    buffer: Vec<u8>,
+}
+
+impl Renderer {
+    fn new(leds: usize, brightness: u8) -> Self {
+        Self {
+            leds,
+            brightness,
+            buffer: vec![0; leds * 9 + RESET_BYTES],
+        }
+    }
+
+    fn run(&mut self, mut spi: Spidev, receiver: mpsc::Receiver<(Vec<Rgb>, Animation)>) {
+        let mut current = (vec![Rgb::OFF; self.leds], Animation::Solid);
+        let mut since = Instant::now();
+        loop {
+            if let Err(e) = spi.write_all(self.render(&current.0, current.1, since.elapsed())) {
+                warn!(error = %e, "Failed to write to WS2812 strip");
+            }
+
+            let next = if current.1 == Animation::Solid {
+                receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                receiver.recv_timeout(FRAME_TIME)
+            };
+            match next {
+                Ok(update) => {
+                    current = update;
+                    since = Instant::now();
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn render(&mut self, colors: &[Rgb], animation: Animation, elapsed: Duration) -> &[u8] {
+        let seconds = elapsed.as_secs_f32();
+        let chase_start = (seconds * 30.0) as usize % self.leds.max(1);
+        let chase_length = (self.leds / 5).max(1);
+        for (led, color) in colors.iter().enumerate().take(self.leds) {
+            let level = match animation {
+                Animation::Solid => 1.0,
+                Animation::Blink if seconds.fract() < 0.5 => 1.0,
+                Animation::Blink => 0.0,
+                Animation::Pulse => 0.5 - 0.5 * (seconds * TAU / 2.0).cos(),
+                Animation::Chase if (led + self.leds - chase_start) % self.leds < chase_length => {
+                    1.0
+                }
+                Animation::Chase => 0.0,
+            };
+            let scale = level * f32::from(self.brightness) / 255.0;
+            // The strip expects green, red, blue
+            for (i, channel) in [color.g(), color.r(), color.b()].into_iter().enumerate() {
+                let value = (f32::from(channel) * scale) as u8;
+                let offset = led * 9 + i * 3;
+                self.buffer[offset..offset + 3].copy_from_slice(&encode_byte(value));
+            }
+        }
+        &self.buffer
+    }
+}
+
+/// Spreads the eight bits of a color channel over three SPI bytes.
+fn encode_byte(value: u8) -> [u8; 3] {
+    let mut bits: u32 = 0;
+    for i in (0..8).rev() {
+        bits <<= 3;
+        bits |= if value & (1 << i) != 0 { 0b110 } else { 0b100 };
+    }
+    let [_, a, b, c] = bits.to_be_bytes();
+    [a, b, c]
+}
@@ -0,0 +1,275 @@
+//! Running as a Windows service, so the sync starts with the PC and keeps running after logging off.
+//!
+//! The service runs the program with the options it was installed with, in the directory it was
+//! installed from. Stopping it turns the lights off like Ctrl+C does.
+use std::ffi::OsStr;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering;
+
+use tracing::error;
+use windows_sys::Win32::Foundation::ERROR_CALL_NOT_IMPLEMENTED;
+use windows_sys::Win32::Foundation::NO_ERROR;
+use windows_sys::Win32::System::Services::ChangeServiceConfig2W;
+use windows_sys::Win32::System::Services::CloseServiceHandle;
+use windows_sys::Win32::System::Services::ControlService;
+use windows_sys::Win32::System::Services::CreateServiceW;
+use windows_sys::Win32::System::Services::DeleteService;
+use windows_sys::Win32::System::Services::OpenSCManagerW;
+use windows_sys::Win32::System::Services::OpenServiceW;
+use windows_sys::Win32::System::Services::RegisterServiceCtrlHandlerExW;
+use windows_sys::Win32::System::Services::SC_HANDLE;
+use windows_sys::Win32::System::Services::SC_MANAGER_CONNECT;
+use windows_sys::Win32::System::Services::SC_MANAGER_CREATE_SERVICE;
+use windows_sys::Win32::System::Services::SERVICE_ACCEPT_SHUTDOWN;
+use windows_sys::Win32::System::Services::SERVICE_ACCEPT_STOP;
+use windows_sys::Win32::System::Services::SERVICE_AUTO_START;
+use windows_sys::Win32::System::Services::SERVICE_CONFIG_DESCRIPTION;
+use windows_sys::Win32::System::Services::SERVICE_CONTROL_INTERROGATE;
+use windows_sys::Win32::System::Services::SERVICE_CONTROL_SHUTDOWN;
+use windows_sys::Win32::System::Services::SERVICE_CONTROL_STOP;
+use windows_sys::Win32::System::Services::SERVICE_DESCRIPTIONW;
+use windows_sys::Win32::System::Services::SERVICE_ERROR_NORMAL;
+use windows_sys::Win32::System::Services::SERVICE_RUNNING;
+use windows_sys::Win32::System::Services::SERVICE_START;
+use windows_sys::Win32::System::Services::SERVICE_STATUS;
+use windows_sys::Win32::System::Services::SERVICE_STATUS_CURRENT_STATE;
+use windows_sys::Win32::System::Services::SERVICE_STOP;
+use windows_sys::Win32::System::Services::SERVICE_STOP_PENDING;
+use windows_sys::Win32::System::Services::SERVICE_STOPPED;
+use windows_sys::Win32::System::Services::SERVICE_TABLE_ENTRYW;
+use windows_sys::Win32::System::Services::SERVICE_WIN32_OWN_PROCESS;
+use windows_sys::Win32::System::Services::SetServiceStatus;
+use windows_sys::Win32::System::Services::StartServiceCtrlDispatcherW;
+use windows_sys::Win32::System::Services::StartServiceW;
+use windows_sys::core::PWSTR;
+
+use crate::shutdown;
+
+/// Name of the service, for `sc` and `net start`.
+pub const SERVICE_NAME: &str = "f1_light_sync";
+const DISPLAY_NAME: &str = "F1 Light Sync";
+const DESCRIPTION: &str = "Syncs the lights with the flags of the racing games.";
+/// Standard access right to delete an object, not in the service module of windows-sys
+const DELETE: u32 = 0x10000;
+
+type Program = Box<dyn FnOnce() -> io::Result<()> + Send>;
+
+/// What the service runs, taken by the service thread
+static PROGRAM: Mutex<Option<Program>> = Mutex::new(None);
+/// Handle to report the state of the service with
+static STATUS: AtomicPtr<std::ffi::c_void> = AtomicPtr::new(ptr::null_mut());
+
+/// Installs the service to start with the PC and starts it. It runs this program with `arguments`.
+pub fn install(arguments: &[String]) -> io::Result<()> {
+    let program = std::env::current_exe()?;
+    let command = std::iter::once(program.to_string_lossy().into_owned())
+        .chain(arguments.iter().cloned())
+        .map(|argument| quote(&argument))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let manager = Handle::manager(SC_MANAGER_CREATE_SERVICE)?;
+    let name = wide(SERVICE_NAME);
+    let display_name = wide(DISPLAY_NAME);
+    let command = wide(&command);
+    // SAFETY: the strings end in a nul and outlive the call, the others may be null
+    let service = Handle::new(unsafe {
+        CreateServiceW(
+            manager.0,
+            name.as_ptr(),
+            display_name.as_ptr(),
+            SERVICE_START,
+            SERVICE_WIN32_OWN_PROCESS,
+            SERVICE_AUTO_START,
+            SERVICE_ERROR_NORMAL,
+            command.as_ptr(),
+            ptr::null(),
+            ptr::null_mut(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+        )
+    })?;
+
+    let mut description = wide(DESCRIPTION);
+    let description = SERVICE_DESCRIPTIONW {
+        lpDescription: description.as_mut_ptr(),
+    };
+    // SAFETY: the description matches the info level and outlives the call
+    unsafe {
+        ChangeServiceConfig2W(
+            service.0,
+            SERVICE_CONFIG_DESCRIPTION,
+            (&raw const description).cast(),
+        );
+    }
+    // SAFETY: no arguments are passed
+    if unsafe { StartServiceW(service.0, 0, ptr::null()) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Stops the service when it runs and removes it.
+pub fn uninstall() -> io::Result<()> {
+    let manager = Handle::manager(SC_MANAGER_CONNECT)?;
+    let name = wide(SERVICE_NAME);
+    // SAFETY: the name ends in a nul and outlives the call
+    let service =
+        Handle::new(unsafe { OpenServiceW(manager.0, name.as_ptr(), SERVICE_STOP | DELETE) })?;
+    let mut status = stopped_status();
+    // SAFETY: the status is written to, failing when the service does not run
+    unsafe { ControlService(service.0, SERVICE_CONTROL_STOP, &mut status) };
+    // SAFETY: the handle has delete access
+    if unsafe { DeleteService(service.0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Runs `program` as the service, returns once it stopped. Only works when started by Windows.
+pub fn run(program: impl FnOnce() -> io::Result<()> + Send + 'static) -> io::Result<()> {
+    *PROGRAM.lock().unwrap() = Some(Box::new(program));
+    let mut name = wide(SERVICE_NAME);
+    let table = [
+        SERVICE_TABLE_ENTRYW {
+            lpServiceName: name.as_mut_ptr(),
+            lpServiceProc: Some(service_main),
+        },
+        SERVICE_TABLE_ENTRYW::default(),
+    ];
+    // SAFETY: the table ends with an empty entry and outlives the call, which blocks until the
+    // service stopped
+    if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Runs on a thread Windows starts for the service.
+unsafe extern "system" fn service_main(_: u32, _: *mut PWSTR) {
+    let name = wide(SERVICE_NAME);
+    // SAFETY: the name ends in a nul and the handler lives as long as the program
+    let status = unsafe { RegisterServiceCtrlHandlerExW(name.as_ptr(), Some(handle), ptr::null()) };
+    if status.is_null() {
+        return;
+    }
+    STATUS.store(status, Ordering::Relaxed);
+    report(SERVICE_RUNNING, 0);
+
+    let program = PROGRAM.lock().unwrap().take();
+    let exit_code = match program.map(|program| program()) {
+        Some(Err(e)) => {
+            error!(error = %e, "The service failed");
+            1
+        }
+        _ => 0,
+    };
+    report(SERVICE_STOPPED, exit_code);
+}
+
+/// Takes the requests of Windows to the service.
+unsafe extern "system" fn handle(
+    control: u32,
+    _: u32,
+    _: *mut std::ffi::c_void,
+    _: *mut std::ffi::c_void,
+) -> u32 {
+    match control {
+        SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+            report(SERVICE_STOP_PENDING, 0);
+            shutdown::request();
+            NO_ERROR
+        }
+        SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+        _ => ERROR_CALL_NOT_IMPLEMENTED,
+    }
+}
+
+fn report(state: SERVICE_STATUS_CURRENT_STATE, exit_code: u32) {
+    let status = SERVICE_STATUS {
+        dwCurrentState: state,
+        dwControlsAccepted: match state {
+            SERVICE_RUNNING => SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN,
+            _ => 0,
+        },
+        dwWin32ExitCode: exit_code,
+        ..stopped_status()
+    };
+    // SAFETY: the handle stays valid until the service stopped
+    unsafe { SetServiceStatus(STATUS.load(Ordering::Relaxed), &status) };
+}
+
+fn stopped_status() -> SERVICE_STATUS {
+    SERVICE_STATUS {
+        dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+        dwCurrentState: SERVICE_STOPPED,
+        dwControlsAccepted: 0,
+        dwWin32ExitCode: 0,
+        dwServiceSpecificExitCode: 0,
+        dwCheckPoint: 0,
+        dwWaitHint: 0,
+    }
+}
+
+/// A handle to the service manager or a service, closed when dropped.
+struct Handle(SC_HANDLE);
+
+impl Handle {
+    fn new(handle: SC_HANDLE) -> io::Result<Self> {
+        match handle.is_null() {
+            true => Err(io::Error::last_os_error()),
+            false => Ok(Self(handle)),
+        }
+    }
+
+    fn manager(access: u32) -> io::Result<Self> {
+        // SAFETY: null opens the services of this PC
+        Self::new(unsafe { OpenSCManagerW(ptr::null(), ptr::null(), access) })
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        // SAFETY: the handle is open and not used after this
+        unsafe { CloseServiceHandle(self.0) };
+    }
+}
+
+/// Nul terminated UTF-16.
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain([0]).collect()
+}
+
+/// Quotes an argument for the command line of the service, the way programs split it again.
+fn quote(argument: &str) -> String {
+    if !argument.is_empty() && !argument.contains([' ', '\t', '"']) {
+        return argument.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in argument.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escaped, and the quote itself
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+    // The closing quote is not escaped
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
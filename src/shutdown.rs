@@ -0,0 +1,140 @@
+//! Noticing that the program is asked to stop, by Ctrl+C or `SIGTERM`, so the lights can be
+//! turned off first.
+//!
+//! Asking a second time ends the program right away.
+use std::io;
+
+use tokio::sync::watch;
+
+/// Resolves once the program is asked to stop, every clone sees it.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    requested: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Takes over the handling of Ctrl+C and `SIGTERM` for the rest of the program, needs a Tokio
+    /// runtime.
+    pub fn install() -> io::Result<Self> {
+        let (sender, requested) = watch::channel(false);
+        imp::install(sender)?;
+        Ok(Self { requested })
+    }
+
+    /// Waits until the program is asked to stop.
+    pub async fn requested(&mut self) {
+        // The sender lives as long as the program, so only the request ends the wait
+        if self
+            .requested
+            .wait_for(|&requested| requested)
+            .await
+            .is_err()
+        {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Asks the program to stop like Ctrl+C does, for the Windows service and the status view.
+pub(crate) fn request() {
+    imp::request();
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::os::fd::IntoRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::AtomicI32;
+    use std::sync::atomic::Ordering;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::sync::watch;
+
+    /// Write end of the pipe that wakes the runtime up from the signal handler
+    static WAKE: AtomicI32 = AtomicI32::new(-1);
+
+    extern "C" fn handle(signal: libc::c_int) {
+        // SAFETY: both calls are async-signal-safe, the next signal gets the default handling
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::write(WAKE.load(Ordering::Relaxed), [0u8].as_ptr().cast(), 1);
+        }
+    }
+
+    pub(super) fn request() {
+        // SAFETY: writing to the pipe only fails before it was installed
+        unsafe { libc::write(WAKE.load(Ordering::Relaxed), [0u8].as_ptr().cast(), 1) };
+    }
+
+    pub(super) fn install(sender: watch::Sender<bool>) -> io::Result<()> {
+        let (read, write) = UnixStream::pair()?;
+        read.set_nonblocking(true)?;
+        write.set_nonblocking(true)?;
+        WAKE.store(write.into_raw_fd(), Ordering::Relaxed);
+        let handler: extern "C" fn(libc::c_int) = handle;
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            // SAFETY: the handler only makes async-signal-safe calls
+            let previous = unsafe { libc::signal(signal, handler as libc::sighandler_t) };
+            if previous == libc::SIG_ERR {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        let mut read = tokio::net::UnixStream::from_std(read)?;
+        tokio::spawn(async move {
+            let mut byte = [0];
+            if read.read(&mut byte).await.is_ok_and(|length| length > 0) {
+                let _ = sender.send(true);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io;
+    use std::sync::OnceLock;
+
+    use tokio::sync::watch;
+    use windows_sys::Win32::System::Console::CTRL_BREAK_EVENT;
+    use windows_sys::Win32::System::Console::CTRL_C_EVENT;
+    use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+    use windows_sys::core::BOOL;
+
+    static REQUESTED: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+    /// Runs on a thread of its own, returning false leaves the event to the default handler.
+    unsafe extern "system" fn handle(event: u32) -> BOOL {
+        if !matches!(event, CTRL_C_EVENT | CTRL_BREAK_EVENT) {
+            return 0;
+        }
+        match REQUESTED.get() {
+            // Asked before, stop right away
+            Some(requested) if *requested.borrow() => 0,
+            Some(requested) => {
+                requested.send_replace(true);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub(super) fn request() {
+        if let Some(requested) = REQUESTED.get() {
+            requested.send_replace(true);
+        }
+    }
+
+    pub(super) fn install(sender: watch::Sender<bool>) -> io::Result<()> {
+        REQUESTED
+            .set(sender)
+            .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "already installed"))?;
+        // SAFETY: the handler is a function that lives as long as the program
+        if unsafe { SetConsoleCtrlHandler(Some(handle), 1) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
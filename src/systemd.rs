@@ -0,0 +1,89 @@
+//! Running under systemd: telling it when the program is ready, pinging its watchdog and taking
+//! the sockets it opened for the unit.
+//!
+//! Everything does nothing when the program was not started by systemd.
+use std::env;
+use std::io;
+use std::net::UdpSocket;
+use std::os::fd::FromRawFd;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::SocketAddr;
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+use tracing::warn;
+
+/// The first file descriptor systemd passes
+const LISTEN_FDS_START: i32 = 3;
+
+/// Sends `state` to systemd, like `READY=1`.
+pub fn notify(state: &str) -> io::Result<()> {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let path = path.to_string_lossy();
+    let address = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None => SocketAddr::from_pathname(path.as_ref())?,
+    };
+    UnixDatagram::unbound()?.send_to_addr(state.as_bytes(), &address)?;
+    Ok(())
+}
+
+/// Tells systemd the program is up, for units with `Type=notify`, and keeps pinging the watchdog
+/// when the unit has `WatchdogSec`. Needs a Tokio runtime.
+pub fn ready() {
+    if let Err(e) = notify("READY=1") {
+        warn!(error = %e, "Failed to notify systemd");
+    }
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    tokio::spawn(async move {
+        // Twice as often as needed, so a late ping is not missed
+        let mut ticks = tokio::time::interval(interval / 2);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = notify("WATCHDOG=1") {
+                warn!(error = %e, "Failed to ping the systemd watchdog");
+            }
+        }
+    });
+}
+
+/// How often systemd wants to hear from the watchdog, `None` without one.
+fn watchdog_interval() -> Option<Duration> {
+    if !for_this_process("WATCHDOG_PID") {
+        return None;
+    }
+    let micros = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(micros)).filter(|interval| !interval.is_zero())
+}
+
+/// The UDP sockets systemd opened for the unit with socket activation, in the order of the
+/// `ListenDatagram` lines. Only call it once, the sockets are owned by what it returns.
+pub fn take_sockets() -> Vec<UdpSocket> {
+    if !for_this_process("LISTEN_PID") {
+        return Vec::new();
+    }
+    let count: i32 = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+    (LISTEN_FDS_START..LISTEN_FDS_START + count)
+        .map(|fd| {
+            // SAFETY: systemd passes this many open sockets from the first descriptor on, and
+            // nothing else owns them
+            unsafe {
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                UdpSocket::from_raw_fd(fd)
+            }
+        })
+        .collect()
+}
+
+/// Whether the variable `name` holds the id of this process, systemd sets it along with the
+/// others so child processes do not take them for their own.
+fn for_this_process(name: &str) -> bool {
+    env::var(name).is_ok_and(|pid| pid.parse() == Ok(std::process::id()))
+}
@@ -0,0 +1,354 @@
+//! A live status view in the terminal, for seeing what the manager does without a browser.
+//!
+//! It takes over the terminal until dropped, so the log should go to a file meanwhile. Keys force
+//! a flag over the game for when it misses an incident: `y` yellow, `s` SC, `v` VSC, `r` red and
+//! `f` the chequered flag, until `c` clears it. During a replay space pauses and `n` steps to the
+//! next event. Ctrl+C asks the program to stop like it does without the view, a second time ends
+//! it right away.
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::style::Stylize as _;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+
+use crate::color::Rgb;
+use crate::event::FlagEvent;
+use crate::flag::Flag;
+use crate::flag::GlobalFlag;
+use crate::flag::LocalFlag;
+use crate::game::PacketStatistics;
+use crate::http::ControlCommand;
+use crate::input::ReplayCommand;
+use crate::manager::FlagManager;
+use crate::shutdown;
+
+/// How often the view is drawn
+pub const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+const EVENT_HISTORY: usize = 10;
+const DRIVERS_PER_ROW: usize = 6;
+
+/// Draws the state of a [`FlagManager`] to the terminal, with the last events.
+#[derive(Debug)]
+pub struct StatusView {
+    output_names: Vec<String>,
+    events: VecDeque<(Instant, String)>,
+    packets: Option<watch::Receiver<PacketStatistics>>,
+    next_draw: Instant,
+    keys: mpsc::UnboundedReceiver<KeyCode>,
+    replay: Option<mpsc::UnboundedSender<ReplayCommand>>,
+    /// Put back the way it was when dropped
+    terminal: DefaultTerminal,
+}
+
+impl StatusView {
+    /// Switches to a screen of its own and starts reading keys, the flag outputs are listed with
+    /// `output_names`.
+    pub fn new(output_names: Vec<String>) -> io::Result<Self> {
+        let terminal = ratatui::try_init()?;
+        let (sender, keys) = mpsc::unbounded_channel();
+        // Reading the keys blocks, the thread goes with the program
+        std::thread::spawn(move || read_keys(sender));
+        Ok(Self {
+            output_names,
+            events: VecDeque::with_capacity(EVENT_HISTORY),
+            packets: None,
+            next_draw: Instant::now(),
+            keys,
+            replay: None,
+            terminal,
+        })
+    }
+
+    /// The command of the next key pressed, waits forever once the keys cannot be read.
+    pub async fn command(&mut self) -> ControlCommand {
+        while let Some(key) = self.keys.recv().await {
+            if let Some(replay) = &self.replay
+                && let Some(command) = replay_command(key)
+            {
+                // Fails once the replay ended, the keys do nothing then
+                let _ = replay.send(command);
+            } else if let Some(command) = key_command(key) {
+                return command;
+            }
+        }
+        std::future::pending().await
+    }
+
+    /// Sends the keys that control a replay to it.
+    pub fn with_replay_commands(mut self, replay: mpsc::UnboundedSender<ReplayCommand>) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+
+    /// Lists the flag outputs with `output_names` from now on, for a manager with other outputs.
+    pub fn set_output_names(&mut self, output_names: Vec<String>) {
+        self.output_names = output_names;
+    }
+
+    /// Also shows the packet rates and losses of the F1 telemetry.
+    pub fn with_packet_statistics(mut self, packets: watch::Receiver<PacketStatistics>) -> Self {
+        self.packets = Some(packets);
+        self
+    }
+
+    /// Adds an event to the ones listed, indicators are left out.
+    pub fn event(&mut self, event: &FlagEvent) {
+        let description = match event {
+            FlagEvent::Indicator(_) => return,
+            FlagEvent::Participants { .. } => String::from("Participants"),
+            event => format!("{event:?}"),
+        };
+        if self.events.len() == EVENT_HISTORY {
+            self.events.pop_front();
+        }
+        self.events.push_back((Instant::now(), description));
+    }
+
+    /// When the view should be drawn again.
+    pub fn next_draw(&self) -> Instant {
+        self.next_draw
+    }
+
+    /// Draws the state of `manager`.
+    pub fn draw(&mut self, manager: &FlagManager) -> io::Result<()> {
+        let now = Instant::now();
+        self.next_draw = now + REFRESH_INTERVAL;
+
+        let mut keys = String::from("y yellow  s SC  v VSC  r red  f finish  c clear");
+        if self.replay.is_some() {
+            keys.push_str("  space pause  n step");
+        }
+        let header = vec![Line::from("f1_light_sync".bold()), Line::from(keys.dim())];
+        let status = self.status(manager, now);
+        let outputs: Vec<Line> = self
+            .output_names
+            .iter()
+            .zip(manager.output_errors())
+            .map(|(name, error)| match error {
+                Some(error) => Line::from(vec![
+                    Span::raw(format!("{name}: ")),
+                    "failing".red(),
+                    Span::raw(format!(" {error}")),
+                ]),
+                None => Line::from(vec![Span::raw(format!("{name}: ")), "ok".green()]),
+            })
+            .collect();
+        let drivers: Vec<String> = manager
+            .driver_numbers()
+            .iter()
+            .zip(manager.driver_abbreviations())
+            .filter(|(number, _)| **number != 0)
+            .map(|(number, driver)| match driver {
+                Some(driver) => format!("#{number} {driver}"),
+                None => format!("#{number}"),
+            })
+            .collect();
+        let drivers: Vec<Line> = drivers
+            .chunks(DRIVERS_PER_ROW)
+            .map(|row| Line::from(row.join("  ")))
+            .collect();
+        let events: Vec<Line> = self
+            .events
+            .iter()
+            .rev()
+            .map(|(time, event)| {
+                Line::from(format!(
+                    "{:>6.1}s ago  {event}",
+                    now.duration_since(*time).as_secs_f64()
+                ))
+            })
+            .collect();
+
+        self.terminal.draw(|frame| {
+            render(frame, [header, status, outputs, drivers, events]);
+        })?;
+        Ok(())
+    }
+
+    /// The flags of `manager` and the packets, a line each.
+    fn status(&self, manager: &FlagManager, now: Instant) -> Vec<Line<'static>> {
+        let state = manager.state();
+        let name = |flag: Option<Flag>| flag.map_or("-", Flag::name);
+        let Rgb([r, g, b]) = Rgb::default_for(state.shown);
+
+        let mut status = vec![
+            Line::from(vec![
+                Span::raw("Shown     "),
+                Span::styled("      ", Style::new().bg(Color::Rgb(r, g, b))),
+                Span::raw(format!(" {}", state.shown.map_or("off", Flag::name))),
+            ]),
+            Line::from(format!(
+                "Global    {}",
+                name(state.global_flag.map(Flag::from))
+            )),
+            Line::from(format!(
+                "Local     {}",
+                name(state.local_flag.map(Flag::from))
+            )),
+            Line::from(format!(
+                "Penalty   {}",
+                match manager.penalty() {
+                    None => String::from("-"),
+                    Some((flag, None)) => format!("{} until served", flag.name()),
+                    Some((flag, Some(until))) => format!(
+                        "{} for {:.1}s",
+                        flag.name(),
+                        until.saturating_duration_since(now).as_secs_f64()
+                    ),
+                }
+            )),
+            Line::from(format!(
+                "Forced    {}",
+                match manager.forced() {
+                    None => String::from("-"),
+                    Some((flag, None)) => format!("{} until cleared", name(flag)),
+                    Some((flag, Some(until))) => format!(
+                        "{} for {:.0}s",
+                        name(flag),
+                        until.saturating_duration_since(now).as_secs_f64()
+                    ),
+                }
+            )),
+            Line::from(format!(
+                "Finished  {}",
+                match state.race_finished {
+                    true => "yes",
+                    false => "no",
+                }
+            )),
+        ];
+        if let Some(packets) = &self.packets {
+            let packets = packets.borrow();
+            let mut rates = String::new();
+            for (id, rate) in &packets.rates {
+                let _ = write!(rates, " {id}:{rate:.0}");
+            }
+            status.push(Line::from(match packets.rates.is_empty() {
+                true => String::from("Packets   -"),
+                false => format!(
+                    "Packets   {} lost, {} late, {} unparseable, per second by id{rates}",
+                    packets
+                        .loss
+                        .map_or_else(|| String::from("-"), |loss| format!("{:.1}%", loss * 100.0)),
+                    packets.late,
+                    packets.failures.values().sum::<u64>()
+                ),
+            }));
+        }
+        status
+    }
+}
+
+/// Lays out the header and the status, outputs, drivers and events under each other, the events
+/// get what is left of the screen.
+fn render(frame: &mut Frame, [header, status, outputs, drivers, events]: [Vec<Line>; 5]) {
+    // The lines and the borders of the blocks
+    let height = |lines: &[Line]| Constraint::Length(lines.len() as u16 + 2);
+    let [
+        header_area,
+        status_area,
+        outputs_area,
+        drivers_area,
+        events_area,
+    ] = Layout::vertical([
+        Constraint::Length(header.len() as u16),
+        height(&status),
+        height(&outputs),
+        height(&drivers),
+        Constraint::Min(0),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(Paragraph::new(header), header_area);
+    let block = |title: &'static str| Block::bordered().title(title.bold());
+    frame.render_widget(Paragraph::new(status).block(block("Status")), status_area);
+    frame.render_widget(
+        Paragraph::new(outputs).block(block("Outputs")),
+        outputs_area,
+    );
+    frame.render_widget(
+        Paragraph::new(drivers).block(block("Drivers")),
+        drivers_area,
+    );
+    frame.render_widget(Paragraph::new(events).block(block("Events")), events_area);
+}
+
+/// Reads the keys until they cannot be read, sending them to `keys`. The terminal takes Ctrl+C as
+/// a key while the view is shown, so the first asks the program to stop and the second ends it
+/// right away.
+fn read_keys(keys: mpsc::UnboundedSender<KeyCode>) {
+    let mut stopping = false;
+    while let Ok(event) = crossterm::event::read() {
+        let Event::Key(key) = event else {
+            continue;
+        };
+        // Windows also reports the keys let go
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if key.code == KeyCode::Char('c') {
+                if stopping {
+                    ratatui::restore();
+                    std::process::exit(130);
+                }
+                stopping = true;
+                shutdown::request();
+            }
+        } else if keys.send(key.code).is_err() {
+            return;
+        }
+    }
+}
+
+/// What a key asks for.
+fn key_command(key: KeyCode) -> Option<ControlCommand> {
+    let KeyCode::Char(key) = key else {
+        return None;
+    };
+    let flag = match key.to_ascii_lowercase() {
+        'y' => Flag::Local(LocalFlag::Yellow),
+        's' => Flag::Global(GlobalFlag::Sc),
+        'v' => Flag::Global(GlobalFlag::Vsc),
+        'r' => Flag::Global(GlobalFlag::Red),
+        'f' => Flag::Finish,
+        'c' => return Some(ControlCommand::ClearForced),
+        _ => return None,
+    };
+    Some(ControlCommand::Force {
+        flag: Some(flag),
+        ttl: None,
+    })
+}
+
+fn replay_command(key: KeyCode) -> Option<ReplayCommand> {
+    match key {
+        KeyCode::Char(' ') => Some(ReplayCommand::TogglePause),
+        KeyCode::Char('n' | 'N') => Some(ReplayCommand::Step),
+        _ => None,
+    }
+}
+
+impl Drop for StatusView {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
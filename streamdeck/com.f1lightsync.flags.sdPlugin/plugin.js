@@ -0,0 +1,86 @@
+// Shows the flag of f1_light_sync on every key and forces the flag of a key when it is pressed,
+// through the dashboard server. The server needs `allow_origin = "*"` in its [http] table.
+
+// Where the dashboard is served, the `listen` address of [http]
+const SERVER = "http://localhost:8080";
+
+// The flag every action forces, `null` for the one that clears it
+const FLAGS = {
+  "com.f1lightsync.flags.yellow": "yellow",
+  "com.f1lightsync.flags.sc": "sc",
+  "com.f1lightsync.flags.vsc": "vsc",
+  "com.f1lightsync.flags.red": "red",
+  "com.f1lightsync.flags.finish": "finish",
+  "com.f1lightsync.flags.clear": null,
+};
+
+let websocket = null;
+// The action of every key shown, by context
+const keys = new Map();
+// The last status of the server, `null` while it cannot be reached
+let status = null;
+
+// Called by the Stream Deck software once the plugin is loaded
+function connectElgatoStreamDeckSocket(port, uuid, registerEvent) {
+  websocket = new WebSocket(`ws://127.0.0.1:${port}`);
+  websocket.onopen = () => websocket.send(JSON.stringify({ event: registerEvent, uuid }));
+  websocket.onmessage = message => {
+    const { event, action, context } = JSON.parse(message.data);
+    if (event === "willAppear") {
+      keys.set(context, action);
+      draw(context);
+    } else if (event === "willDisappear") {
+      keys.delete(context);
+    } else if (event === "keyDown") {
+      press(action, context);
+    }
+  };
+
+  // Reconnects by itself when the server goes away
+  const events = new EventSource(`${SERVER}/events`);
+  events.onmessage = message => {
+    status = JSON.parse(message.data);
+    keys.forEach((_, context) => draw(context));
+  };
+  events.onerror = () => {
+    status = null;
+    keys.forEach((_, context) => draw(context));
+  };
+}
+
+function send(event, context, payload) {
+  websocket.send(JSON.stringify({ event, context, payload }));
+}
+
+// Fills the key with the color the lights show, titled with its flag
+function draw(context) {
+  const canvas = document.createElement("canvas");
+  canvas.width = canvas.height = 144;
+  const graphics = canvas.getContext("2d");
+  graphics.fillStyle = status?.color ?? "#202020";
+  graphics.fillRect(0, 0, canvas.width, canvas.height);
+  send("setImage", context, { image: canvas.toDataURL(), target: 0 });
+
+  const flag = FLAGS[keys.get(context)];
+  const forced = flag !== null && status?.forced?.flag === flag;
+  const title = status === null ? "offline" : `${flag ?? "clear"}${forced ? "\n(forced)" : ""}`;
+  send("setTitle", context, { title, target: 0 });
+}
+
+// Pressing the flag that is forced already gives the lights back to the game
+async function press(action, context) {
+  const flag = FLAGS[action];
+  const clear = flag === null || status?.forced?.flag === flag;
+  const request = clear
+    ? { method: "DELETE" }
+    : { method: "POST", body: JSON.stringify({ flag }) };
+  try {
+    const response = await fetch(`${SERVER}/flag`, request);
+    if (!response.ok) {
+      throw new Error(await response.text());
+    }
+    send("showOk", context);
+  } catch {
+    send("showAlert", context);
+  }
+}
@@ -0,0 +1,171 @@
+//! Synthetic F1 25 packets and an in-process pipeline from a game socket to a light socket.
+// Every test uses only some of it
+#![allow(dead_code)]
+use std::time::Duration;
+use std::time::Instant;
+
+use f1_light_sync::FlagManager;
+use f1_light_sync::GameYear;
+use f1_light_sync::PacketParser;
+use f1_light_sync::input::F1Input;
+use f1_light_sync::input::InputSource;
+use f1_light_sync::output::UdpOutput;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+const CARS: usize = 22;
+const EVENT_PACKET: u8 = 3;
+const PARTICIPANTS_PACKET: u8 = 4;
+const CAR_STATUS_PACKET: u8 = 7;
+const CAR_STATUS_SIZE: usize = 55;
+const FIA_FLAG_OFFSET: usize = 28;
+const PARTICIPANT_SIZE: usize = 57;
+const RACE_NUMBER_OFFSET: usize = 5;
+const NAME_OFFSET: usize = 7;
+const EVENT_DETAILS_SIZE: usize = 12;
+
+/// How long to wait for the lights to get something
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Builds the packets of one session, each with the next frame number.
+#[derive(Debug, Default)]
+pub struct Packets {
+    frame: u32,
+    /// The index of the player's car
+    pub player: u8,
+    /// The index of the second player's car in split-screen, `None` when playing alone
+    pub second_player: Option<u8>,
+    /// Seconds since the start of the session, in the header
+    pub session_time: f32,
+}
+
+impl Packets {
+    fn header(&mut self, packet_id: u8) -> Vec<u8> {
+        self.frame += 1;
+        let mut header = Vec::with_capacity(29);
+        header.extend_from_slice(&2025u16.to_le_bytes());
+        // Game year, major and minor version, packet version
+        header.extend_from_slice(&[25, 1, 0, 1, packet_id]);
+        header.extend_from_slice(&1u64.to_le_bytes());
+        header.extend_from_slice(&self.session_time.to_le_bytes());
+        header.extend_from_slice(&self.frame.to_le_bytes());
+        header.extend_from_slice(&self.frame.to_le_bytes());
+        // 255 without a second player
+        header.extend_from_slice(&[self.player, self.second_player.unwrap_or(255)]);
+        header
+    }
+
+    /// An event with its four letter code and details, padded to the size of the largest details.
+    pub fn event(&mut self, code: &[u8; 4], details: &[u8]) -> Vec<u8> {
+        let mut packet = self.header(EVENT_PACKET);
+        packet.extend_from_slice(code);
+        packet.extend_from_slice(details);
+        packet.resize(packet.len() + EVENT_DETAILS_SIZE - details.len(), 0);
+        packet
+    }
+
+    /// The car status of every car with the FIA flag `flag`, -1 for unknown up to 4 for red.
+    pub fn car_status(&mut self, flag: i8) -> Vec<u8> {
+        let mut car = [0; CAR_STATUS_SIZE];
+        car[FIA_FLAG_OFFSET] = flag as u8;
+        let mut packet = self.header(CAR_STATUS_PACKET);
+        for _ in 0..CARS {
+            packet.extend_from_slice(&car);
+        }
+        packet
+    }
+
+    /// The participants with the race numbers and names, in the order of the car indices.
+    pub fn participants(&mut self, drivers: &[(u8, &str)]) -> Vec<u8> {
+        let mut packet = self.header(PARTICIPANTS_PACKET);
+        packet.push(drivers.len() as u8);
+        for i in 0..CARS {
+            let mut participant = [0; PARTICIPANT_SIZE];
+            if let Some((number, name)) = drivers.get(i) {
+                participant[RACE_NUMBER_OFFSET] = *number;
+                participant[NAME_OFFSET..NAME_OFFSET + name.len()].copy_from_slice(name.as_bytes());
+            }
+            packet.extend_from_slice(&participant);
+        }
+        packet
+    }
+}
+
+/// A [`FlagManager`] with a UDP output, fed by an [`F1Input`], all on loopback sockets.
+pub struct Pipeline {
+    game: UdpSocket,
+    lights: UdpSocket,
+    task: JoinHandle<()>,
+}
+
+impl Pipeline {
+    pub async fn start() -> Self {
+        let lights = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let output = UdpOutput::connect(&lights.local_addr().unwrap().to_string())
+            .await
+            .unwrap();
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let input_address = socket.local_addr().unwrap();
+        let mut input = F1Input::from_std(socket, PacketParser::new(GameYear::F1_25)).unwrap();
+        let mut manager = FlagManager::new(vec![Box::new(output)]);
+        let task = tokio::spawn(async move {
+            loop {
+                let deadline = manager.deadline();
+                tokio::select! {
+                    event = input.next_event() => match event {
+                        Ok(Some(event)) => manager.handle(event).await,
+                        Ok(None) => return,
+                        Err(e) => panic!("the input failed: {e}"),
+                    },
+                    () = sleep_until(deadline) => manager.tick().await,
+                }
+            }
+        });
+
+        let game = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        game.connect(input_address).await.unwrap();
+        Self { game, lights, task }
+    }
+
+    /// Sends a packet like the game does.
+    pub async fn send(&self, packet: &[u8]) {
+        self.game.send(packet).await.unwrap();
+    }
+
+    /// The next message the lights get, fails the test when nothing comes.
+    pub async fn receive(&self) -> String {
+        let mut buf = [0; 1024];
+        let length = timeout(RECEIVE_TIMEOUT, self.lights.recv(&mut buf))
+            .await
+            .expect("the lights should get a message")
+            .unwrap();
+        String::from_utf8(buf[..length].to_vec()).unwrap()
+    }
+
+    /// Fails the test when the lights get something within `wait`.
+    pub async fn assert_quiet(&self, wait: Duration) {
+        let mut buf = [0; 1024];
+        if let Ok(received) = timeout(wait, self.lights.recv(&mut buf)).await {
+            let length = received.unwrap();
+            panic!(
+                "the lights should get nothing, got {:?}",
+                String::from_utf8_lossy(&buf[..length])
+            );
+        }
+    }
+}
+
+/// Waits for `deadline`, forever when there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,143 @@
+//! Captures of synthetic F1 25 packets played back, with seeking and the replay controls.
+mod common;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use common::Packets;
+use f1_light_sync::FlagEvent;
+use f1_light_sync::GameYear;
+use f1_light_sync::GlobalFlag;
+use f1_light_sync::LocalFlag;
+use f1_light_sync::PacketParser;
+use f1_light_sync::capture::CaptureReader;
+use f1_light_sync::capture::MAGIC;
+use f1_light_sync::input::InputSource;
+use f1_light_sync::input::ReplayCommand;
+use f1_light_sync::input::ReplayInput;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+const YELLOW: i8 = 3;
+const NO_FLAG: i8 = 0;
+
+/// A capture file with the packets at their time since the start of the recording.
+struct Capture(PathBuf);
+
+impl Capture {
+    fn write(name: &str, packets: &[(Duration, Vec<u8>)]) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "f1_light_sync_replay_{name}_{}.f1cap",
+            std::process::id()
+        ));
+        let mut data = MAGIC.to_vec();
+        for (timestamp, packet) in packets {
+            data.extend_from_slice(&(timestamp.as_micros() as u64).to_le_bytes());
+            data.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            data.extend_from_slice(packet);
+        }
+        std::fs::write(&path, data).unwrap();
+        Self(path)
+    }
+
+    fn replay(&self) -> ReplayInput {
+        let capture = CaptureReader::open(&self.0).unwrap();
+        ReplayInput::new(capture, PacketParser::new(GameYear::F1_25), 1.0).unwrap()
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A yellow at 10 seconds, a safety car at 20 and the yellow gone at 30, recorded from the yellow
+/// on and a second apart.
+fn safety_car_capture(name: &str) -> Capture {
+    let mut packets = Packets::default();
+    let mut at = |session_time: f32, packet: fn(&mut Packets) -> Vec<u8>| {
+        packets.session_time = session_time;
+        let timestamp = Duration::from_secs_f32((session_time - 10.0) / 10.0);
+        (timestamp, packet(&mut packets))
+    };
+    Capture::write(
+        name,
+        &[
+            at(10.0, |packets| packets.car_status(YELLOW)),
+            at(20.0, |packets| packets.event(b"SCAR", &[1, 0])),
+            at(30.0, |packets| packets.car_status(NO_FLAG)),
+        ],
+    )
+}
+
+/// The next event other than the indicators, `None` when none comes within `wait`.
+async fn next(replay: &mut ReplayInput, wait: Duration) -> Option<FlagEvent> {
+    let next = async {
+        loop {
+            match replay.next_event().await.unwrap() {
+                Some(FlagEvent::Indicator(_)) => continue,
+                event => return event.expect("the replay should not end"),
+            }
+        }
+    };
+    timeout(wait, next).await.ok()
+}
+
+#[tokio::test]
+async fn start_at_brings_the_flags_out_at_that_time() {
+    let capture = safety_car_capture("start_at");
+    let mut replay = capture.replay().with_start_at(25.0);
+
+    let wait = Duration::from_millis(200);
+    // The packet at 30 seconds plays right away, after the state before it
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::PitLimiter(false))
+    );
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::SetGlobalFlag(GlobalFlag::Sc))
+    );
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+    );
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::ResetLocalFlag)
+    );
+}
+
+#[tokio::test]
+async fn paused_replays_step_to_the_next_event() {
+    let capture = safety_car_capture("step");
+    let (commands, receiver) = mpsc::unbounded_channel();
+    let mut replay = capture.replay().with_commands(receiver);
+
+    let wait = Duration::from_millis(200);
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::SetLocalFlag(LocalFlag::Yellow))
+    );
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::PitLimiter(false))
+    );
+    commands.send(ReplayCommand::TogglePause).unwrap();
+    // The safety car is due a second in, long after the wait
+    assert_eq!(next(&mut replay, Duration::from_millis(1500)).await, None);
+
+    commands.send(ReplayCommand::Step).unwrap();
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::SetGlobalFlag(GlobalFlag::Sc))
+    );
+    // Paused again after the step
+    assert_eq!(next(&mut replay, Duration::from_millis(1500)).await, None);
+    commands.send(ReplayCommand::TogglePause).unwrap();
+    assert_eq!(
+        next(&mut replay, wait).await,
+        Some(FlagEvent::ResetLocalFlag)
+    );
+}
@@ -0,0 +1,22 @@
+//! The parser of the second player in split-screen, from synthetic F1 25 packets.
+mod common;
+
+use common::Packets;
+use f1_light_sync::FlagEvent;
+use f1_light_sync::GameYear;
+use f1_light_sync::LocalFlag;
+use f1_light_sync::PacketParser;
+
+const YELLOW: i8 = 3;
+
+#[test]
+fn packets_without_a_second_player_are_skipped() {
+    let mut parser = PacketParser::new(GameYear::F1_25).with_second_player();
+    let mut packets = Packets::default();
+
+    assert_eq!(parser.parse(&packets.car_status(YELLOW)).unwrap(), []);
+
+    packets.second_player = Some(1);
+    let events = parser.parse(&packets.car_status(YELLOW)).unwrap();
+    assert_eq!(events[0], FlagEvent::SetLocalFlag(LocalFlag::Yellow));
+}
@@ -0,0 +1,74 @@
+//! Synthetic F1 25 packets through the F1 input, the manager and the UDP output.
+mod common;
+
+use std::time::Duration;
+
+use common::Packets;
+use common::Pipeline;
+
+const YELLOW: i8 = 3;
+const NO_FLAG: i8 = 0;
+const DRIVE_THROUGH: u8 = 0;
+
+#[tokio::test]
+async fn car_status_flags_reach_the_lights() {
+    let pipeline = Pipeline::start().await;
+    let mut packets = Packets::default();
+
+    pipeline.send(&packets.car_status(YELLOW)).await;
+    assert_eq!(pipeline.receive().await, "2");
+    pipeline.send(&packets.car_status(YELLOW)).await;
+    pipeline.send(&packets.car_status(NO_FLAG)).await;
+    // Only changes are sent, the yellow ending flashes green
+    assert_eq!(pipeline.receive().await, "1");
+}
+
+#[tokio::test]
+async fn safety_car_goes_over_yellow() {
+    let pipeline = Pipeline::start().await;
+    let mut packets = Packets::default();
+
+    pipeline.send(&packets.car_status(YELLOW)).await;
+    assert_eq!(pipeline.receive().await, "2");
+    // A safety car deployed
+    pipeline.send(&packets.event(b"SCAR", &[1, 0])).await;
+    assert_eq!(pipeline.receive().await, "4");
+    // Returning to the pits
+    pipeline.send(&packets.event(b"SCAR", &[1, 1])).await;
+    assert_eq!(pipeline.receive().await, "13");
+}
+
+#[tokio::test]
+async fn penalties_carry_the_race_number() {
+    let pipeline = Pipeline::start().await;
+    let mut packets = Packets::default();
+
+    let participants = packets.participants(&[(1, "Max VERSTAPPEN"), (44, "Lewis HAMILTON")]);
+    pipeline.send(&participants).await;
+    // Penalty type, infringement, vehicle index, other vehicle index, time, lap, places
+    let penalty = [DRIVE_THROUGH, 0, 1, 255, 0, 1, 0];
+    pipeline.send(&packets.event(b"PENA", &penalty)).await;
+    assert_eq!(pipeline.receive().await, "17,44");
+}
+
+#[tokio::test]
+async fn session_start_turns_the_lights_off() {
+    let pipeline = Pipeline::start().await;
+    let mut packets = Packets::default();
+
+    pipeline.send(&packets.event(b"CHQF", &[])).await;
+    assert_eq!(pipeline.receive().await, "16");
+    pipeline.send(&packets.event(b"SSTA", &[])).await;
+    assert_eq!(pipeline.receive().await, "c");
+}
+
+#[tokio::test]
+async fn unknown_packets_send_nothing() {
+    let pipeline = Pipeline::start().await;
+    let mut packets = Packets::default();
+
+    pipeline.send(&[0; 10]).await;
+    // Speed trap events are not flags
+    pipeline.send(&packets.event(b"SPTP", &[0; 12])).await;
+    pipeline.assert_quiet(Duration::from_millis(200)).await;
+}